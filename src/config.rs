@@ -14,7 +14,8 @@
 
 //! load, save configuration and parse cli args
 use crate::{
-    config_picker::ConfigPicker, dialog, globals, result_ext::ResultExt, utils::ImageType,
+    config_picker::ConfigPicker, dialog, error::Error, globals, result_ext::ResultExt,
+    utils::ImageType,
 };
 use clap::{ArgEnum, Parser};
 use fltk_theme::ThemeType;
@@ -48,6 +49,12 @@ lazy_static! {
 
     /// Log File
     static ref LOG_FILE: PathBuf = CONFIG_DIR.join("post_maker.log");
+
+    /// Recently opened project files
+    static ref RECENT_PROJECTS_FILE: PathBuf = CONFIG_DIR.join("recent_projects.json");
+
+    /// Index files of properties of folders
+    static ref INDEX_DIR: PathBuf = CONFIG_DIR.join("index");
 }
 
 /// Simple program calculate size of stuff in quote image
@@ -57,6 +64,10 @@ pub(crate) struct Args {
     /// Theme to use for gui
     #[clap(short, long, arg_enum)]
     pub(crate) theme: Option<Themes>,
+
+    /// Only log errors, without showing dialogs
+    #[clap(long)]
+    pub(crate) log_errors: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ArgEnum)]
@@ -135,6 +146,80 @@ pub(crate) struct ConfigFile {
     pub(crate) draw_box_around_quote: bool,
     pub(crate) line_spacing: bool,
     pub(crate) image_format: ImageType,
+    /// Lowest contrast ratio of text against image, before warning
+    #[serde(default = "default_minimum_contrast_ratio")]
+    pub(crate) minimum_contrast_ratio: f64,
+    /// How to make text readable on Export All
+    #[serde(default)]
+    pub(crate) legibility_fix: LegibilityFix,
+    /// Images exported at same time on Export All
+    #[serde(default = "default_export_threads")]
+    pub(crate) export_threads: usize,
+    /// Name of exported image without extension, see `utils::export_file_name` for placeholders
+    #[serde(default = "default_export_filename")]
+    pub(crate) export_filename: String,
+    /// Folder of exported images, relative to folder of image or absolute
+    #[serde(default = "default_export_dir")]
+    pub(crate) export_dir: String,
+    /// What to do when exported image already exists
+    #[serde(default)]
+    pub(crate) export_collision: ExportCollision,
+    /// Where properties of images are kept
+    #[serde(default)]
+    pub(crate) properties_storage: PropertiesStorage,
+    /// Copy artist and copyright of image to exported Jpeg
+    #[serde(default)]
+    pub(crate) keep_copyright: bool,
+}
+
+/// Way to handle exported image which already exists
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum ExportCollision {
+    /// Replace existing image
+    #[default]
+    Overwrite,
+    /// Add number to name, `-2`, `-3`...
+    Suffix,
+    /// Keep existing image and do not export
+    Skip,
+}
+
+/// Storage of properties of images
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum PropertiesStorage {
+    /// `.prop` file next to every image
+    #[default]
+    Files,
+    /// One index file for folder or project, kept in config folder or next to project file
+    Index,
+}
+
+/// Way to make text readable when contrast is too low
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum LegibilityFix {
+    /// Keep as it is
+    #[default]
+    None,
+    /// Pick black or white text, whichever is more readable
+    TextColour,
+    /// Raise alpha of translucent layer just enough
+    LayerAlpha,
+}
+
+fn default_minimum_contrast_ratio() -> f64 {
+    3.0
+}
+
+fn default_export_threads() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+fn default_export_filename() -> String {
+    "{prefix}{name}".to_owned()
+}
+
+fn default_export_dir() -> String {
+    "export".to_owned()
 }
 
 impl Default for ConfigFile {
@@ -163,6 +248,14 @@ impl Default for ConfigFile {
             draw_box_around_quote: true,
             line_spacing: true,
             image_format: ImageType::Jpeg,
+            minimum_contrast_ratio: default_minimum_contrast_ratio(),
+            legibility_fix: LegibilityFix::None,
+            export_threads: default_export_threads(),
+            export_filename: default_export_filename(),
+            export_dir: default_export_dir(),
+            export_collision: ExportCollision::Overwrite,
+            properties_storage: PropertiesStorage::Files,
+            keep_copyright: false,
         }
     }
 }
@@ -210,9 +303,14 @@ impl ConfigFile {
 
 /// Get parsed configs from file
 pub(crate) fn get_configs() -> Option<HashMap<String, ConfigFile>> {
-    match std::fs::read_to_string(&*CONFIG_FILE) {
-        Ok(r) => serde_json::from_str::<HashMap<String, ConfigFile>>(&r).ok(),
-        Err(_) => None,
+    let r = std::fs::read_to_string(&*CONFIG_FILE).ok()?;
+    match serde_json::from_str::<HashMap<String, ConfigFile>>(&r) {
+        Ok(configs) => Some(configs),
+        Err(e) => {
+            Result::<(), _>::Err(Error::Config(e.to_string()))
+                .warn_log("Config file is corrupt, default config is used!");
+            None
+        }
     }
 }
 
@@ -222,6 +320,31 @@ pub(crate) fn save_configs(configs: HashMap<String, ConfigFile>) {
         .expect_log("Can't write config!");
 }
 
+/// Get recently opened projects, latest first
+pub(crate) fn get_recent_projects() -> Vec<PathBuf> {
+    match std::fs::read_to_string(&*RECENT_PROJECTS_FILE) {
+        Ok(r) => serde_json::from_str(&r).unwrap_or_default(),
+        Err(_) => vec![],
+    }
+}
+
+/// Save recently opened projects
+pub(crate) fn save_recent_projects(projects: &[PathBuf]) {
+    std::fs::write(
+        &*RECENT_PROJECTS_FILE,
+        serde_json::to_string(projects).unwrap(),
+    )
+    .warn_log("Can't write recent projects!");
+}
+
+/// Folder of index files of properties, made if it does not exist
+pub(crate) fn index_dir() -> PathBuf {
+    if !INDEX_DIR.exists() {
+        std::fs::create_dir_all(&*INDEX_DIR).warn_log("Can't create index folder!");
+    }
+    INDEX_DIR.clone()
+}
+
 pub(crate) fn log_file() -> File {
     match File::create(&*LOG_FILE) {
         Ok(f) => f,
@@ -15,7 +15,7 @@
 //! Window to edit configuration
 
 use crate::{
-    config::{self, ConfigFile},
+    config::{self, ConfigFile, ExportCollision, LegibilityFix, PropertiesStorage},
     dialog, globals,
     result_ext::ResultExt,
     utils::{self, ImageType},
@@ -29,6 +29,7 @@ use fltk::{
     frame::Frame,
     group::{Flex, Scroll},
     image::SvgImage,
+    input::Input,
     output::Output,
     prelude::*,
     valuator::ValueInput,
@@ -67,6 +68,7 @@ pub(crate) struct ConfigWindow {
     pub(crate) image_ratio_height: ValueInput,
     pub(crate) draw_box_around_quote: CheckButton,
     pub(crate) line_spacing: CheckButton,
+    pub(crate) keep_copyright: CheckButton,
     pub(crate) minimum_width_limit: ValueInput,
     pub(crate) maximum_width_limit: ValueInput,
     /// RGB value of top translucent layer
@@ -75,6 +77,19 @@ pub(crate) struct ConfigWindow {
     pub(crate) translucent_layer_alpha: ValueInput,
     pub(crate) png_format: RadioRoundButton,
     pub(crate) jpeg_format: RadioRoundButton,
+    pub(crate) minimum_contrast_ratio: ValueInput,
+    pub(crate) export_threads: ValueInput,
+    pub(crate) export_filename: Input,
+    pub(crate) export_dir: Input,
+    pub(crate) export_dir_browse: Button,
+    pub(crate) collision_overwrite: RadioRoundButton,
+    pub(crate) collision_suffix: RadioRoundButton,
+    pub(crate) collision_skip: RadioRoundButton,
+    pub(crate) legibility_none: RadioRoundButton,
+    pub(crate) legibility_text_colour: RadioRoundButton,
+    pub(crate) legibility_layer_alpha: RadioRoundButton,
+    pub(crate) properties_files: RadioRoundButton,
+    pub(crate) properties_index: RadioRoundButton,
     pub(crate) defaults_btn: Button,
     pub(crate) save_btn: Button,
     pub(crate) cancel_btn: Button,
@@ -135,7 +150,7 @@ impl ConfigWindow {
             .with_pos(205, 5);
 
         let mut col = Flex::default()
-            .with_size(scroll.width() - 35, 850)
+            .with_size(scroll.width() - 35, 1180)
             .column()
             .with_pos(100, 0);
 
@@ -479,9 +494,128 @@ impl ConfigWindow {
         let mut png_format = RadioRoundButton::default().with_label("Png");
         png_format.set_value(true);
         let jpeg_format = RadioRoundButton::default().with_label("Jpeg");
+        let mut keep_copyright = CheckButton::default().with_label("Keep artist and copyright");
+        keep_copyright.set_tooltip(
+            "Copy artist and copyright of image to export, as EXIF of Jpeg or eXIf chunk of Png.\n\
+            Location and other metadata are never copied",
+        );
         image_format_flex.end();
         col.set_size(&image_format_flex, 30);
 
+        let mut export_threads_grp = Flex::default().row();
+        export_threads_grp.set_size(
+            &Frame::default()
+                .with_label("Export threads")
+                .with_align(Align::Right | Align::Inside),
+            130,
+        );
+        let export_threads = ValueInput::default();
+        Frame::default();
+        export_threads_grp.end();
+        col.set_size(&export_threads_grp, 30);
+
+        let mut label = Frame::default().with_label("Export Name:");
+        label.set_label_font(enums::Font::HelveticaBold);
+        col.set_size(&label, 15);
+
+        let mut hint = Frame::default().with_label(
+            "{prefix} {name} {stem} {ext} {index} {date} {config} {width} {height} {quote}",
+        );
+        hint.set_label_font(Font::CourierItalic);
+        hint.set_label_size(12);
+        col.set_size(&hint, 20);
+
+        let mut export_filename_grp = Flex::default().row();
+        export_filename_grp.set_size(
+            &Frame::default()
+                .with_label("File name")
+                .with_align(Align::Right | Align::Inside),
+            130,
+        );
+        let export_filename = Input::default();
+        export_filename_grp.end();
+        col.set_size(&export_filename_grp, 30);
+
+        let mut export_dir_grp = Flex::default().row();
+        export_dir_grp.set_size(
+            &Frame::default()
+                .with_label("Folder")
+                .with_align(Align::Right | Align::Inside),
+            130,
+        );
+        let mut export_dir = Input::default();
+        export_dir.set_tooltip("Relative to folder of image or absolute");
+        let export_dir_browse = Button::default().with_label("Pick");
+        export_dir_grp.set_size(&export_dir_browse, 50);
+        export_dir_grp.end();
+        col.set_size(&export_dir_grp, 30);
+
+        let mut collision_flex = Flex::default().row();
+        collision_flex.set_size(
+            &Frame::default()
+                .with_label("If exists")
+                .with_align(Align::Right | Align::Inside),
+            130,
+        );
+        let mut collision_overwrite = RadioRoundButton::default().with_label("Overwrite");
+        collision_overwrite.set_value(true);
+        let collision_suffix = RadioRoundButton::default().with_label("Add number");
+        let collision_skip = RadioRoundButton::default().with_label("Skip");
+        collision_flex.end();
+        col.set_size(&collision_flex, 30);
+
+        let mut label = Frame::default().with_label("Legibility:");
+        label.set_label_font(enums::Font::HelveticaBold);
+        col.set_size(&label, 15);
+
+        let mut hint =
+            Frame::default().with_label("Contrast ratio of text (1-21) and fix on Export All");
+        hint.set_label_font(Font::CourierItalic);
+        hint.set_label_size(12);
+        col.set_size(&hint, 20);
+
+        let mut minimum_contrast_ratio_grp = Flex::default().row();
+        minimum_contrast_ratio_grp.set_size(
+            &Frame::default()
+                .with_label("Minimum contrast")
+                .with_align(Align::Right | Align::Inside),
+            130,
+        );
+        let minimum_contrast_ratio = ValueInput::default();
+        Frame::default();
+        minimum_contrast_ratio_grp.end();
+        col.set_size(&minimum_contrast_ratio_grp, 30);
+
+        let mut legibility_flex = Flex::default().row();
+        legibility_flex.set_size(&Frame::default(), 20);
+        let mut legibility_none = RadioRoundButton::default().with_label("Keep");
+        legibility_none.set_value(true);
+        let legibility_text_colour = RadioRoundButton::default().with_label("Black or white text");
+        let legibility_layer_alpha = RadioRoundButton::default().with_label("Raise layer alpha");
+        legibility_flex.end();
+        col.set_size(&legibility_flex, 30);
+
+        let mut label = Frame::default().with_label("Properties:");
+        label.set_label_font(enums::Font::HelveticaBold);
+        col.set_size(&label, 15);
+
+        let mut hint = Frame::default().with_label("Where texts and positions of images are kept");
+        hint.set_label_font(Font::CourierItalic);
+        hint.set_label_size(12);
+        col.set_size(&hint, 20);
+
+        let mut properties_flex = Flex::default().row();
+        properties_flex.set_size(&Frame::default(), 20);
+        let mut properties_files = RadioRoundButton::default().with_label("File next to image");
+        properties_files.set_value(true);
+        let mut properties_index = RadioRoundButton::default().with_label("Index file");
+        properties_index.set_tooltip(
+            "One file for folder in config folder, or next to project file.\n\
+            Use File/Move Properties to Index to bring existing properties",
+        );
+        properties_flex.end();
+        col.set_size(&properties_flex, 30);
+
         Frame::default();
         col.end();
 
@@ -523,12 +657,26 @@ impl ConfigWindow {
             image_ratio_height,
             draw_box_around_quote,
             line_spacing,
+            keep_copyright,
             minimum_width_limit,
             maximum_width_limit,
             translucent_layer_rgb,
             translucent_layer_alpha,
             png_format,
             jpeg_format,
+            minimum_contrast_ratio,
+            export_threads,
+            export_filename,
+            export_dir,
+            export_dir_browse,
+            collision_overwrite,
+            collision_suffix,
+            collision_skip,
+            legibility_none,
+            legibility_text_colour,
+            legibility_layer_alpha,
+            properties_files,
+            properties_index,
             defaults_btn,
             save_btn,
             cancel_btn,
@@ -582,6 +730,7 @@ impl ConfigWindow {
         self.draw_box_around_quote
             .set_checked(config.draw_box_around_quote);
         self.line_spacing.set_checked(config.line_spacing);
+        self.keep_copyright.set_checked(config.keep_copyright);
         self.minimum_width_limit
             .set_value(config.minimum_width_limit);
         self.maximum_width_limit
@@ -602,6 +751,29 @@ impl ConfigWindow {
             _ => (),
         }
 
+        self.minimum_contrast_ratio
+            .set_value(config.minimum_contrast_ratio);
+        self.export_threads.set_value(config.export_threads as f64);
+        self.export_filename.set_value(&config.export_filename);
+        self.export_dir.set_value(&config.export_dir);
+        set_export_collision(
+            config.export_collision,
+            &mut self.collision_overwrite,
+            &mut self.collision_suffix,
+            &mut self.collision_skip,
+        );
+        set_legibility_fix(
+            config.legibility_fix,
+            &mut self.legibility_none,
+            &mut self.legibility_text_colour,
+            &mut self.legibility_layer_alpha,
+        );
+        set_properties_storage(
+            config.properties_storage,
+            &mut self.properties_files,
+            &mut self.properties_index,
+        );
+
         *self.did_save.borrow_mut() = false;
         drop(config);
         self.win.show();
@@ -634,10 +806,23 @@ impl ConfigWindow {
         let mut image_ratio_height = self.image_ratio_height.clone();
         let draw_box_around_quote = self.draw_box_around_quote.clone();
         let line_spacing = self.line_spacing.clone();
+        let keep_copyright = self.keep_copyright.clone();
         let mut minimum_width_limit = self.minimum_width_limit.clone();
         let mut maximum_width_limit = self.maximum_width_limit.clone();
         let mut layer_rgb = self.translucent_layer_rgb.clone();
         let mut layer_alpha = self.translucent_layer_alpha.clone();
+        let mut minimum_contrast_ratio = self.minimum_contrast_ratio.clone();
+        let mut export_threads = self.export_threads.clone();
+        let mut export_filename = self.export_filename.clone();
+        let mut export_dir = self.export_dir.clone();
+        let mut collision_overwrite = self.collision_overwrite.clone();
+        let mut collision_suffix = self.collision_suffix.clone();
+        let mut collision_skip = self.collision_skip.clone();
+        let mut legibility_none = self.legibility_none.clone();
+        let mut legibility_text_colour = self.legibility_text_colour.clone();
+        let mut legibility_layer_alpha = self.legibility_layer_alpha.clone();
+        let mut properties_files = self.properties_files.clone();
+        let mut properties_index = self.properties_index.clone();
         let mut browse = self.browse.clone();
         let configs = Rc::clone(&self.configs);
         let selected_browse_line = Rc::clone(&self.selected_browse_line);
@@ -682,10 +867,32 @@ impl ConfigWindow {
             image_ratio_height.set_value(conf.image_ratio.1);
             draw_box_around_quote.set_checked(conf.draw_box_around_quote);
             line_spacing.set_checked(conf.line_spacing);
+            keep_copyright.set_checked(conf.keep_copyright);
             minimum_width_limit.set_value(conf.minimum_width_limit);
             maximum_width_limit.set_value(conf.maximum_width_limit);
             utils::set_color_btn_rgba(conf.color_layer, &mut layer_rgb);
             layer_alpha.set_value(conf.color_layer[3] as f64);
+            minimum_contrast_ratio.set_value(conf.minimum_contrast_ratio);
+            export_threads.set_value(conf.export_threads as f64);
+            export_filename.set_value(&conf.export_filename);
+            export_dir.set_value(&conf.export_dir);
+            set_export_collision(
+                conf.export_collision,
+                &mut collision_overwrite,
+                &mut collision_suffix,
+                &mut collision_skip,
+            );
+            set_legibility_fix(
+                conf.legibility_fix,
+                &mut legibility_none,
+                &mut legibility_text_colour,
+                &mut legibility_layer_alpha,
+            );
+            set_properties_storage(
+                conf.properties_storage,
+                &mut properties_files,
+                &mut properties_index,
+            );
             browse.add(&name);
             configs.borrow_mut().insert(name.clone(), conf);
             browse.select(browse.size());
@@ -714,10 +921,23 @@ impl ConfigWindow {
         let mut image_ratio_height = self.image_ratio_height.clone();
         let draw_box_around_quote = self.draw_box_around_quote.clone();
         let line_spacing = self.line_spacing.clone();
+        let keep_copyright = self.keep_copyright.clone();
         let mut minimum_width_limit = self.minimum_width_limit.clone();
         let mut maximum_width_limit = self.maximum_width_limit.clone();
         let mut layer_rgb = self.translucent_layer_rgb.clone();
         let mut layer_alpha = self.translucent_layer_alpha.clone();
+        let mut minimum_contrast_ratio = self.minimum_contrast_ratio.clone();
+        let mut export_threads = self.export_threads.clone();
+        let mut export_filename = self.export_filename.clone();
+        let mut export_dir = self.export_dir.clone();
+        let mut collision_overwrite = self.collision_overwrite.clone();
+        let mut collision_suffix = self.collision_suffix.clone();
+        let mut collision_skip = self.collision_skip.clone();
+        let mut legibility_none = self.legibility_none.clone();
+        let mut legibility_text_colour = self.legibility_text_colour.clone();
+        let mut legibility_layer_alpha = self.legibility_layer_alpha.clone();
+        let mut properties_files = self.properties_files.clone();
+        let mut properties_index = self.properties_index.clone();
         let mut browse = self.browse.clone();
         let configs = Rc::clone(&self.configs);
         let selected_browse_line = Rc::clone(&self.selected_browse_line);
@@ -761,10 +981,32 @@ impl ConfigWindow {
                 image_ratio_height.set_value(conf.image_ratio.1);
                 draw_box_around_quote.set_checked(conf.draw_box_around_quote);
                 line_spacing.set_checked(conf.line_spacing);
+                keep_copyright.set_checked(conf.keep_copyright);
                 minimum_width_limit.set_value(conf.minimum_width_limit);
                 maximum_width_limit.set_value(conf.maximum_width_limit);
                 utils::set_color_btn_rgba(conf.color_layer, &mut layer_rgb);
                 layer_alpha.set_value(conf.color_layer[3] as f64);
+                minimum_contrast_ratio.set_value(conf.minimum_contrast_ratio);
+                export_threads.set_value(conf.export_threads as f64);
+                export_filename.set_value(&conf.export_filename);
+                export_dir.set_value(&conf.export_dir);
+                set_export_collision(
+                    conf.export_collision,
+                    &mut collision_overwrite,
+                    &mut collision_suffix,
+                    &mut collision_skip,
+                );
+                set_legibility_fix(
+                    conf.legibility_fix,
+                    &mut legibility_none,
+                    &mut legibility_text_colour,
+                    &mut legibility_layer_alpha,
+                );
+                set_properties_storage(
+                    conf.properties_storage,
+                    &mut properties_files,
+                    &mut properties_index,
+                );
                 layer_rgb.redraw();
             }
         });
@@ -790,10 +1032,23 @@ impl ConfigWindow {
         let mut image_ratio_height = self.image_ratio_height.clone();
         let draw_box_around_quote = self.draw_box_around_quote.clone();
         let line_spacing = self.line_spacing.clone();
+        let keep_copyright = self.keep_copyright.clone();
         let mut minimum_width_limit = self.minimum_width_limit.clone();
         let mut maximum_width_limit = self.maximum_width_limit.clone();
         let mut layer_rgb = self.translucent_layer_rgb.clone();
         let mut layer_alpha = self.translucent_layer_alpha.clone();
+        let mut minimum_contrast_ratio = self.minimum_contrast_ratio.clone();
+        let mut export_threads = self.export_threads.clone();
+        let mut export_filename = self.export_filename.clone();
+        let mut export_dir = self.export_dir.clone();
+        let mut collision_overwrite = self.collision_overwrite.clone();
+        let mut collision_suffix = self.collision_suffix.clone();
+        let mut collision_skip = self.collision_skip.clone();
+        let mut legibility_none = self.legibility_none.clone();
+        let mut legibility_text_colour = self.legibility_text_colour.clone();
+        let mut legibility_layer_alpha = self.legibility_layer_alpha.clone();
+        let mut properties_files = self.properties_files.clone();
+        let mut properties_index = self.properties_index.clone();
         let configs = Rc::clone(&self.configs);
         let selected_browse_line = Rc::clone(&self.selected_browse_line);
         self.browse.set_callback(move |f| {
@@ -827,10 +1082,32 @@ impl ConfigWindow {
                 image_ratio_height.set_value(conf.image_ratio.1);
                 draw_box_around_quote.set_checked(conf.draw_box_around_quote);
                 line_spacing.set_checked(conf.line_spacing);
+                keep_copyright.set_checked(conf.keep_copyright);
                 minimum_width_limit.set_value(conf.minimum_width_limit);
                 maximum_width_limit.set_value(conf.maximum_width_limit);
                 utils::set_color_btn_rgba(conf.color_layer, &mut layer_rgb);
                 layer_alpha.set_value(conf.color_layer[3] as f64);
+                minimum_contrast_ratio.set_value(conf.minimum_contrast_ratio);
+                export_threads.set_value(conf.export_threads as f64);
+                export_filename.set_value(&conf.export_filename);
+                export_dir.set_value(&conf.export_dir);
+                set_export_collision(
+                    conf.export_collision,
+                    &mut collision_overwrite,
+                    &mut collision_suffix,
+                    &mut collision_skip,
+                );
+                set_legibility_fix(
+                    conf.legibility_fix,
+                    &mut legibility_none,
+                    &mut legibility_text_colour,
+                    &mut legibility_layer_alpha,
+                );
+                set_properties_storage(
+                    conf.properties_storage,
+                    &mut properties_files,
+                    &mut properties_index,
+                );
                 layer_rgb.redraw();
             }
             *selected_browse_line.borrow_mut() = f.value();
@@ -1167,6 +1444,19 @@ impl ConfigWindow {
             true
         });
 
+        // Keep artist and copyright
+        let browse = self.browse.clone();
+        let configs = Rc::clone(&self.configs);
+        self.keep_copyright.handle(move |f, _| {
+            if let Some(conf) = configs
+                .borrow_mut()
+                .get_mut(&browse.selected_text().unwrap())
+            {
+                conf.keep_copyright = f.value();
+            }
+            true
+        });
+
         // Minimum Width Limit
         let browse = self.browse.clone();
         let configs = Rc::clone(&self.configs);
@@ -1265,6 +1555,184 @@ impl ConfigWindow {
             }
         });
 
+        // Minimum contrast ratio
+        let browse = self.browse.clone();
+        let configs = Rc::clone(&self.configs);
+        self.minimum_contrast_ratio.handle(move |f, ev| {
+            if ev == Event::KeyUp {
+                if let Some(conf) = configs
+                    .borrow_mut()
+                    .get_mut(&browse.selected_text().unwrap())
+                {
+                    conf.minimum_contrast_ratio = f.value();
+                }
+            }
+            true
+        });
+
+        // Images exported at same time
+        let browse = self.browse.clone();
+        let configs = Rc::clone(&self.configs);
+        self.export_threads.handle(move |f, ev| {
+            if ev == Event::KeyUp {
+                if let Some(conf) = configs
+                    .borrow_mut()
+                    .get_mut(&browse.selected_text().unwrap())
+                {
+                    conf.export_threads = f.value().max(1.0) as usize;
+                }
+            }
+            true
+        });
+
+        // Filename template of exported image
+        let browse = self.browse.clone();
+        let configs = Rc::clone(&self.configs);
+        self.export_filename.handle(move |f, ev| {
+            if ev == Event::KeyUp {
+                if let Some(conf) = configs
+                    .borrow_mut()
+                    .get_mut(&browse.selected_text().unwrap())
+                {
+                    conf.export_filename = f.value();
+                }
+            }
+            false
+        });
+
+        // Folder of exported images
+        let browse = self.browse.clone();
+        let configs = Rc::clone(&self.configs);
+        self.export_dir.handle(move |f, ev| {
+            if ev == Event::KeyUp {
+                if let Some(conf) = configs
+                    .borrow_mut()
+                    .get_mut(&browse.selected_text().unwrap())
+                {
+                    conf.export_dir = f.value();
+                }
+            }
+            false
+        });
+
+        // Browse for folder of exported images
+        let browse = self.browse.clone();
+        let configs = Rc::clone(&self.configs);
+        let mut export_dir = self.export_dir.clone();
+        self.export_dir_browse.set_callback(move |_| {
+            let mut chooser = NativeFileChooser::new(fltk::dialog::FileDialogType::BrowseDir);
+            chooser.show();
+            let path = chooser.filename();
+            if path.as_os_str().is_empty() {
+                return;
+            }
+            let path = std::fs::canonicalize(&path).unwrap_or(path);
+            let path = path.to_str().unwrap();
+            export_dir.set_value(path);
+            if let Some(conf) = configs
+                .borrow_mut()
+                .get_mut(&browse.selected_text().unwrap())
+            {
+                conf.export_dir = path.to_owned();
+            }
+        });
+
+        // Overwrite existing exported image
+        let browse = self.browse.clone();
+        let configs = Rc::clone(&self.configs);
+        self.collision_overwrite.set_callback(move |_| {
+            if let Some(conf) = configs
+                .borrow_mut()
+                .get_mut(&browse.selected_text().unwrap())
+            {
+                conf.export_collision = ExportCollision::Overwrite;
+            }
+        });
+
+        // Add number to name of exported image
+        let browse = self.browse.clone();
+        let configs = Rc::clone(&self.configs);
+        self.collision_suffix.set_callback(move |_| {
+            if let Some(conf) = configs
+                .borrow_mut()
+                .get_mut(&browse.selected_text().unwrap())
+            {
+                conf.export_collision = ExportCollision::Suffix;
+            }
+        });
+
+        // Keep existing exported image
+        let browse = self.browse.clone();
+        let configs = Rc::clone(&self.configs);
+        self.collision_skip.set_callback(move |_| {
+            if let Some(conf) = configs
+                .borrow_mut()
+                .get_mut(&browse.selected_text().unwrap())
+            {
+                conf.export_collision = ExportCollision::Skip;
+            }
+        });
+
+        // Keep text and layer as it is
+        let browse = self.browse.clone();
+        let configs = Rc::clone(&self.configs);
+        self.legibility_none.set_callback(move |_| {
+            if let Some(conf) = configs
+                .borrow_mut()
+                .get_mut(&browse.selected_text().unwrap())
+            {
+                conf.legibility_fix = LegibilityFix::None;
+            }
+        });
+
+        // Pick black or white text
+        let browse = self.browse.clone();
+        let configs = Rc::clone(&self.configs);
+        self.legibility_text_colour.set_callback(move |_| {
+            if let Some(conf) = configs
+                .borrow_mut()
+                .get_mut(&browse.selected_text().unwrap())
+            {
+                conf.legibility_fix = LegibilityFix::TextColour;
+            }
+        });
+
+        // Raise alpha of layer
+        let browse = self.browse.clone();
+        let configs = Rc::clone(&self.configs);
+        self.legibility_layer_alpha.set_callback(move |_| {
+            if let Some(conf) = configs
+                .borrow_mut()
+                .get_mut(&browse.selected_text().unwrap())
+            {
+                conf.legibility_fix = LegibilityFix::LayerAlpha;
+            }
+        });
+
+        // Keep properties next to images
+        let browse = self.browse.clone();
+        let configs = Rc::clone(&self.configs);
+        self.properties_files.set_callback(move |_| {
+            if let Some(conf) = configs
+                .borrow_mut()
+                .get_mut(&browse.selected_text().unwrap())
+            {
+                conf.properties_storage = PropertiesStorage::Files;
+            }
+        });
+
+        // Keep properties in index file
+        let browse = self.browse.clone();
+        let configs = Rc::clone(&self.configs);
+        self.properties_index.set_callback(move |_| {
+            if let Some(conf) = configs
+                .borrow_mut()
+                .get_mut(&browse.selected_text().unwrap())
+            {
+                conf.properties_storage = PropertiesStorage::Index;
+            }
+        });
+
         // Reset to default configuation button
         let mut quote_font = self.quote_font.clone();
         let mut subquote_font = self.subquote_font.clone();
@@ -1286,12 +1754,25 @@ impl ConfigWindow {
         let mut image_ratio_height = self.image_ratio_height.clone();
         let draw_box_around_quote = self.draw_box_around_quote.clone();
         let line_spacing = self.line_spacing.clone();
+        let keep_copyright = self.keep_copyright.clone();
         let mut minimum_width_limit = self.minimum_width_limit.clone();
         let mut maximum_width_limit = self.maximum_width_limit.clone();
         let mut layer_rgb = self.translucent_layer_rgb.clone();
         let mut layer_alpha = self.translucent_layer_alpha.clone();
         let mut png_format = self.png_format.clone();
         let mut jpeg_format = self.jpeg_format.clone();
+        let mut minimum_contrast_ratio = self.minimum_contrast_ratio.clone();
+        let mut export_threads = self.export_threads.clone();
+        let mut export_filename = self.export_filename.clone();
+        let mut export_dir = self.export_dir.clone();
+        let mut collision_overwrite = self.collision_overwrite.clone();
+        let mut collision_suffix = self.collision_suffix.clone();
+        let mut collision_skip = self.collision_skip.clone();
+        let mut legibility_none = self.legibility_none.clone();
+        let mut legibility_text_colour = self.legibility_text_colour.clone();
+        let mut legibility_layer_alpha = self.legibility_layer_alpha.clone();
+        let mut properties_files = self.properties_files.clone();
+        let mut properties_index = self.properties_index.clone();
         let configs = Rc::clone(&self.configs);
         let browse = self.browse.clone();
         self.defaults_btn.set_callback(move |_| {
@@ -1316,6 +1797,7 @@ impl ConfigWindow {
             image_ratio_height.set_value(conf.image_ratio.1);
             draw_box_around_quote.set_checked(conf.draw_box_around_quote);
             line_spacing.set_checked(conf.line_spacing);
+            keep_copyright.set_checked(conf.keep_copyright);
             minimum_width_limit.set_value(conf.minimum_width_limit);
             maximum_width_limit.set_value(conf.maximum_width_limit);
             utils::set_color_btn_rgba(conf.color_layer, &mut layer_rgb);
@@ -1332,6 +1814,27 @@ impl ConfigWindow {
                 }
                 _ => {}
             }
+            minimum_contrast_ratio.set_value(conf.minimum_contrast_ratio);
+            export_threads.set_value(conf.export_threads as f64);
+            export_filename.set_value(&conf.export_filename);
+            export_dir.set_value(&conf.export_dir);
+            set_export_collision(
+                conf.export_collision,
+                &mut collision_overwrite,
+                &mut collision_suffix,
+                &mut collision_skip,
+            );
+            set_legibility_fix(
+                conf.legibility_fix,
+                &mut legibility_none,
+                &mut legibility_text_colour,
+                &mut legibility_layer_alpha,
+            );
+            set_properties_storage(
+                conf.properties_storage,
+                &mut properties_files,
+                &mut properties_index,
+            );
             configs
                 .borrow_mut()
                 .insert(browse.selected_text().unwrap(), conf);
@@ -1353,3 +1856,36 @@ impl ConfigWindow {
         });
     }
 }
+
+/// Select radio button of legibility fix
+fn set_legibility_fix(
+    fix: LegibilityFix,
+    none: &mut RadioRoundButton,
+    text_colour: &mut RadioRoundButton,
+    layer_alpha: &mut RadioRoundButton,
+) {
+    none.set_value(fix == LegibilityFix::None);
+    text_colour.set_value(fix == LegibilityFix::TextColour);
+    layer_alpha.set_value(fix == LegibilityFix::LayerAlpha);
+}
+
+fn set_export_collision(
+    collision: ExportCollision,
+    overwrite: &mut RadioRoundButton,
+    suffix: &mut RadioRoundButton,
+    skip: &mut RadioRoundButton,
+) {
+    overwrite.set_value(collision == ExportCollision::Overwrite);
+    suffix.set_value(collision == ExportCollision::Suffix);
+    skip.set_value(collision == ExportCollision::Skip);
+}
+
+/// Select radio button of properties storage
+fn set_properties_storage(
+    storage: PropertiesStorage,
+    files: &mut RadioRoundButton,
+    index: &mut RadioRoundButton,
+) {
+    files.set_value(storage == PropertiesStorage::Files);
+    index.set_value(storage == PropertiesStorage::Index);
+}
@@ -112,7 +112,13 @@ impl CropWindow {
         crop_pos: Option<(f64, f64)>,
     ) -> Option<(f64, f64)> {
         let mut container =
-            ImageContainer::new(path, Arc::new(RwLock::new(ImageProperties::default())));
+            match ImageContainer::new(path, Arc::new(RwLock::new(ImageProperties::default()))) {
+                Ok(container) => container,
+                Err(e) => {
+                    utils::show_alert(&e.to_string());
+                    return None;
+                }
+            };
         {
             let prop = &mut rw_write!(container.properties);
             prop.dimension = prop.original_dimension;
@@ -15,8 +15,10 @@
 //! Thread to manage drawing in background
 
 use crate::{
-    globals,
-    main_window::{MainWindow, Page},
+    error::{self, Reporter},
+    globals, history, image_list,
+    main_window::{MainWindow, Page, Zoom},
+    properties_store,
     result_ext::ResultExt,
     utils::{self, ImageContainer, ImageInfo, ImageProperties, ImagePropertiesFile},
     AppMessage,
@@ -32,11 +34,23 @@ use fltk::{
     prelude::*,
     valuator::Slider,
 };
+use image::{imageops, GenericImageView, RgbImage};
 use std::{
-    fs,
-    sync::{mpsc, Arc, RwLock},
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, RwLock,
+    },
 };
 
+/// Changed on every request to redraw, so that drawing of older properties can be stopped
+static REDRAW_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+/// Tell drawing in progress that properties have changed
+pub(crate) fn new_redraw_generation() {
+    REDRAW_GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
 #[derive(Debug, Clone)]
 pub(crate) enum DrawMessage {
     /// Open file or cropped file
@@ -49,6 +63,8 @@ pub(crate) enum DrawMessage {
     Flush,
     /// Save to file
     Save,
+    /// Show image exactly as it will be saved
+    PreviewExport,
     /// Clone file
     Clone,
     /// Delete file
@@ -57,6 +73,10 @@ pub(crate) enum DrawMessage {
     ShowImagesDetails,
     /// Check If image is proper
     CheckImage,
+    /// Go back to properties before last edit
+    Undo,
+    /// Apply again the last undone edit
+    Redo,
 }
 
 /// Spawn thread to manage all actions related to image, like: edit, save, delete
@@ -68,168 +88,223 @@ pub(crate) fn spawn_image_thread(
 ) {
     let mut win = main_win.win.clone();
     let mut file_choice = main_win.file_choice.clone();
-    let mut name_prefix = main_win.name_prefix.clone();
-    let mut quote = main_win.quote.clone();
-    let mut subquote = main_win.subquote.clone();
-    let mut subquote2 = main_win.subquote2.clone();
-    let mut tag = main_win.tag.clone();
-    let mut tag2 = main_win.tag2.clone();
-    let mut layer_rgb = main_win.translucent_layer_rgb.clone();
-    let mut layer_alpha = main_win.translucent_layer_alpha.clone();
-    let mut quote_position = main_win.quote_position.clone();
-    let mut subquote_position = main_win.subquote_position.clone();
-    let mut subquote2_position = main_win.subquote2_position.clone();
-    let mut tag_position = main_win.tag_position.clone();
-    let mut tag2_position = main_win.tag2_position.clone();
-    let mut quote_position_slider = main_win.quote_position_slider.clone();
-    let mut subquote_position_slider = main_win.subquote_position_slider.clone();
-    let mut subquote2_position_slider = main_win.subquote2_position_slider.clone();
-    let mut tag_position_slider = main_win.tag_position_slider.clone();
-    let mut tag2_position_slider = main_win.tag2_position_slider.clone();
+    let mut controls = Controls::new(main_win);
     let mut page = main_win.page.clone();
     let mut status = main_win.status.clone();
     let mut count = main_win.count.clone();
     let mut dimension = main_win.dimension.clone();
     let images_list = Arc::clone(&main_win.images_list);
+    let history = Arc::clone(&main_win.history);
 
     let mut _container: Option<ImageContainer> = None;
-    std::thread::spawn(move || loop {
-        if let Ok(val) = reciver.recv() {
-            match val {
-                DrawMessage::Open => {
-                    status.set_label("Loading...");
-                    load_image(
-                        &mut file_choice,
-                        Arc::clone(&images_list),
-                        None,
-                        &mut name_prefix,
-                        &mut quote,
-                        &mut subquote,
-                        &mut subquote2,
-                        &mut tag,
-                        &mut tag2,
-                        &mut layer_rgb,
-                        &mut layer_alpha,
-                        &mut quote_position,
-                        &mut subquote_position,
-                        &mut subquote2_position,
-                        &mut tag_position,
-                        &mut tag2_position,
-                        &mut quote_position_slider,
-                        &mut subquote_position_slider,
-                        &mut subquote2_position_slider,
-                        &mut tag_position_slider,
-                        &mut tag2_position_slider,
-                        &mut page,
-                        &mut count,
-                        &mut dimension,
-                        &app_sender,
-                        Arc::clone(&properties),
-                        &mut _container,
-                    );
-                    status.set_label("");
-                    status.set_label("");
-                }
-                DrawMessage::ChangeCrop((x, y)) => {
-                    status.set_label("Loading...");
-                    load_image(
-                        &mut file_choice,
-                        Arc::clone(&images_list),
-                        Some((x, y)),
-                        &mut name_prefix,
-                        &mut quote,
-                        &mut subquote,
-                        &mut subquote2,
-                        &mut tag,
-                        &mut tag2,
-                        &mut layer_rgb,
-                        &mut layer_alpha,
-                        &mut quote_position,
-                        &mut subquote_position,
-                        &mut subquote2_position,
-                        &mut tag_position,
-                        &mut tag2_position,
-                        &mut quote_position_slider,
-                        &mut subquote_position_slider,
-                        &mut subquote2_position_slider,
-                        &mut tag_position_slider,
-                        &mut tag2_position_slider,
-                        &mut page,
-                        &mut count,
-                        &mut dimension,
-                        &app_sender,
-                        Arc::clone(&properties),
-                        &mut _container,
-                    );
-                    status.set_label("");
-                }
-                DrawMessage::RedrawToBuffer => {
-                    if let Some(cont) = &mut _container {
-                        cont.redraw_to_buffer();
+    // Messages taken from channel while merging requests to redraw, but not handled yet
+    let mut pending: VecDeque<DrawMessage> = VecDeque::new();
+    std::thread::spawn(move || {
+        // Errors of editing are shown in status, so they don't stop user
+        error::set_thread_reporter(Some(Reporter::Status));
+        loop {
+            let val = match pending.pop_front() {
+                Some(val) => Ok(val),
+                None => reciver.recv(),
+            };
+            if let Ok(val) = val {
+                match val {
+                    DrawMessage::Open => {
+                        status.set_label("Loading...");
+                        rw_write!(history).seal();
+                        *rw_write!(page.selected) = None;
+                        rw_write!(page.view).pan = (0.0, 0.0);
+                        load_image(
+                            &mut file_choice,
+                            Arc::clone(&images_list),
+                            None,
+                            &mut controls,
+                            &mut page,
+                            &mut count,
+                            &mut dimension,
+                            &app_sender,
+                            Arc::clone(&properties),
+                            &mut _container,
+                        );
+                        show_legibility(&mut status, &_container);
                     }
-                }
-                DrawMessage::Flush => {
-                    flush_buffer(&app_sender, &mut _container);
-                }
-                DrawMessage::Save => {
-                    if let Some(cont) = &mut _container {
-                        status.set_label("Saving...");
-                        win.deactivate();
-                        cont.save();
-                        status.set_label("");
-                        win.activate();
-                        win.redraw();
-                        app::awake();
+                    DrawMessage::ChangeCrop((x, y)) => {
+                        status.set_label("Loading...");
+                        load_image(
+                            &mut file_choice,
+                            Arc::clone(&images_list),
+                            Some((x, y)),
+                            &mut controls,
+                            &mut page,
+                            &mut count,
+                            &mut dimension,
+                            &app_sender,
+                            Arc::clone(&properties),
+                            &mut _container,
+                        );
+                        show_legibility(&mut status, &_container);
                     }
-                }
-                DrawMessage::Clone => {
-                    if let Some(cont) = &mut _container {
-                        status.set_label("Cloning...");
-                        win.deactivate();
-                        if let Some(image_info) = cont.clone_img() {
-                            let idx = file_choice.value();
+                    DrawMessage::RedrawToBuffer | DrawMessage::Flush => {
+                        // Merge all waiting requests, so that only latest properties are drawn
+                        let (mut redraw, mut flush) = (false, false);
+                        pending.push_front(val);
+                        pending.extend(reciver.try_iter());
+                        while let Some(DrawMessage::RedrawToBuffer | DrawMessage::Flush) =
+                            pending.front()
+                        {
+                            match pending.pop_front() {
+                                Some(DrawMessage::RedrawToBuffer) => redraw = true,
+                                _ => flush = true,
+                            }
+                        }
+
+                        let generation = REDRAW_GENERATION.load(Ordering::SeqCst);
+                        let cancelled = || REDRAW_GENERATION.load(Ordering::SeqCst) != generation;
+                        if redraw {
+                            if let Some(cont) = &mut _container {
+                                if !cont.redraw_to_buffer(&cancelled) {
+                                    // Newer request is on the way, it will draw and flush
+                                    continue;
+                                }
+                            }
+                            show_legibility(&mut status, &_container);
+                        }
+                        if flush {
+                            flush_buffer(&app_sender, &mut _container, &page, &cancelled);
+                        }
+                    }
+                    DrawMessage::Save => {
+                        if let Some(cont) = &mut _container {
+                            status.set_label("Saving...");
+                            win.deactivate();
+                            match cont.save() {
+                                Ok(None) => {
+                                    utils::show_alert("Exported image already exists, kept it")
+                                }
+                                Ok(Some(_)) => (),
+                                // Edits are not saved, so user has to know
+                                Err(e) => {
+                                    warn!("Failed to save image!\n{:?}", e);
+                                    utils::show_alert(&format!("Failed to save image!\n{}", e));
+                                }
+                            }
+                            status.set_label("");
+                            win.activate();
+                            win.redraw();
+                            app::awake();
+                        }
+                    }
+                    DrawMessage::PreviewExport => {
+                        if let Some(cont) = &_container {
+                            status.set_label("Rendering...");
+                            win.deactivate();
+                            let data = cont.export_bytes();
+                            status.set_label("");
+                            win.activate();
+                            win.redraw();
+                            app::awake();
+                            data.warn_log("Failed to render export preview!");
+                            if let Ok(data) = data {
+                                app_sender.send(AppMessage::PreviewExport(data));
+                            }
+                        }
+                    }
+                    DrawMessage::Clone => {
+                        if let Some(cont) = &mut _container {
+                            status.set_label("Cloning...");
+                            win.deactivate();
+                            if let Some(image_info) = cont.clone_img() {
+                                let idx = file_choice.value();
+                                let mut imgs = rw_write!(images_list);
+                                image_list::insert_before(&imgs[idx as usize], image_info.clone());
+                                imgs.insert(idx as usize, image_info.clone());
+                                let root = utils::common_folder(&imgs);
+                                file_choice.insert(
+                                    idx,
+                                    &utils::image_label(&image_info, &root),
+                                    enums::Shortcut::None,
+                                    menu::MenuFlag::Normal,
+                                    |a| a.do_callback(),
+                                );
+                                file_choice.set_value(idx);
+                            }
+                            status.set_label("");
+                            win.activate();
+                            win.redraw();
+                            app::awake();
+                        }
+                    }
+                    DrawMessage::Delete => {
+                        if let Some(cont) = &mut _container {
+                            status.set_label("Deleting...");
+                            win.deactivate();
+                            cont.delete();
                             let mut imgs = rw_write!(images_list);
-                            imgs.insert(idx as usize, image_info.clone());
-                            file_choice.insert(
-                                idx,
-                                image_info.path.file_name().unwrap().to_str().unwrap(),
-                                enums::Shortcut::None,
-                                menu::MenuFlag::Normal,
-                                |a| a.do_callback(),
-                            );
-                            file_choice.set_value(idx);
+                            image_list::remove(&imgs.remove(file_choice.value() as usize));
+                            file_choice.remove(file_choice.value());
+                            if file_choice.value() != imgs.len() as i32 {
+                                file_choice.set_value(file_choice.value());
+                            } else {
+                                file_choice.set_value(file_choice.value() - 1);
+                            }
+                            status.set_label("");
+                            win.activate();
+                            win.redraw();
+                            app::awake();
                         }
-                        status.set_label("");
-                        win.activate();
-                        win.redraw();
-                        app::awake();
                     }
-                }
-                DrawMessage::Delete => {
-                    if let Some(cont) = &mut _container {
-                        status.set_label("Deleting...");
-                        win.deactivate();
-                        cont.delete();
-                        let mut imgs = rw_write!(images_list);
-                        imgs.remove(file_choice.value() as usize);
-                        file_choice.remove(file_choice.value());
-                        if file_choice.value() != imgs.len() as i32 {
-                            file_choice.set_value(file_choice.value());
-                        } else {
-                            file_choice.set_value(file_choice.value() - 1);
+                    DrawMessage::ShowImagesDetails => show_images_details(Arc::clone(&images_list)),
+                    DrawMessage::Undo | DrawMessage::Redo => {
+                        let mut prop = rw_write!(properties);
+                        let snapshot = match val {
+                            DrawMessage::Undo => rw_write!(history).undo(&prop),
+                            _ => rw_write!(history).redo(&prop),
+                        };
+                        let snapshot = match snapshot {
+                            Some(s) => s,
+                            None => continue,
+                        };
+                        let crop_changed = snapshot.crop_position != prop.crop_position;
+                        history::restore(&mut prop, snapshot);
+                        let restored = prop.clone();
+                        drop(prop);
+
+                        if crop_changed {
+                            status.set_label("Loading...");
+                            load_image(
+                                &mut file_choice,
+                                Arc::clone(&images_list),
+                                restored.crop_position,
+                                &mut controls,
+                                &mut page,
+                                &mut count,
+                                &mut dimension,
+                                &app_sender,
+                                Arc::clone(&properties),
+                                &mut _container,
+                            );
+                            history::restore(&mut rw_write!(properties), restored.clone());
+                        }
+
+                        controls.fill(&restored);
+                        if let Some(cont) = &mut _container {
+                            cont.redraw_to_buffer(&|| false);
                         }
-                        status.set_label("");
-                        win.activate();
+                        show_legibility(&mut status, &_container);
+                        flush_buffer(&app_sender, &mut _container, &page, &|| false);
                         win.redraw();
                         app::awake();
                     }
-                }
-                DrawMessage::ShowImagesDetails => show_images_details(Arc::clone(&images_list)),
-                DrawMessage::CheckImage => {
-                    let (width, height) = rw_read!(properties).original_dimension;
-                    if utils::is_too_small(width, height) {
-                        if let Some(a) = &*rw_read!(globals::MAIN_SENDER) {
-                            a.send(crate::AppMessage::DeleteImage);
+                    DrawMessage::CheckImage => {
+                        // Image which could not be opened has no size
+                        if _container.is_none() {
+                            continue;
+                        }
+                        let (width, height) = rw_read!(properties).original_dimension;
+                        if utils::is_too_small(width, height) {
+                            if let Some(a) = &*rw_read!(globals::MAIN_SENDER) {
+                                a.send(crate::AppMessage::DeleteImage);
+                            }
                         }
                     }
                 }
@@ -238,29 +313,22 @@ pub(crate) fn spawn_image_thread(
     });
 }
 
+/// Show position of open image in shown images, and count of loaded images if some are filtered
+pub(crate) fn set_count_label(count: &mut Frame, value: i32, shown: usize) {
+    let loaded = image_list::loaded_len();
+    if loaded > shown {
+        count.set_label(&format!("[{}/{} of {}]", value + 1, shown, loaded));
+    } else {
+        count.set_label(&format!("[{}/{}]", value + 1, shown));
+    }
+}
+
 /// Loads the selected image in file_choice to ImageContainer to edit
 fn load_image(
     file_choice: &mut menu::Choice,
     images_list: Arc<RwLock<Vec<ImageInfo>>>,
     crop: Option<(f64, f64)>,
-    name_prefix: &mut Input,
-    quote: &mut MultilineInput,
-    subquote: &mut MultilineInput,
-    subquote2: &mut MultilineInput,
-    tag: &mut Input,
-    tag2: &mut Input,
-    layer_rgb: &mut Button,
-    layer_alpha: &mut Spinner,
-    quote_position: &mut Spinner,
-    subquote_position: &mut Spinner,
-    subquote2_position: &mut Spinner,
-    tag_position: &mut Spinner,
-    tag2_position: &mut Spinner,
-    quote_position_slider: &mut Slider,
-    subquote_position_slider: &mut Slider,
-    subquote2_position_slider: &mut Slider,
-    tag_position_slider: &mut Slider,
-    tag2_position_slider: &mut Slider,
+    controls: &mut Controls,
     page: &mut Page,
     count: &mut Frame,
     dimension: &mut Frame,
@@ -269,68 +337,51 @@ fn load_image(
     container: &mut Option<ImageContainer>,
 ) {
     let imgs = rw_read!(images_list);
-    if imgs.len() == 0 {
-        *container = None;
-        flush_buffer(app_sender, container);
-        return;
-    }
-    count.set_label(&format!("[{}/{}]", file_choice.value() + 1, imgs.len()));
-    let image_info = imgs.get(file_choice.value() as usize).unwrap();
+    // Nothing is picked, or list changed after it was picked
+    let image_info = match usize::try_from(file_choice.value())
+        .ok()
+        .and_then(|i| imgs.get(i))
+    {
+        Some(image_info) => image_info,
+        None => {
+            *container = None;
+            flush_buffer(app_sender, container, page, &|| false);
+            return;
+        }
+    };
+    set_count_label(count, file_choice.value(), imgs.len());
 
-    *container = Some(ImageContainer::new(&image_info, Arc::clone(&properties)));
+    *container = match ImageContainer::new(image_info, Arc::clone(&properties)) {
+        Ok(cont) => Some(cont),
+        Err(e) => {
+            warn!("Failed to open {}: {}", image_info.path.display(), e);
+            // Nothing to edit or save, so leaving image does not ask to save
+            *rw_write!(properties) = ImageProperties::default();
+            *rw_write!(page.error) = Some(e.to_string());
+            dimension.set_label("");
+            page.col_flex.set_size(&page.image, 500);
+            page.row_flex.set_size(&page.col_flex, 400);
+            None
+        }
+    };
 
     if let Some(cont) = container {
-        let properties_file = utils::get_properties_path(&image_info);
-
-        let read = fs::read_to_string(&properties_file).unwrap_or("{}".to_owned());
-        let read = match serde_json::from_str::<ImagePropertiesFile>(&read) {
-            Ok(r) => r,
+        *rw_write!(page.error) = None;
+        let read = match properties_store::read(image_info) {
+            Ok(r) => r.unwrap_or_default(),
             Err(e) => {
-                Result::<(), _>::Err(e).warn_log("Config is corrupt");
-                fs::remove_file(&properties_file)
+                Result::<(), _>::Err(e).warn_log("Properties of image are corrupt");
+                properties_store::remove(image_info)
                     .warn_log("Failed to delete image properties file!");
                 ImagePropertiesFile::default()
             }
         };
 
         let mut properties = rw_write!(cont.properties);
-        properties.merge(read, &tag.value(), &tag2.value());
+        properties.merge(read, &controls.tag.value(), &controls.tag2.value());
         properties.is_saved = true;
 
-        name_prefix.set_value(&properties.name_prefix);
-        quote.set_value(&properties.quote);
-        subquote.set_value(&properties.subquote);
-        subquote2.set_value(&properties.subquote2);
-        tag.set_value(&properties.tag);
-        tag2.set_value(&properties.tag2);
-
-        quote_position.set_range(0.0, properties.original_dimension.1);
-        quote_position.set_value(properties.quote_position);
-        quote_position_slider.set_range(0.0, properties.original_dimension.1);
-        quote_position_slider.set_value(properties.quote_position);
-
-        subquote_position.set_range(0.0, properties.original_dimension.1);
-        subquote_position.set_value(properties.subquote_position);
-        subquote_position_slider.set_range(0.0, properties.original_dimension.1);
-        subquote_position_slider.set_value(properties.subquote_position);
-
-        subquote2_position.set_range(0.0, properties.original_dimension.1);
-        subquote2_position.set_value(properties.subquote2_position);
-        subquote2_position_slider.set_range(0.0, properties.original_dimension.1);
-        subquote2_position_slider.set_value(properties.subquote2_position);
-
-        tag_position.set_range(0.0, properties.original_dimension.1);
-        tag_position.set_value(properties.tag_position);
-        tag_position_slider.set_range(0.0, properties.original_dimension.1);
-        tag_position_slider.set_value(properties.tag_position);
-
-        tag2_position.set_range(0.0, properties.original_dimension.1);
-        tag2_position.set_value(properties.tag2_position);
-        tag2_position_slider.set_range(0.0, properties.original_dimension.1);
-        tag2_position_slider.set_value(properties.tag2_position);
-
-        utils::set_color_btn_rgba(properties.translucent_layer_color, layer_rgb);
-        layer_alpha.set_value(properties.translucent_layer_color[3] as f64);
+        controls.fill(&properties);
 
         dimension.set_label(&format!(
             "[{}x{}]",
@@ -353,23 +404,125 @@ fn load_image(
         page.row_flex.set_size(&page.col_flex, width as i32);
         page.col_flex.recalc();
         page.row_flex.recalc();
-        cont.redraw_to_buffer();
+        cont.redraw_to_buffer(&|| false);
     }
-    flush_buffer(&app_sender, &container);
+    flush_buffer(app_sender, container, page, &|| false);
+}
+
+/// Controls of main window which show properties of open image
+struct Controls {
+    name_prefix: Input,
+    quote: MultilineInput,
+    subquote: MultilineInput,
+    subquote2: MultilineInput,
+    tag: Input,
+    tag2: Input,
+    layer_rgb: Button,
+    layer_alpha: Spinner,
+    quote_position: Spinner,
+    subquote_position: Spinner,
+    subquote2_position: Spinner,
+    tag_position: Spinner,
+    tag2_position: Spinner,
+    quote_position_slider: Slider,
+    subquote_position_slider: Slider,
+    subquote2_position_slider: Slider,
+    tag_position_slider: Slider,
+    tag2_position_slider: Slider,
 }
 
+impl Controls {
+    fn new(main_win: &MainWindow) -> Self {
+        Self {
+            name_prefix: main_win.name_prefix.clone(),
+            quote: main_win.quote.clone(),
+            subquote: main_win.subquote.clone(),
+            subquote2: main_win.subquote2.clone(),
+            tag: main_win.tag.clone(),
+            tag2: main_win.tag2.clone(),
+            layer_rgb: main_win.translucent_layer_rgb.clone(),
+            layer_alpha: main_win.translucent_layer_alpha.clone(),
+            quote_position: main_win.quote_position.clone(),
+            subquote_position: main_win.subquote_position.clone(),
+            subquote2_position: main_win.subquote2_position.clone(),
+            tag_position: main_win.tag_position.clone(),
+            tag2_position: main_win.tag2_position.clone(),
+            quote_position_slider: main_win.quote_position_slider.clone(),
+            subquote_position_slider: main_win.subquote_position_slider.clone(),
+            subquote2_position_slider: main_win.subquote2_position_slider.clone(),
+            tag_position_slider: main_win.tag_position_slider.clone(),
+            tag2_position_slider: main_win.tag2_position_slider.clone(),
+        }
+    }
+
+    /// Show values of properties in controls
+    fn fill(&mut self, properties: &ImageProperties) {
+        self.name_prefix.set_value(&properties.name_prefix);
+        self.quote.set_value(&properties.quote);
+        self.subquote.set_value(&properties.subquote);
+        self.subquote2.set_value(&properties.subquote2);
+        self.tag.set_value(&properties.tag);
+        self.tag2.set_value(&properties.tag2);
+
+        self.quote_position
+            .set_range(0.0, properties.original_dimension.1);
+        self.quote_position.set_value(properties.quote_position);
+        self.quote_position_slider
+            .set_range(0.0, properties.original_dimension.1);
+        self.quote_position_slider
+            .set_value(properties.quote_position);
+
+        self.subquote_position
+            .set_range(0.0, properties.original_dimension.1);
+        self.subquote_position
+            .set_value(properties.subquote_position);
+        self.subquote_position_slider
+            .set_range(0.0, properties.original_dimension.1);
+        self.subquote_position_slider
+            .set_value(properties.subquote_position);
+
+        self.subquote2_position
+            .set_range(0.0, properties.original_dimension.1);
+        self.subquote2_position
+            .set_value(properties.subquote2_position);
+        self.subquote2_position_slider
+            .set_range(0.0, properties.original_dimension.1);
+        self.subquote2_position_slider
+            .set_value(properties.subquote2_position);
+
+        self.tag_position
+            .set_range(0.0, properties.original_dimension.1);
+        self.tag_position.set_value(properties.tag_position);
+        self.tag_position_slider
+            .set_range(0.0, properties.original_dimension.1);
+        self.tag_position_slider.set_value(properties.tag_position);
+
+        self.tag2_position
+            .set_range(0.0, properties.original_dimension.1);
+        self.tag2_position.set_value(properties.tag2_position);
+        self.tag2_position_slider
+            .set_range(0.0, properties.original_dimension.1);
+        self.tag2_position_slider
+            .set_value(properties.tag2_position);
+
+        utils::set_color_btn_rgba(properties.translucent_layer_color, &mut self.layer_rgb);
+        self.layer_alpha
+            .set_value(properties.translucent_layer_color[3] as f64);
+    }
+}
+
+/// Most unsupported images listed in details
+const UNSUPPORTED_SHOWN: usize = 10;
+
 fn show_images_details(images_list: Arc<RwLock<Vec<ImageInfo>>>) {
     let mut image_with_quote: usize = 0;
     let mut image_without_quote: usize = 0;
 
     let list = rw_read!(images_list);
     for image_info in list.iter() {
-        let properties_file = utils::get_properties_path(&image_info);
-
-        let read = fs::read_to_string(&properties_file).unwrap_or("{}".to_owned());
-        let read = match serde_json::from_str::<ImagePropertiesFile>(&read) {
-            Ok(r) => r,
-            Err(_) => {
+        let read = match properties_store::read(image_info) {
+            Ok(Some(r)) => r,
+            _ => {
                 image_without_quote += 1;
                 continue;
             }
@@ -386,20 +539,81 @@ fn show_images_details(images_list: Arc<RwLock<Vec<ImageInfo>>>) {
         }
     }
 
-    utils::show_message(&format!(
+    let mut details = format!(
         "With Quote: {}\nWithout Quote: {}",
         image_with_quote, image_without_quote
-    ));
+    );
+    let unsupported = image_list::unsupported();
+    if !unsupported.is_empty() {
+        details += &format!("\n\nNot Supported: {}", unsupported.len());
+        for image in unsupported.iter().take(UNSUPPORTED_SHOWN) {
+            details += &format!("\n{} ({})", image.path.display(), image.format);
+        }
+        if unsupported.len() > UNSUPPORTED_SHOWN {
+            details += &format!("\n...and {} more", unsupported.len() - UNSUPPORTED_SHOWN);
+        }
+    }
+    utils::show_message(&details);
+}
+
+/// Warn in status if some text is hard to read on image
+fn show_legibility(status: &mut Frame, container: &Option<ImageContainer>) {
+    let minimum = rw_read!(globals::CONFIG).minimum_contrast_ratio;
+    match container.as_ref().and_then(|cont| cont.legibility()) {
+        Some((element, ratio)) if ratio < minimum => {
+            status.set_label(&format!("Contrast {:.1}:1", ratio));
+            status.set_tooltip(&format!("{} is hard to read on this image", element.name()));
+        }
+        _ => {
+            status.set_label("");
+            status.set_tooltip("");
+        }
+    }
 }
 
 /// Flush the Buffer from image container to drawing buffer for fltk
 // for drawing buffer for fltk (check in main.rs)
-fn flush_buffer(app_sender: &app::Sender<crate::AppMessage>, container: &Option<ImageContainer>) {
+fn flush_buffer(
+    app_sender: &app::Sender<crate::AppMessage>,
+    container: &mut Option<ImageContainer>,
+    page: &Page,
+    cancelled: &dyn Fn() -> bool,
+) {
     match container {
         Some(cont) => {
-            app_sender.send(AppMessage::RedrawMainWindowImage(Some(
-                cont.buffer.as_rgb8().unwrap().as_raw().to_owned(),
-            )));
+            let view = *rw_read!(page.view);
+            let data = match view.zoom {
+                Zoom::Fit => cont.buffer.to_rgb8().into_raw(),
+                zoom => {
+                    let (page_width, page_height) = rw_read!(cont.properties).dimension;
+                    let scale = if zoom == Zoom::Double { 2.0 } else { 1.0 };
+                    let export = match cont.export_preview(cancelled) {
+                        Some(export) => export,
+                        None => return,
+                    };
+                    let (width, height) = export.dimensions();
+
+                    // Part of exported image visible in page
+                    let (x, y) = (
+                        ((view.pan.0 / scale) as u32).min(width),
+                        ((view.pan.1 / scale) as u32).min(height),
+                    );
+                    let (w, h) = (
+                        ((page_width / scale).ceil() as u32).min(width - x),
+                        ((page_height / scale).ceil() as u32).min(height - y),
+                    );
+                    let mut region = export.crop_imm(x, y, w, h).to_rgb8();
+                    if zoom == Zoom::Double {
+                        region =
+                            imageops::resize(&region, w * 2, h * 2, imageops::FilterType::Nearest);
+                    }
+
+                    let mut page_image = RgbImage::new(page_width as u32, page_height as u32);
+                    imageops::replace(&mut page_image, &region, 0, 0);
+                    page_image.into_raw()
+                }
+            };
+            app_sender.send(AppMessage::RedrawMainWindowImage(Some(data)));
         }
         None => {
             app_sender.send(AppMessage::RedrawMainWindowImage(None));
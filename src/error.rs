@@ -0,0 +1,162 @@
+/*
+    This file is part of Post Maker.
+    Post Maker is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+    Post Maker is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with Post Maker.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Errors of the program, and where reported errors go.
+//!
+//! Reporter is set for whole program, and can be changed for one thread, so batch work on a
+//! background thread can collect its errors and show them once at end.
+use crate::{dialog, globals, result_ext::ResultExt, AppMessage};
+use lazy_static::lazy_static;
+use log::Level;
+use std::{
+    cell::RefCell,
+    fmt, io,
+    sync::{Arc, RwLock},
+};
+
+lazy_static! {
+    static ref REPORTER: RwLock<Reporter> = RwLock::new(Reporter::Dialog);
+}
+
+thread_local! {
+    /// Reporter of this thread, in place of reporter of program
+    static THREAD_REPORTER: RefCell<Option<Reporter>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug)]
+pub(crate) enum Error {
+    /// Reading or writing file failed, with what was done
+    Io(String, io::Error),
+    /// Image could not be read
+    Decode(String),
+    /// Image could not be made
+    Encode(String),
+    /// Config file is corrupt
+    Config(String),
+    /// Saved file of program, like properties, index or project, can't be read
+    Corrupt(String),
+    /// File picked by user has nothing to use, or is not valid
+    Input(String),
+    /// Font could not be loaded
+    Font(String),
+    /// No image is opened to work on
+    NoImage,
+    /// Work was stopped before it was done
+    Stopped,
+    /// Some items of work failed, with name of every item
+    Batch(String, Vec<(String, Error)>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(action, e) => write!(f, "{}: {}", action, e),
+            Self::Decode(e)
+            | Self::Encode(e)
+            | Self::Config(e)
+            | Self::Corrupt(e)
+            | Self::Input(e)
+            | Self::Font(e) => write!(f, "{}", e),
+            Self::NoImage => write!(f, "No image is opened"),
+            Self::Stopped => write!(f, "Work was stopped"),
+            Self::Batch(msg, errors) => {
+                write!(f, "{}:", msg)?;
+                for (name, e) in errors {
+                    write!(f, "\n{}: {}", name, e)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Where reported errors go, they are always logged too
+#[derive(Debug, Clone)]
+pub(crate) enum Reporter {
+    /// Alert dialog on main window
+    Dialog,
+    /// Status bar of main window, which does not stop work of user
+    Status,
+    /// Only log file and terminal, to run without dialogs
+    Log,
+    /// Kept in list, to be shown together
+    Collect(Arc<RwLock<Vec<String>>>),
+}
+
+/// Set reporter of whole program
+pub(crate) fn set_reporter(reporter: Reporter) {
+    *rw_write!(REPORTER) = reporter;
+}
+
+/// Set reporter of this thread, `None` to use reporter of program again
+pub(crate) fn set_thread_reporter(reporter: Option<Reporter>) {
+    THREAD_REPORTER.with(|r| *r.borrow_mut() = reporter);
+}
+
+fn reporter() -> Reporter {
+    THREAD_REPORTER
+        .with(|r| r.borrow().clone())
+        .unwrap_or_else(|| rw_read!(REPORTER).clone())
+}
+
+/// Report failure with message for user and details for log
+pub(crate) fn report(level: Level, msg: &str, details: &str) {
+    log!(level, "{}\n{}", msg, details);
+    match reporter() {
+        Reporter::Dialog => send(AppMessage::Alert(msg.to_owned())),
+        Reporter::Status => send(AppMessage::Status(msg.to_owned())),
+        Reporter::Log => (),
+        Reporter::Collect(list) => {
+            let detail = details.lines().next().unwrap_or_default();
+            rw_write!(list).push(format!("{} {}", msg, detail));
+        }
+    }
+}
+
+/// Report error after which program can't go on. Program is closed after user sees it, unless
+/// errors are only logged
+pub(crate) fn report_panic(msg: &str, details: &str) {
+    error!("{}\n{}", msg, details);
+    // Locks are not used with `rw_read!`, which reports its own panic if lock is poisoned
+    let reporter = THREAD_REPORTER
+        .with(|r| r.borrow().clone())
+        .unwrap_or_else(|| match REPORTER.read() {
+            Ok(reporter) => reporter.clone(),
+            Err(e) => e.into_inner().clone(),
+        });
+    if matches!(reporter, Reporter::Log) {
+        return;
+    }
+    let sender = match globals::MAIN_SENDER.read() {
+        Ok(sender) => sender.clone(),
+        Err(e) => e.into_inner().clone(),
+    };
+    if let Some(sender) = sender {
+        sender.send(AppMessage::ProgramPanicMessage(msg.to_owned()));
+    }
+}
+
+fn send(msg: AppMessage) {
+    match &*rw_read!(globals::MAIN_SENDER) {
+        Some(sender) => sender.send(msg),
+        // Before main window is made only main thread runs, so dialog can be shown here
+        None => {
+            if let AppMessage::Alert(msg) = msg {
+                dialog::alert_default(&msg);
+            }
+        }
+    }
+}
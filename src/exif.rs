@@ -0,0 +1,414 @@
+/*
+    This file is part of Post Maker.
+    Post Maker is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+    Post Maker is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with Post Maker.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Minimal reader of EXIF data of JPEG images, only for the few tags which are used, and writer
+//! of EXIF with artist and copyright for exported images
+use crate::error::Error;
+use image::DynamicImage;
+use std::{fs::File, io::Read, path::Path};
+
+/// Most bytes read from start of image to find EXIF data
+const HEADER_LIMIT: u64 = 256 * 1024;
+
+/// Tag which points to EXIF sub IFD
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_DATE_TIME: u16 = 0x0132;
+const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+const TAG_ORIENTATION: u16 = 0x0112;
+const TAG_ARTIST: u16 = 0x013B;
+const TAG_COPYRIGHT: u16 = 0x8298;
+
+/// Type of entry which has text
+const TYPE_ASCII: u16 = 2;
+
+/// TIFF structure of EXIF segment
+pub(crate) struct Exif {
+    data: Vec<u8>,
+    little_endian: bool,
+}
+
+impl Exif {
+    /// Read EXIF data of image, `None` if it is not a JPEG or has no EXIF
+    pub(crate) fn read(path: &Path) -> Option<Self> {
+        Self::from_jpeg(&read_header(path)?)
+    }
+
+    /// Find APP1 Exif segment in start of JPEG
+    pub(crate) fn from_jpeg(data: &[u8]) -> Option<Self> {
+        let (_, segment) = jpeg_segments(data)
+            .into_iter()
+            .find(|(marker, segment)| *marker == 0xE1 && segment.starts_with(b"Exif\0\0"))?;
+        let tiff = segment[6..].to_vec();
+        let little_endian = match tiff.get(0..2)? {
+            b"II" => true,
+            b"MM" => false,
+            _ => return None,
+        };
+        Some(Self {
+            data: tiff,
+            little_endian,
+        })
+    }
+
+    fn u16_at(&self, pos: usize) -> Option<u16> {
+        let bytes = [*self.data.get(pos)?, *self.data.get(pos + 1)?];
+        Some(if self.little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    }
+
+    fn u32_at(&self, pos: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.data.get(pos..pos + 4)?.try_into().ok()?;
+        Some(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    /// Position of first IFD
+    fn ifd0(&self) -> Option<usize> {
+        Some(self.u32_at(4)? as usize)
+    }
+
+    /// Position of EXIF sub IFD
+    fn exif_ifd(&self) -> Option<usize> {
+        let entry = self.entry(self.ifd0()?, TAG_EXIF_IFD)?;
+        Some(self.u32_at(entry + 8)? as usize)
+    }
+
+    /// Position of 12 byte entry of tag in IFD
+    fn entry(&self, ifd: usize, tag: u16) -> Option<usize> {
+        let count = self.u16_at(ifd)? as usize;
+        (0..count)
+            .map(|i| ifd + 2 + i * 12)
+            .find(|entry| self.u16_at(*entry) == Some(tag))
+    }
+
+    /// Text value of tag in IFD
+    fn ascii(&self, ifd: usize, tag: u16) -> Option<String> {
+        let entry = self.entry(ifd, tag)?;
+        let count = self.u32_at(entry + 4)? as usize;
+        let pos = if count <= 4 {
+            entry + 8
+        } else {
+            self.u32_at(entry + 8)? as usize
+        };
+        let bytes = self.data.get(pos..pos + count)?;
+        let text = String::from_utf8_lossy(bytes);
+        let text = text.trim_end_matches('\0').trim();
+        (!text.is_empty()).then(|| text.to_owned())
+    }
+
+    /// Date and time photo was taken as `YYYY:MM:DD HH:MM:SS`, or when it was last changed
+    pub(crate) fn capture_date(&self) -> Option<String> {
+        self.exif_ifd()
+            .and_then(|ifd| self.ascii(ifd, TAG_DATE_TIME_ORIGINAL))
+            .or_else(|| self.ascii(self.ifd0()?, TAG_DATE_TIME))
+    }
+
+    /// How image has to be turned to be upright, 1 to 8 as in EXIF. 1 is upright
+    pub(crate) fn orientation(&self) -> u16 {
+        self.ifd0()
+            .and_then(|ifd| self.entry(ifd, TAG_ORIENTATION))
+            .and_then(|entry| self.u16_at(entry + 8))
+            .filter(|orientation| (1..=8).contains(orientation))
+            .unwrap_or(1)
+    }
+
+    pub(crate) fn artist(&self) -> Option<String> {
+        self.ascii(self.ifd0()?, TAG_ARTIST)
+    }
+
+    pub(crate) fn copyright(&self) -> Option<String> {
+        self.ascii(self.ifd0()?, TAG_COPYRIGHT)
+    }
+}
+
+/// Read start of image, where JPEG keeps its metadata
+pub(crate) fn read_header(path: &Path) -> Option<Vec<u8>> {
+    let mut header = vec![];
+    File::open(path)
+        .ok()?
+        .take(HEADER_LIMIT)
+        .read_to_end(&mut header)
+        .ok()?;
+    Some(header)
+}
+
+/// Marker and data of every segment of JPEG before its image data
+pub(crate) fn jpeg_segments(data: &[u8]) -> Vec<(u8, &[u8])> {
+    let mut segments = vec![];
+    if data.get(0..2) != Some(&[0xFF, 0xD8]) {
+        return segments;
+    }
+    let mut pos = 2;
+    while let (Some(0xFF), Some(&marker)) = (data.get(pos), data.get(pos + 1)) {
+        // Start of scan, no more headers
+        if marker == 0xDA {
+            break;
+        }
+        let length = match (data.get(pos + 2), data.get(pos + 3)) {
+            (Some(&high), Some(&low)) => u16::from_be_bytes([high, low]) as usize,
+            _ => break,
+        };
+        match data.get(pos + 4..pos + 2 + length) {
+            Some(segment) => segments.push((marker, segment)),
+            None => break,
+        }
+        pos += 2 + length;
+    }
+    segments
+}
+
+/// Width and height of image when it is shown upright
+pub(crate) fn upright_dimensions(path: &Path) -> Option<(u32, u32)> {
+    let (width, height) = image::image_dimensions(path).ok()?;
+    match Exif::read(path).map_or(1, |exif| exif.orientation()) {
+        // Turned by quarter
+        5..=8 => Some((height, width)),
+        _ => Some((width, height)),
+    }
+}
+
+/// Turn image as EXIF orientation says, so it is shown upright
+pub(crate) fn apply_orientation(image: DynamicImage, orientation: u16) -> DynamicImage {
+    match orientation {
+        2 => image.fliph(),
+        3 => image.rotate180(),
+        4 => image.flipv(),
+        5 => image.rotate90().fliph(),
+        6 => image.rotate90(),
+        7 => image.rotate270().fliph(),
+        8 => image.rotate270(),
+        _ => image,
+    }
+}
+
+/// Data of APP1 segment with only artist and copyright, `None` if both are not there.
+/// Location and other tags of source image are left out
+pub(crate) fn copyright_segment(artist: Option<&str>, copyright: Option<&str>) -> Option<Vec<u8>> {
+    let entries: Vec<(u16, &str)> = [(TAG_ARTIST, artist), (TAG_COPYRIGHT, copyright)]
+        .into_iter()
+        .filter_map(|(tag, text)| Some((tag, text?)))
+        .collect();
+    if entries.is_empty() {
+        return None;
+    }
+
+    // Little endian TIFF header, with IFD0 just after it and text after IFD0
+    let mut tiff = b"II".to_vec();
+    tiff.extend(42u16.to_le_bytes());
+    tiff.extend(8u32.to_le_bytes());
+    tiff.extend((entries.len() as u16).to_le_bytes());
+    let mut text_data = vec![];
+    let text_start = 8 + 2 + entries.len() * 12 + 4;
+    for (tag, text) in &entries {
+        let mut bytes = text.as_bytes().to_vec();
+        bytes.push(0);
+        tiff.extend(tag.to_le_bytes());
+        tiff.extend(TYPE_ASCII.to_le_bytes());
+        tiff.extend((bytes.len() as u32).to_le_bytes());
+        if bytes.len() <= 4 {
+            bytes.resize(4, 0);
+            tiff.extend(bytes);
+        } else {
+            tiff.extend(((text_start + text_data.len()) as u32).to_le_bytes());
+            text_data.extend(bytes);
+        }
+    }
+    // No next IFD
+    tiff.extend(0u32.to_le_bytes());
+    tiff.extend(text_data);
+
+    let mut segment = b"Exif\0\0".to_vec();
+    segment.extend(tiff);
+    Some(segment)
+}
+
+/// Add eXIf chunk with data of EXIF segment to encoded PNG
+pub(crate) fn embed_in_png(png: Vec<u8>, segment: &[u8]) -> Result<Vec<u8>, Error> {
+    // PNG keeps only TIFF structure, without `Exif` signature of JPEG
+    let tiff = segment.strip_prefix(b"Exif\0\0").unwrap_or(segment);
+    insert_png_chunk(png, b"eXIf", tiff)
+}
+
+/// Add chunk to encoded PNG, just after its IHDR chunk
+pub(crate) fn insert_png_chunk(
+    png: Vec<u8>,
+    chunk_type: &[u8; 4],
+    data: &[u8],
+) -> Result<Vec<u8>, Error> {
+    // Signature, and IHDR which is always 13 bytes with length, type and CRC
+    const IHDR_END: usize = 8 + 4 + 4 + 13 + 4;
+    if png.len() < IHDR_END || &png[12..16] != b"IHDR" {
+        return Err(Error::Encode("Exported PNG is not valid".to_owned()));
+    }
+
+    let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+    chunk.extend_from_slice(chunk_type);
+    chunk.extend_from_slice(data);
+    chunk.extend(crc32fast::hash(&chunk[4..]).to_be_bytes());
+
+    let mut output = png;
+    output.splice(IHDR_END..IHDR_END, chunk);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// JPEG with segments, and start of scan after them
+    fn jpeg(segments: &[(u8, &[u8])]) -> Vec<u8> {
+        let mut data = vec![0xFF, 0xD8];
+        for (marker, segment) in segments {
+            data.extend([0xFF, *marker]);
+            data.extend(((segment.len() + 2) as u16).to_be_bytes());
+            data.extend(*segment);
+        }
+        data.extend([0xFF, 0xDA, 0x00, 0x02]);
+        data
+    }
+
+    /// EXIF segment with only orientation in IFD0, big endian
+    fn orientation_segment(orientation: u16) -> Vec<u8> {
+        let mut segment = b"Exif\0\0MM".to_vec();
+        segment.extend(42u16.to_be_bytes());
+        segment.extend(8u32.to_be_bytes());
+        segment.extend(1u16.to_be_bytes());
+        segment.extend(TAG_ORIENTATION.to_be_bytes());
+        // SHORT, one value
+        segment.extend(3u16.to_be_bytes());
+        segment.extend(1u32.to_be_bytes());
+        segment.extend(orientation.to_be_bytes());
+        segment.extend([0, 0]);
+        segment.extend(0u32.to_be_bytes());
+        segment
+    }
+
+    #[test]
+    fn segments_are_read_until_start_of_scan() {
+        let data = jpeg(&[(0xE0, b"JFIF\0"), (0xE1, b"Exif\0\0")]);
+        let segments = jpeg_segments(&data);
+        assert_eq!(
+            segments,
+            vec![(0xE0, &b"JFIF\0"[..]), (0xE1, &b"Exif\0\0"[..])]
+        );
+    }
+
+    #[test]
+    fn data_which_is_not_jpeg_has_no_segments() {
+        assert!(jpeg_segments(b"").is_empty());
+        assert!(jpeg_segments(b"\x89PNG\r\n").is_empty());
+    }
+
+    #[test]
+    fn truncated_segment_is_left_out() {
+        let mut data = jpeg(&[(0xE0, b"JFIF\0"), (0xE1, b"Exif\0\0II")]);
+        data.truncate(data.len() - 6);
+        assert_eq!(jpeg_segments(&data), vec![(0xE0, &b"JFIF\0"[..])]);
+        // Length is cut off
+        assert!(jpeg_segments(&[0xFF, 0xD8, 0xFF, 0xE1, 0x00]).is_empty());
+    }
+
+    #[test]
+    fn segment_with_invalid_length_stops_reading() {
+        for length in [0u16, 1] {
+            let mut data = vec![0xFF, 0xD8, 0xFF, 0xE1];
+            data.extend(length.to_be_bytes());
+            data.extend(b"Exif\0\0");
+            assert!(jpeg_segments(&data).is_empty());
+        }
+        // Garbage in place of next marker
+        let mut data = jpeg(&[(0xE0, b"JFIF\0")]);
+        data.insert(11, 0x00);
+        assert_eq!(jpeg_segments(&data).len(), 1);
+    }
+
+    #[test]
+    fn orientation_is_read() {
+        for orientation in 1..=8 {
+            let data = jpeg(&[(0xE1, &orientation_segment(orientation))]);
+            let exif = Exif::from_jpeg(&data).unwrap();
+            assert_eq!(exif.orientation(), orientation);
+        }
+        let data = jpeg(&[(0xE1, &orientation_segment(9))]);
+        assert_eq!(Exif::from_jpeg(&data).unwrap().orientation(), 1);
+    }
+
+    #[test]
+    fn malformed_exif_has_no_values() {
+        // IFD0 points past end of data
+        let mut segment = orientation_segment(6);
+        segment[10..14].copy_from_slice(&1000u32.to_be_bytes());
+        let exif = Exif::from_jpeg(&jpeg(&[(0xE1, &segment)])).unwrap();
+        assert_eq!(exif.orientation(), 1);
+        assert_eq!(exif.capture_date(), None);
+        assert_eq!(exif.artist(), None);
+
+        // Count of entries is more than there is data for
+        let mut segment = orientation_segment(6);
+        segment.truncate(20);
+        let exif = Exif::from_jpeg(&jpeg(&[(0xE1, &segment)])).unwrap();
+        assert_eq!(exif.orientation(), 1);
+
+        // Unknown byte order
+        let mut segment = orientation_segment(6);
+        segment[6..8].copy_from_slice(b"XX");
+        assert!(Exif::from_jpeg(&jpeg(&[(0xE1, &segment)])).is_none());
+    }
+
+    #[test]
+    fn copyright_segment_is_read_back() {
+        let segment = copyright_segment(Some("Ann"), Some("(c) 2024 Ann Example")).unwrap();
+        let exif = Exif::from_jpeg(&jpeg(&[(0xE1, &segment)])).unwrap();
+        assert_eq!(exif.artist().as_deref(), Some("Ann"));
+        assert_eq!(exif.copyright().as_deref(), Some("(c) 2024 Ann Example"));
+
+        let segment = copyright_segment(None, Some("CC BY")).unwrap();
+        let exif = Exif::from_jpeg(&jpeg(&[(0xE1, &segment)])).unwrap();
+        assert_eq!(exif.artist(), None);
+        assert_eq!(exif.copyright().as_deref(), Some("CC BY"));
+
+        assert!(copyright_segment(None, None).is_none());
+    }
+
+    #[test]
+    fn png_gets_exif_chunk_without_signature() {
+        use image::ImageEncoder;
+        let mut png = vec![];
+        image::codecs::png::PngEncoder::new(&mut png)
+            .write_image(&[255, 0, 0], 1, 1, image::ColorType::Rgb8)
+            .unwrap();
+        let segment = copyright_segment(None, Some("Ann")).unwrap();
+        let output = embed_in_png(png.clone(), &segment).unwrap();
+        assert_eq!(&output[..33], &png[..33]);
+        assert_eq!(&output[33..37], &((segment.len() - 6) as u32).to_be_bytes());
+        assert_eq!(&output[37..41], b"eXIf");
+        assert_eq!(&output[41..43], b"II");
+        assert!(image::load_from_memory(&output).is_ok());
+        assert!(embed_in_png(vec![0; 40], &segment).is_err());
+    }
+
+    #[test]
+    fn text_past_end_of_data_is_not_read() {
+        let mut segment = copyright_segment(Some("Long name of artist"), None).unwrap();
+        segment.truncate(segment.len() - 5);
+        let exif = Exif::from_jpeg(&jpeg(&[(0xE1, &segment)])).unwrap();
+        assert_eq!(exif.artist(), None);
+    }
+}
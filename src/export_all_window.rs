@@ -14,41 +14,121 @@
 
 //! Picker to pick config if multiple configs are present or defalut config is not present
 use crate::{
-    config, dialog, globals,
+    config, dialog,
+    error::{self, Reporter},
+    export_report::{self, Entry, Status},
+    globals, image_list,
+    manifest::{self, Manifests},
+    properties_store,
     result_ext::ResultExt,
     utils::{self, ImageContainer, ImageInfo, ImageProperties, ImagePropertiesFile},
 };
 use bichannel::Channel;
 use fltk::{
     app::{self},
-    button::Button,
+    browser::Browser,
+    button::{Button, CheckButton},
     enums,
     frame::Frame,
     group::Flex,
     image::SvgImage,
+    input::{Input, IntInput},
+    menu::Choice,
     misc::Progress,
     prelude::*,
     window::Window,
 };
 use std::{
-    fs::File,
-    sync::{Arc, RwLock},
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Arc, RwLock,
+    },
     thread,
+    time::Duration,
 };
 
+/// Most problems listed in alert at end of export, rest are only in log
+const PROBLEMS_SHOWN: usize = 10;
+
+/// Which images of folder are exported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ExportScope {
+    All,
+    /// Image open in main window
+    Selected,
+    /// Image or its properties modified after it was exported
+    Changed,
+    /// File name or tag matches pattern
+    Matching,
+    /// Position in list of images, from 1
+    Range,
+}
+
+impl ExportScope {
+    const ALL: [Self; 5] = [
+        Self::All,
+        Self::Selected,
+        Self::Changed,
+        Self::Matching,
+        Self::Range,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::All => "All images",
+            Self::Selected => "Selected image",
+            Self::Changed => "Changed since last export",
+            Self::Matching => "Matching name or tag",
+            Self::Range => "Range",
+        }
+    }
+}
+
+/// Options picked in window before starting export
+#[derive(Debug, Clone)]
+pub(crate) struct ExportOptions {
+    pub(crate) scope: ExportScope,
+    pub(crate) pattern: String,
+    /// First and last position, inclusive
+    pub(crate) range: (usize, usize),
+    /// Export images with empty quote, with only layer and tags
+    pub(crate) include_no_quote: bool,
+}
+
 pub(crate) struct ExportAllWindow {
     pub(crate) win: Window,
+    options: Flex,
+    scope: Choice,
+    pattern: Input,
+    range_from: IntInput,
+    range_to: IntInput,
+    include_no_quote: CheckButton,
     pub(crate) progress: Progress,
     pub(crate) image_name: Frame,
+    start_btn: Button,
     pub(crate) close_btn: Button,
+    /// Result of every exported image
+    pub(crate) report: Browser,
     pub(crate) images_list: Arc<RwLock<Vec<ImageInfo>>>,
+    /// Properties of image open in main window
+    properties: Arc<RwLock<ImageProperties>>,
     pub(crate) channel: Arc<RwLock<Option<Channel<ThreadMessage, ThreadMessage>>>>,
+    /// Progress of running export, widgets are changed only on main thread
+    progress_receiver: Option<mpsc::Receiver<ExportProgress>>,
     pub(crate) finished: Arc<RwLock<bool>>,
+    /// Start button was pressed
+    start: Arc<RwLock<bool>>,
 }
 
 impl ExportAllWindow {
-    pub(crate) fn new(images_list: Arc<RwLock<Vec<ImageInfo>>>) -> Self {
-        let mut win = Window::new(0, 0, 500, 130, "Export All").center_screen();
+    pub(crate) fn new(
+        images_list: Arc<RwLock<Vec<ImageInfo>>>,
+        properties: Arc<RwLock<ImageProperties>>,
+    ) -> Self {
+        let mut win = Window::new(0, 0, 640, 490, "Export All").center_screen();
         win.set_icon(Some(
             SvgImage::from_data(globals::ICON.to_str().unwrap()).unwrap(),
         ));
@@ -67,11 +147,55 @@ impl ExportAllWindow {
         let mut panel_flex = Flex::default().row();
         panel_flex.set_size(&Frame::default(), 1);
         Frame::default()
-            .with_label("Exporting all with quotes")
+            .with_label("Export images with quotes")
             .with_align(enums::Align::Left | enums::Align::Inside);
         panel_flex.end();
         main_flex.set_size(&panel_flex, 25);
 
+        // options
+        let mut options = Flex::default().column();
+        let mut panel_flex = Flex::default().row();
+        panel_flex.set_size(
+            &Frame::default()
+                .with_label("Images:")
+                .with_align(enums::Align::Left | enums::Align::Inside),
+            70,
+        );
+        let mut scope = Choice::default();
+        for s in ExportScope::ALL {
+            scope.add_choice(s.label());
+        }
+        scope.set_value(0);
+        panel_flex.set_size(&scope, 220);
+        let mut pattern = Input::default();
+        pattern.set_tooltip("Part of file name or tag, * and ? as wildcards");
+        pattern.deactivate();
+        panel_flex.set_size(&Frame::default(), 5);
+        panel_flex.end();
+        options.set_size(&panel_flex, 30);
+
+        let mut panel_flex = Flex::default().row();
+        panel_flex.set_size(
+            &Frame::default()
+                .with_label("Range:")
+                .with_align(enums::Align::Left | enums::Align::Inside),
+            70,
+        );
+        let mut range_from = IntInput::default();
+        range_from.deactivate();
+        panel_flex.set_size(&range_from, 80);
+        panel_flex.set_size(&Frame::default().with_label("to"), 30);
+        let mut range_to = IntInput::default();
+        range_to.deactivate();
+        panel_flex.set_size(&range_to, 80);
+        Frame::default();
+        let include_no_quote = CheckButton::default().with_label("Include images without quote");
+        panel_flex.set_size(&include_no_quote, 230);
+        panel_flex.end();
+        options.set_size(&panel_flex, 30);
+        options.end();
+        main_flex.set_size(&options, 65);
+
         //image name
         let mut panel_flex = Flex::default().row();
         panel_flex.set_size(&Frame::default(), 1);
@@ -84,21 +208,33 @@ impl ExportAllWindow {
         // progress bar
         let mut panel_flex = Flex::default().row();
         Frame::default();
-        let mut progress = Progress::default().with_label("Exporting...");
+        let mut progress = Progress::default();
         progress.set_maximum(1.0);
         progress.set_value(0.0);
         progress.set_frame(enums::FrameType::ThinDownBox);
         progress.set_selection_color(progress_color);
 
         Frame::default();
-        panel_flex.set_size(&progress, 490);
+        panel_flex.set_size(&progress, 630);
         panel_flex.end();
         main_flex.set_size(&panel_flex, 30);
 
-        //close button
+        // report of exported images
+        let mut panel_flex = Flex::default().row();
+        panel_flex.set_size(&Frame::default(), 5);
+        let mut report = Browser::default();
+        report.set_column_widths(&[200, 150, 90, 80]);
+        report.set_column_char('\t');
+        report.set_format_char('\u{1}');
+        panel_flex.set_size(&Frame::default(), 5);
+        panel_flex.end();
+
+        //start and close button
         let mut panel_flex = Flex::default().row();
         Frame::default();
-        let close_btn = Button::default().with_label("Cancel");
+        let start_btn = Button::default().with_label("Start");
+        panel_flex.set_size(&start_btn, 100);
+        let close_btn = Button::default().with_label("Close");
         panel_flex.set_size(&Frame::default(), 1);
         panel_flex.set_size(&close_btn, 100);
         panel_flex.end();
@@ -111,30 +247,51 @@ impl ExportAllWindow {
 
         let mut config_picker = Self {
             win,
+            options,
+            scope,
+            pattern,
+            range_from,
+            range_to,
+            include_no_quote,
             progress,
             image_name,
+            start_btn,
             close_btn,
+            report,
             images_list,
+            properties,
             channel: Arc::new(RwLock::new(None)),
-            finished: Arc::new(RwLock::new(false)),
+            progress_receiver: None,
+            finished: Arc::new(RwLock::new(true)),
+            start: Arc::new(RwLock::new(false)),
         };
         config_picker.event();
 
         config_picker
     }
 
+    /// Show window to pick which images to export
     pub(crate) fn export(&mut self) {
         self.image_name.set_label("");
-        self.progress.set_label("Exporting...");
-        self.close_btn.set_label("Cancel");
+        self.report.clear();
+        self.progress.set_label("");
+        self.close_btn.set_label("Close");
         self.progress.set_maximum(1.0);
         self.progress.set_value(0.0);
-        *rw_write!(self.finished) = false;
+        self.options.activate();
+        self.start_btn.activate();
+        let total = rw_read!(self.images_list).len();
+        self.range_from.set_value("1");
+        self.range_to.set_value(&total.to_string());
+        *rw_write!(self.finished) = true;
+        *rw_write!(self.start) = false;
+        *rw_write!(self.channel) = None;
+        self.progress_receiver = None;
         self.win.show();
-        let (left, right) = bichannel::channel();
-        *rw_write!(self.channel) = Some(left);
-        spawn_export_thread(self, right);
         while self.win.shown() {
+            if std::mem::take(&mut *rw_write!(self.start)) {
+                self.start_export();
+            }
             if let Some(channel) = &*rw_read!(self.channel) {
                 if let Ok(msg) = channel.try_recv() {
                     match msg {
@@ -145,12 +302,107 @@ impl ExportAllWindow {
                     }
                 }
             }
+            self.show_progress();
             app::wait();
         }
     }
 
+    /// Show progress sent by export threads
+    fn show_progress(&mut self) {
+        let receiver = match &self.progress_receiver {
+            Some(receiver) => receiver,
+            None => return,
+        };
+        while let Ok(msg) = receiver.try_recv() {
+            match msg {
+                ExportProgress::Started(total) => {
+                    self.progress.set_maximum(total as f64);
+                    self.progress.set_value(0.0);
+                }
+                ExportProgress::Image(name) => self.image_name.set_label(&name),
+                ExportProgress::Exported { row, done, total } => {
+                    self.report.add(&row);
+                    self.report.bottom_line(self.report.size());
+                    self.progress.set_value(done as f64);
+                    self.progress.set_label(&format!("[{}/{}]", done, total));
+                }
+                ExportProgress::Finished { summary, total } => {
+                    self.image_name.set_label(&summary);
+                    self.progress.set_value(total as f64);
+                    self.close_btn.set_label("Close");
+                    *rw_write!(self.finished) = true;
+                }
+            }
+            self.win.redraw();
+        }
+    }
+
+    /// Export images picked by options
+    fn start_export(&mut self) {
+        let options = ExportOptions {
+            scope: ExportScope::ALL
+                .get(self.scope.value().max(0) as usize)
+                .copied()
+                .unwrap_or(ExportScope::All),
+            pattern: self.pattern.value(),
+            range: (
+                self.range_from.value().parse().unwrap_or(1),
+                self.range_to.value().parse().unwrap_or(usize::MAX),
+            ),
+            include_no_quote: self.include_no_quote.is_checked(),
+        };
+        let properties = rw_read!(self.properties);
+        let selected = properties.image_info.as_ref().map(|info| info.path.clone());
+        // Edits of open image are exported even if they are not saved yet, and are not saved by
+        // export
+        let unsaved = (!properties.is_saved && selected.is_some())
+            .then(|| ImagePropertiesFile::from(&*properties));
+        drop(properties);
+        if options.scope == ExportScope::Selected && selected.is_none() {
+            utils::show_alert("No image is selected!");
+            return;
+        }
+
+        self.options.deactivate();
+        self.start_btn.deactivate();
+        self.progress.set_label("Exporting...");
+        self.close_btn.set_label("Cancel");
+        *rw_write!(self.finished) = false;
+        let (left, right) = bichannel::channel();
+        *rw_write!(self.channel) = Some(left);
+        let (sender, receiver) = mpsc::channel();
+        self.progress_receiver = Some(receiver);
+        spawn_export_thread(self, right, sender, options, selected, unsaved);
+    }
+
     // Set callbacks of elements
     fn event(&mut self) {
+        // Scope Choice
+        let mut pattern = self.pattern.clone();
+        let mut range_from = self.range_from.clone();
+        let mut range_to = self.range_to.clone();
+        self.scope.set_callback(move |f| {
+            let scope = ExportScope::ALL.get(f.value().max(0) as usize);
+            if scope == Some(&ExportScope::Matching) {
+                pattern.activate();
+            } else {
+                pattern.deactivate();
+            }
+            if scope == Some(&ExportScope::Range) {
+                range_from.activate();
+                range_to.activate();
+            } else {
+                range_from.deactivate();
+                range_to.deactivate();
+            }
+        });
+
+        // Start Button
+        let start = Arc::clone(&self.start);
+        self.start_btn.set_callback(move |_| {
+            *rw_write!(start) = true;
+        });
+
         // Close Button
         let channel = Arc::clone(&self.channel);
         let finished = Arc::clone(&self.finished);
@@ -185,84 +437,474 @@ pub(crate) enum ThreadMessage {
     HideWindow,
 }
 
+/// Progress of export, sent from export threads to window
+enum ExportProgress {
+    /// Count of images to export
+    Started(usize),
+    /// Name of image being exported
+    Image(String),
+    Exported {
+        /// Row of report
+        row: String,
+        done: usize,
+        total: usize,
+    },
+    Finished {
+        summary: String,
+        total: usize,
+    },
+}
+
+/// Send progress and wake main thread to show it
+fn send_progress(sender: &mpsc::Sender<ExportProgress>, progress: ExportProgress) {
+    if sender.send(progress).is_ok() {
+        app::awake();
+    }
+}
+
 fn spawn_export_thread(
     export_all: &mut ExportAllWindow,
     channel: Channel<ThreadMessage, ThreadMessage>,
+    sender: mpsc::Sender<ExportProgress>,
+    options: ExportOptions,
+    selected: Option<PathBuf>,
+    unsaved: Option<ImagePropertiesFile>,
 ) {
-    let mut win = export_all.win.clone();
-    let mut progress = export_all.progress.clone();
-    let mut image_name = export_all.image_name.clone();
-    let mut close_btn = export_all.close_btn.clone();
-    let finished = Arc::clone(&export_all.finished);
+    let unsaved = Arc::new(selected.clone().zip(unsaved));
     let images_list = Arc::clone(&export_all.images_list);
 
     thread::spawn(move || {
-        let total = rw_read!(images_list).len();
-        progress.set_maximum(total as f64);
-        progress.set_value(0.0);
+        // Problems are shown together at end, so they don't stop export
+        let problems = Arc::new(RwLock::new(vec![]));
+        error::set_thread_reporter(Some(Reporter::Collect(Arc::clone(&problems))));
+        // Position of images in loaded images, for index in name of exported images
+        let positions = Arc::new(image_list::positions());
+        let images = Arc::new(select_images(
+            &rw_read!(images_list),
+            &positions,
+            selected.as_deref(),
+            &options,
+        ));
+        let total = images.len();
+        send_progress(&sender, ExportProgress::Started(total));
 
-        let mut update_progress = |idx: usize| {
-            progress.set_value(idx as f64 + 1.0);
-            progress.set_label(&format!("[{}/{}]", idx + 1, total));
-        };
+        // Index of next image to export, count of images done and results of all workers
+        let next = Arc::new(AtomicUsize::new(0));
+        let done = Arc::new(AtomicUsize::new(0));
+        let stop = Arc::new(AtomicBool::new(false));
+        let entries: Arc<RwLock<Vec<Entry>>> = Arc::new(RwLock::new(vec![]));
+        let manifests = Arc::new(RwLock::new(Manifests::default()));
+        let config_hash = manifest::config_hash();
 
-        for (idx, image) in (*rw_read!(images_list)).iter().enumerate() {
-            image_name.set_label(
-                image
-                    .path
-                    .file_name()
-                    .unwrap_or_default()
-                    .to_str()
-                    .unwrap_or_default(),
-            );
-            let properties = Arc::new(RwLock::new(ImageProperties::default()));
-            let container = ImageContainer::new(image, properties);
-            let properties_file = utils::get_properties_path(image);
-            let read = match File::open(&properties_file) {
-                Ok(r) => r,
-                Err(_) => {
-                    update_progress(idx);
-                    continue;
-                }
-            };
-            let read = match serde_json::from_reader::<File, ImagePropertiesFile>(read) {
-                Ok(r) => r,
-                Err(_) => {
-                    update_progress(idx);
-                    continue;
-                }
-            };
+        let threads = rw_read!(globals::CONFIG)
+            .export_threads
+            .clamp(1, total.max(1));
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                let sender = sender.clone();
+                let images = Arc::clone(&images);
+                let next = Arc::clone(&next);
+                let done = Arc::clone(&done);
+                let stop = Arc::clone(&stop);
+                let entries = Arc::clone(&entries);
+                let manifests = Arc::clone(&manifests);
+                let include_no_quote = options.include_no_quote;
+                let unsaved = Arc::clone(&unsaved);
+                let positions = Arc::clone(&positions);
+                let problems = Arc::clone(&problems);
+                thread::spawn(move || {
+                    error::set_thread_reporter(Some(Reporter::Collect(problems)));
+                    loop {
+                        if stop.load(Ordering::SeqCst) {
+                            return;
+                        }
+                        let image = match images.get(next.fetch_add(1, Ordering::SeqCst)) {
+                            Some(image) => image,
+                            None => return,
+                        };
+                        let name = image.path.file_name().unwrap_or_default();
+                        send_progress(
+                            &sender,
+                            ExportProgress::Image(name.to_string_lossy().into_owned()),
+                        );
+
+                        let unsaved = unsaved
+                            .as_ref()
+                            .as_ref()
+                            .filter(|(path, _)| *path == image.path)
+                            .map(|(_, properties)| properties.clone());
+                        let index = positions.get(&image.path).copied().unwrap_or_default();
+                        let entry = export_image(
+                            image,
+                            index,
+                            unsaved,
+                            config_hash,
+                            &manifests,
+                            include_no_quote,
+                        );
+                        if let Some(e) = &entry.error {
+                            warn!("{}: {}", image.path.display(), e);
+                        }
+                        let row = entry.to_row();
+                        rw_write!(entries).push(entry);
 
-            rw_write!(container.properties).merge(read, "", "");
+                        let done = done.fetch_add(1, Ordering::SeqCst) + 1;
+                        send_progress(&sender, ExportProgress::Exported { row, done, total });
+                    }
+                })
+            })
+            .collect();
 
-            if rw_read!(container.properties).quote.trim().len() == 0 {
-                update_progress(idx);
-                continue;
+        while workers.iter().any(|worker| !worker.is_finished()) {
+            if let Ok(ThreadMessage::Stop) = channel.try_recv() {
+                stop.store(true, Ordering::SeqCst);
             }
+            thread::sleep(Duration::from_millis(50));
+        }
+        for worker in workers {
+            if worker.join().is_err() {
+                error!("Export thread panicked");
+            }
+        }
 
-            container.save();
+        rw_read!(manifests)
+            .save()
+            .warn_log("Failed to save export manifest!");
+        properties_store::flush().warn_log("Failed to save properties index!");
 
-            update_progress(idx);
-            win.redraw();
-            app::awake();
+        if stop.load(Ordering::SeqCst) {
+            channel
+                .send(ThreadMessage::HideWindow)
+                .error_log("Failed to close window");
+            return;
+        }
 
-            if let Ok(msg) = channel.try_recv() {
-                match msg {
-                    ThreadMessage::Stop => {
-                        channel
-                            .send(ThreadMessage::HideWindow)
-                            .error_log("Failed to close window");
-                        return;
-                    }
-                    _ => (),
-                }
+        let entries = rw_read!(entries);
+        export_report::write(&entries).warn_log("Failed to write export report!");
+
+        let count = |status| entries.iter().filter(|e| e.status == status).count();
+        let failed = count(Status::Failed);
+        let mut summary = format!(
+            "Finished: {} exported, {} skipped (up to date)",
+            count(Status::Exported),
+            count(Status::SkippedUpToDate)
+        );
+        let unreadable = count(Status::SkippedUnreadable);
+        if unreadable > 0 {
+            summary = format!("{}, {} can't be opened", summary, unreadable);
+        }
+        let problems = rw_read!(problems);
+        let mut alert = vec![];
+        if failed > 0 {
+            summary = format!("{}, {} failed", summary, failed);
+            alert.push(format!(
+                "Failed to export {} images, see export report for details",
+                failed
+            ));
+        }
+        if !problems.is_empty() {
+            summary = format!("{}, {} problems", summary, problems.len());
+            alert.extend(problems.iter().take(PROBLEMS_SHOWN).cloned());
+            if problems.len() > PROBLEMS_SHOWN {
+                alert.push(format!(
+                    "and {} more, see log",
+                    problems.len() - PROBLEMS_SHOWN
+                ));
             }
         }
-        image_name.set_label("Finished");
-        progress.set_value(total as f64);
-        close_btn.set_label("Close");
-        *rw_write!(finished) = true;
-        win.redraw();
-        app::awake();
+        if !alert.is_empty() {
+            utils::show_alert(&alert.join("\n"));
+        }
+        if total == 0 {
+            summary = "No images to export".to_owned();
+        }
+        send_progress(&sender, ExportProgress::Finished { summary, total });
     });
 }
+
+/// Images picked by options, in order of list
+fn select_images(
+    images: &[ImageInfo],
+    positions: &HashMap<PathBuf, usize>,
+    selected: Option<&Path>,
+    options: &ExportOptions,
+) -> Vec<ImageInfo> {
+    let (from, to) = options.range;
+    images
+        .iter()
+        .enumerate()
+        .filter(|(i, image)| match options.scope {
+            ExportScope::All => true,
+            ExportScope::Selected => Some(image.path.as_path()) == selected,
+            ExportScope::Changed => changed_since_export(
+                image,
+                positions.get(&image.path).copied().unwrap_or_default(),
+            ),
+            ExportScope::Matching => matches_pattern(image, &options.pattern),
+            ExportScope::Range => (from..=to).contains(&(i + 1)),
+        })
+        .map(|(_, image)| image.clone())
+        .collect()
+}
+
+fn read_properties(image: &ImageInfo) -> Option<ImagePropertiesFile> {
+    properties_store::read(image).ok()?
+}
+
+/// Image or its properties are newer than exported image, or it is not exported yet
+fn changed_since_export(image: &ImageInfo, index: usize) -> bool {
+    let read = read_properties(image).unwrap_or_default();
+    let modified = |path: &Path| fs::metadata(path).and_then(|meta| meta.modified());
+    let exported = match modified(&utils::get_export_image_path(image, &read, index)) {
+        Ok(time) => time,
+        Err(_) => return true,
+    };
+    modified(&image.path).is_ok_and(|time| time > exported)
+        || properties_store::modified(image).is_some_and(|time| time > exported)
+}
+
+/// File name or a tag matches pattern, ignoring case. Pattern without wildcards matches anywhere in text
+fn matches_pattern(image: &ImageInfo, pattern: &str) -> bool {
+    let pattern = pattern.trim().to_lowercase();
+    if pattern.is_empty() {
+        return true;
+    }
+    let pattern = if pattern.contains(['*', '?']) {
+        pattern
+    } else {
+        format!("*{}*", pattern)
+    };
+
+    let name = image
+        .path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_lowercase();
+    if wildcard_match(&pattern, &name) {
+        return true;
+    }
+    read_properties(image).is_some_and(|read| {
+        [read.tag, read.tag2]
+            .iter()
+            .flatten()
+            .any(|tag| wildcard_match(&pattern, &tag.to_lowercase()))
+    })
+}
+
+/// Match text with pattern where `*` is any text and `?` is any character
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of last `*` and of text it matched till
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Export an image if it has properties with quote and is changed since last export. `index` is
+/// position of image in loaded images, `unsaved` are edits of image open in main window, used in
+/// place of saved properties. Properties are only read, never saved
+fn export_image(
+    image: &ImageInfo,
+    index: usize,
+    unsaved: Option<ImagePropertiesFile>,
+    config_hash: u64,
+    manifests: &RwLock<Manifests>,
+    include_no_quote: bool,
+) -> Entry {
+    let read = match unsaved.map_or_else(|| properties_store::read(image), |p| Ok(Some(p))) {
+        Ok(Some(r)) => r,
+        // Image without properties has no quote, exported with default properties
+        Ok(None) if include_no_quote => ImagePropertiesFile::default(),
+        Ok(None) => return Entry::new(&image.path, Status::SkippedNoProps, None),
+        Err(e) => return Entry::failed(&image.path, e.to_string()),
+    };
+
+    if !include_no_quote && read.quote.as_deref().unwrap_or_default().trim().is_empty() {
+        return Entry::new(&image.path, Status::SkippedNoQuote, None);
+    }
+
+    let export_path = utils::get_export_image_path(image, &read, index);
+    let image_hash = match manifest::file_hash(&image.path) {
+        Ok(hash) => hash,
+        Err(e) => return Entry::failed(&image.path, format!("Failed to read image: {}", e)),
+    };
+    let data = serde_json::to_vec(&read).unwrap();
+    let hash = manifest::input_hash(image_hash, &data, config_hash, &export_path);
+    let export_dir = export_path.parent().unwrap_or_else(|| Path::new(""));
+    let (up_to_date, own) = {
+        let mut manifests = rw_write!(manifests);
+        let manifest = manifests.get(&export_path);
+        (
+            manifest.up_to_date_output(&image.path, export_dir, hash),
+            manifest.output(&image.path, export_dir),
+        )
+    };
+    if let Some(output) = up_to_date {
+        return Entry::new(&image.path, Status::SkippedUpToDate, Some(output));
+    }
+
+    let properties = Arc::new(RwLock::new(ImageProperties::default()));
+    let container = match ImageContainer::new(image, properties) {
+        Ok(container) => container,
+        Err(e) => return Entry::unreadable(&image.path, e.to_string()),
+    };
+    rw_write!(container.properties).merge(read, "", "");
+    // Fix is only for exported image, saved properties are kept as user made them
+    let export = container.legibility_fixed();
+    // Properties are not saved, so unsaved edits of open image stay as user left them
+    let output = match ImageContainer::export(&export, export_path.clone(), own.as_deref()) {
+        Ok(Some(output)) => output,
+        Ok(None) => return Entry::new(&image.path, Status::SkippedExists, Some(export_path)),
+        Err(e) => return Entry::failed(&image.path, e.to_string()),
+    };
+
+    // Suffix can be added to name on collision, so file which is written is kept
+    rw_write!(manifests)
+        .get(&output)
+        .insert(&image.path, &output, hash);
+    Entry::new(&image.path, Status::Exported, Some(output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::ImageType;
+    use std::time::SystemTime;
+
+    fn image(path: &str) -> ImageInfo {
+        ImageInfo {
+            path: PathBuf::from(path),
+            image_type: ImageType::Jpeg,
+        }
+    }
+
+    fn options(scope: ExportScope) -> ExportOptions {
+        ExportOptions {
+            scope,
+            pattern: String::new(),
+            range: (1, 1),
+            include_no_quote: false,
+        }
+    }
+
+    fn names(images: &[ImageInfo]) -> Vec<String> {
+        images
+            .iter()
+            .map(|i| i.path.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn wildcards() {
+        assert!(wildcard_match("abc", "abc"));
+        assert!(!wildcard_match("abc", "abcd"));
+        assert!(!wildcard_match("abc", "ab"));
+        assert!(wildcard_match("a?c", "abc"));
+        assert!(!wildcard_match("a?c", "ac"));
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("*", "anything"));
+        assert!(wildcard_match("*.jpg", "photo.jpg"));
+        assert!(!wildcard_match("*.jpg", "photo.png"));
+        assert!(wildcard_match("img_*_?.jpg", "img_2022_1.jpg"));
+        // `*` has to backtrack over repeated text
+        assert!(wildcard_match("*ab*ab", "xabyabab"));
+        assert!(!wildcard_match("*ab*ab", "xabyaba"));
+        assert!(wildcard_match("ünï*", "ünïcode"));
+    }
+
+    #[test]
+    fn pattern_matches_file_name() {
+        let folder = utils::temp_folder("pattern_matches_file_name");
+        let photo = ImageInfo {
+            path: folder.join("Beach_01.JPG"),
+            image_type: ImageType::Jpeg,
+        };
+        assert!(matches_pattern(&photo, ""));
+        assert!(matches_pattern(&photo, "beach"));
+        assert!(matches_pattern(&photo, " BEACH "));
+        assert!(matches_pattern(&photo, "*_0?.jpg"));
+        assert!(!matches_pattern(&photo, "beach*.png"));
+        assert!(!matches_pattern(&photo, "mountain"));
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn images_are_selected_by_scope() {
+        let images = [image("a.jpg"), image("b.jpg"), image("c.jpg")];
+        let positions = HashMap::new();
+        let select = |options: &ExportOptions, selected: Option<&Path>| {
+            names(&select_images(&images, &positions, selected, options))
+        };
+
+        assert_eq!(
+            select(&options(ExportScope::All), None),
+            ["a.jpg", "b.jpg", "c.jpg"]
+        );
+        assert_eq!(
+            select(&options(ExportScope::Selected), Some(Path::new("b.jpg"))),
+            ["b.jpg"]
+        );
+        assert!(select(&options(ExportScope::Selected), None).is_empty());
+
+        let range = ExportOptions {
+            range: (2, 3),
+            ..options(ExportScope::Range)
+        };
+        assert_eq!(select(&range, None), ["b.jpg", "c.jpg"]);
+        let outside = ExportOptions {
+            range: (4, 9),
+            ..options(ExportScope::Range)
+        };
+        assert!(select(&outside, None).is_empty());
+
+        let matching = ExportOptions {
+            pattern: "c".to_owned(),
+            ..options(ExportScope::Matching)
+        };
+        assert_eq!(select(&matching, None), ["c.jpg"]);
+    }
+
+    #[test]
+    fn changed_since_export_compares_times() {
+        let folder = utils::temp_folder("changed_since_export_compares_times");
+        let photo = ImageInfo {
+            path: folder.join("photo.jpg"),
+            image_type: ImageType::Jpeg,
+        };
+        fs::write(&photo.path, b"photo").unwrap();
+        let file = fs::File::options().write(true).open(&photo.path).unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(60))
+            .unwrap();
+
+        // Not exported yet
+        assert!(changed_since_export(&photo, 0));
+
+        let export_path = utils::get_export_image_path(&photo, &ImagePropertiesFile::default(), 0);
+        fs::create_dir_all(export_path.parent().unwrap()).unwrap();
+        fs::write(&export_path, b"export").unwrap();
+        assert!(!changed_since_export(&photo, 0));
+
+        // Image is edited after export
+        file.set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+        assert!(changed_since_export(&photo, 0));
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
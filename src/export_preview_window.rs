@@ -0,0 +1,136 @@
+/*
+    This file is part of Post Maker.
+    Post Maker is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+    Post Maker is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with Post Maker.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Window to see image exactly as it will be exported
+use crate::{
+    globals,
+    result_ext::ResultExt,
+    utils::{self, ImageType},
+};
+use fltk::{
+    app,
+    button::Button,
+    frame::Frame,
+    group::{Flex, Scroll},
+    image::{JpegImage, PngImage, SvgImage},
+    prelude::*,
+    window::Window,
+};
+
+pub(crate) struct ExportPreviewWindow {
+    pub(crate) win: Window,
+    scroll: Scroll,
+    image: Frame,
+    info: Frame,
+    close_btn: Button,
+}
+
+impl ExportPreviewWindow {
+    pub(crate) fn new() -> Self {
+        let mut win = Window::new(0, 0, 700, 800, "Preview Export").center_screen();
+        win.set_icon(Some(
+            SvgImage::from_data(globals::ICON.to_str().unwrap()).unwrap(),
+        ));
+
+        let mut main_flex = Flex::default().size_of_parent().column();
+
+        let scroll = Scroll::default();
+        let image = Frame::default();
+        scroll.end();
+
+        // Panel
+        let mut panel_flex = Flex::default().row();
+        panel_flex.set_size(&Frame::default(), 10);
+        let info = Frame::default();
+        let close_btn = Button::default().with_label("Close");
+        panel_flex.set_size(&close_btn, 100);
+        panel_flex.set_size(&Frame::default(), 10);
+        panel_flex.end();
+        main_flex.set_size(&panel_flex, 30);
+        main_flex.set_size(&Frame::default(), 5);
+
+        main_flex.end();
+        win.end();
+        win.make_resizable(true);
+        win.make_modal(true);
+
+        let mut preview = Self {
+            win,
+            scroll,
+            image,
+            info,
+            close_btn,
+        };
+        preview.event();
+
+        preview
+    }
+
+    /// Show encoded image in its real size
+    pub(crate) fn show(&mut self, data: &[u8]) {
+        let mime = infer::get(data).map_or("", |ty| ty.mime_type());
+        let (width, height) = match ImageType::from_mime(mime) {
+            ImageType::Jpeg => {
+                let img = JpegImage::from_data(data);
+                img.warn_log("Failed to show exported image!");
+                let img = match img {
+                    Ok(img) => img,
+                    Err(_) => return,
+                };
+                let size = (img.width(), img.height());
+                self.image.set_image(Some(img));
+                size
+            }
+            ImageType::Png => {
+                let img = PngImage::from_data(data);
+                img.warn_log("Failed to show exported image!");
+                let img = match img {
+                    Ok(img) => img,
+                    Err(_) => return,
+                };
+                let size = (img.width(), img.height());
+                self.image.set_image(Some(img));
+                size
+            }
+            _ => {
+                utils::show_alert("Preview is not available for this export format!");
+                return;
+            }
+        };
+
+        self.image
+            .resize(self.scroll.x(), self.scroll.y(), width, height);
+        self.scroll.scroll_to(0, 0);
+        self.info.set_label(&format!(
+            "{}x{} px, {:.1} KB",
+            width,
+            height,
+            data.len() as f64 / 1024.0
+        ));
+
+        self.win.show();
+        while self.win.shown() {
+            app::wait();
+        }
+    }
+
+    // Set callbacks of elements
+    fn event(&mut self) {
+        // Close Button
+        let mut win = self.win.clone();
+        self.close_btn.set_callback(move |_| {
+            win.hide();
+        });
+    }
+}
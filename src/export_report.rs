@@ -0,0 +1,218 @@
+/*
+    This file is part of Post Maker.
+    Post Maker is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+    Post Maker is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with Post Maker.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Results of Export All for every image, written in export folder
+use crate::{
+    manifest::{self, Manifest},
+    utils,
+};
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Name of report files in export folder, without extension
+const REPORT_FILE: &str = "export-report";
+
+/// What happened to an image on Export All
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Status {
+    Exported,
+    /// Exported image is made from same inputs
+    SkippedUpToDate,
+    /// Exported image already exists and config is to keep it
+    SkippedExists,
+    SkippedNoQuote,
+    SkippedNoProps,
+    /// Image could not be opened, like a truncated file
+    SkippedUnreadable,
+    Failed,
+}
+
+impl Status {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::Exported => "exported",
+            Self::SkippedUpToDate => "skipped-up-to-date",
+            Self::SkippedExists => "skipped-exists",
+            Self::SkippedNoQuote => "skipped-no-quote",
+            Self::SkippedNoProps => "skipped-no-props",
+            Self::SkippedUnreadable => "skipped-unreadable",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// Result of export of one image
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Entry {
+    pub(crate) image: PathBuf,
+    pub(crate) status: Status,
+    pub(crate) output: Option<PathBuf>,
+    pub(crate) width: Option<u32>,
+    pub(crate) height: Option<u32>,
+    /// Size of exported file in bytes
+    pub(crate) file_size: Option<u64>,
+    pub(crate) error: Option<String>,
+}
+
+impl Entry {
+    /// Entry of image, with details of exported file if it exists
+    pub(crate) fn new(image: &Path, status: Status, output: Option<PathBuf>) -> Self {
+        let (width, height) = match output.as_ref().map(image::image_dimensions) {
+            Some(Ok((width, height))) => (Some(width), Some(height)),
+            _ => (None, None),
+        };
+        let file_size = output
+            .as_ref()
+            .and_then(|path| fs::metadata(path).ok())
+            .map(|meta| meta.len());
+        Self {
+            image: image.to_path_buf(),
+            status,
+            output,
+            width,
+            height,
+            file_size,
+            error: None,
+        }
+    }
+
+    pub(crate) fn failed(image: &Path, error: String) -> Self {
+        let mut entry = Self::new(image, Status::Failed, None);
+        entry.error = Some(error);
+        entry
+    }
+
+    pub(crate) fn unreadable(image: &Path, error: String) -> Self {
+        let mut entry = Self::new(image, Status::SkippedUnreadable, None);
+        entry.error = Some(error);
+        entry
+    }
+
+    /// Line to show in browser, columns separated by tab
+    pub(crate) fn to_row(&self) -> String {
+        let size = match (self.width, self.height) {
+            (Some(width), Some(height)) => format!("{}x{}", width, height),
+            _ => String::new(),
+        };
+        let file_size = self
+            .file_size
+            .map(|size| format!("{:.1} KB", size as f64 / 1024.0))
+            .unwrap_or_default();
+        format!(
+            "{}\t{}\t{}\t{}\t{}",
+            self.image.file_name().unwrap_or_default().to_string_lossy(),
+            self.status.as_str(),
+            size,
+            file_size,
+            self.error.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+/// Write report of images in `export` folder next to them, as JSON and CSV
+pub(crate) fn write(entries: &[Entry]) -> io::Result<()> {
+    let mut folders: HashMap<PathBuf, Vec<&Entry>> = HashMap::new();
+    for entry in entries {
+        let dir = utils::get_export_dir(&entry.image);
+        folders.entry(dir).or_default().push(entry);
+    }
+
+    for (dir, entries) in folders {
+        if !dir.exists() {
+            fs::create_dir(&dir)?;
+        }
+        fs::write(
+            dir.join(format!("{}.json", REPORT_FILE)),
+            serde_json::to_string_pretty(&entries).unwrap(),
+        )?;
+        fs::write(dir.join(format!("{}.csv", REPORT_FILE)), to_csv(&entries))?;
+    }
+    Ok(())
+}
+
+/// Delete exported images recorded in manifest and report of export folder, with those files.
+/// Other files are kept, folder is removed only if it is left empty. Returns count of deleted
+/// images
+pub(crate) fn delete_exports(export_dir: &Path) -> io::Result<usize> {
+    let mut images = Manifest::load(export_dir).files(export_dir);
+    images.extend(report_outputs(export_dir));
+    images.sort();
+    images.dedup();
+    for image in &images {
+        fs::remove_file(image)?;
+    }
+
+    let manifest = manifest::manifest_path(export_dir);
+    let reports = ["json", "csv"].map(|ext| export_dir.join(format!("{}.{}", REPORT_FILE, ext)));
+    for file in reports.iter().chain([&manifest]) {
+        if file.exists() {
+            fs::remove_file(file)?;
+        }
+    }
+    // Fails if folder has other files
+    let _ = fs::remove_dir(export_dir);
+    Ok(images.len())
+}
+
+/// Exported images in report of export folder which still exist in it
+fn report_outputs(export_dir: &Path) -> Vec<PathBuf> {
+    let report = export_dir.join(format!("{}.json", REPORT_FILE));
+    let entries: Vec<serde_json::Value> = fs::read(report)
+        .ok()
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default();
+    entries
+        .iter()
+        .filter_map(|entry| entry["output"].as_str())
+        .map(PathBuf::from)
+        .filter(|path| path.parent() == Some(export_dir) && path.is_file())
+        .collect()
+}
+
+fn to_csv(entries: &[&Entry]) -> String {
+    let mut csv = String::from("image,status,output,width,height,file_size,error\n");
+    for entry in entries {
+        let row = [
+            entry.image.to_string_lossy().into_owned(),
+            entry.status.as_str().to_owned(),
+            entry
+                .output
+                .as_ref()
+                .map(|path| path.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            entry.width.map(|w| w.to_string()).unwrap_or_default(),
+            entry.height.map(|h| h.to_string()).unwrap_or_default(),
+            entry.file_size.map(|s| s.to_string()).unwrap_or_default(),
+            entry.error.clone().unwrap_or_default(),
+        ];
+        let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Quote field if it has comma, quote or new line
+pub(crate) fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
@@ -0,0 +1,211 @@
+/*
+    This file is part of Post Maker.
+    Post Maker is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+    Post Maker is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with Post Maker.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Watch folders of loaded images for images which are added, removed or changed on disk.
+//!
+//! Folders are scanned again every few seconds, and main window is told when anything changed.
+use crate::{
+    properties_store,
+    result_ext::ResultExt,
+    utils::{self, ImageInfo, ImageType},
+    AppMessage,
+};
+use fltk::app;
+use lazy_static::lazy_static;
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::RwLock,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+/// Time between scans of watched folders
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+lazy_static! {
+    static ref WATCHED: RwLock<Watched> = RwLock::new(Watched::default());
+}
+
+/// Folders being watched
+#[derive(Debug, Default)]
+struct Watched {
+    /// Folders with whether their subfolders are watched too
+    folders: Vec<(PathBuf, bool)>,
+    /// Changed every time folders are set, to leave out scans of folders watched before
+    generation: usize,
+}
+
+/// Images found in watched folders
+#[derive(Debug, Clone)]
+pub(crate) struct FolderChange {
+    pub(crate) generation: usize,
+    /// All images of watched folders, in order of `Open Folder`
+    pub(crate) images: Vec<ImageInfo>,
+    /// Images of which file changed since last scan
+    pub(crate) changed: Vec<PathBuf>,
+    /// Images of which properties changed since last scan
+    pub(crate) properties_changed: Vec<PathBuf>,
+}
+
+/// What is checked to know if image or its properties changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Stamp {
+    size: u64,
+    modified: Option<SystemTime>,
+    properties: Option<SystemTime>,
+}
+
+/// Watch these folders, in place of folders watched till now
+pub(crate) fn watch(folders: Vec<(PathBuf, bool)>) {
+    let mut watched = rw_write!(WATCHED);
+    watched.folders = folders;
+    watched.generation += 1;
+}
+
+pub(crate) fn generation() -> usize {
+    rw_read!(WATCHED).generation
+}
+
+/// Image is in a watched folder, so it is removed from list if it is not found by scan
+pub(crate) fn is_watched(path: &Path) -> bool {
+    rw_read!(WATCHED).folders.iter().any(|(folder, recursive)| {
+        path.parent() == Some(folder.as_path()) || (*recursive && path.starts_with(folder))
+    })
+}
+
+/// Spawn thread which scans watched folders and sends `FolderChanged` when images change
+pub(crate) fn spawn_watcher_thread(app_sender: app::Sender<AppMessage>) {
+    thread::spawn(move || {
+        // Type of files by path, kept while file is not changed
+        let mut types: HashMap<PathBuf, (Stamp, ImageType)> = HashMap::new();
+        let mut last: Option<(usize, Vec<(PathBuf, Stamp)>)> = None;
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let (folders, generation) = {
+                let watched = rw_read!(WATCHED);
+                (watched.folders.clone(), watched.generation)
+            };
+            if folders.is_empty() {
+                continue;
+            }
+            if last.as_ref().is_some_and(|(last, _)| *last != generation) {
+                types.clear();
+            }
+
+            let mut images = vec![];
+            let mut failed = false;
+            for (folder, recursive) in &folders {
+                if let Err(e) = scan(folder, *recursive, &mut types, &mut images) {
+                    // Folder can be unplugged or renamed, its images are not removed for it
+                    debug!("Failed to scan folder {}: {}", folder.display(), e);
+                    failed = true;
+                    break;
+                }
+            }
+            if failed {
+                continue;
+            }
+
+            let stamps: Vec<(PathBuf, Stamp)> = images
+                .iter()
+                .map(|(image, stamp)| (image.path.clone(), stamp.clone()))
+                .collect();
+            let (changed, properties_changed) = match &last {
+                Some((last_generation, last_stamps)) if *last_generation == generation => {
+                    if *last_stamps == stamps {
+                        continue;
+                    }
+                    let last_stamps: HashMap<&PathBuf, &Stamp> =
+                        last_stamps.iter().map(|(p, s)| (p, s)).collect();
+                    let mut changed = vec![];
+                    let mut properties_changed = vec![];
+                    for (path, stamp) in &stamps {
+                        if let Some(last) = last_stamps.get(path) {
+                            if last.size != stamp.size || last.modified != stamp.modified {
+                                changed.push(path.clone());
+                            }
+                            if last.properties != stamp.properties {
+                                properties_changed.push(path.clone());
+                            }
+                        }
+                    }
+                    (changed, properties_changed)
+                }
+                // First scan of folders, list is made same as it
+                _ => (vec![], vec![]),
+            };
+            last = Some((generation, stamps));
+            app_sender.send(AppMessage::FolderChanged(FolderChange {
+                generation,
+                images: images.into_iter().map(|(image, _)| image).collect(),
+                changed,
+                properties_changed,
+            }));
+        }
+    });
+}
+
+/// Add images of folder, and of its subfolders if `recursive`, with their stamps
+fn scan(
+    folder: &Path,
+    recursive: bool,
+    types: &mut HashMap<PathBuf, (Stamp, ImageType)>,
+    images: &mut Vec<(ImageInfo, Stamp)>,
+) -> io::Result<()> {
+    let (files, folders) = utils::folder_entries(folder)?;
+    for path in files {
+        let meta = match fs::metadata(&path) {
+            Ok(meta) => meta,
+            // Removed while scanning
+            Err(_) => continue,
+        };
+        let mut stamp = Stamp {
+            size: meta.len(),
+            modified: meta.modified().ok(),
+            properties: None,
+        };
+        let image_type = match types.get(&path) {
+            Some((known, image_type))
+                if known.size == stamp.size && known.modified == stamp.modified =>
+            {
+                image_type.clone()
+            }
+            _ => {
+                let image_type = match infer::get_from_path(&path) {
+                    Ok(Some(ty)) => ImageType::from_mime(ty.mime_type()),
+                    _ => ImageType::None,
+                };
+                types.insert(path.clone(), (stamp.clone(), image_type.clone()));
+                image_type
+            }
+        };
+        if let ImageType::None = image_type {
+            continue;
+        }
+        let image = ImageInfo { path, image_type };
+        stamp.properties = properties_store::modified(&image);
+        images.push((image, stamp));
+    }
+    if recursive {
+        for folder in folders {
+            // Unreadable subfolder is left out, as `Open Folder` does
+            if let Err(e) = scan(&folder, true, types, images) {
+                debug!("Failed to scan folder {}: {}", folder.display(), e);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,292 @@
+/*
+    This file is part of Post Maker.
+    Post Maker is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+    Post Maker is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with Post Maker.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Window to make images from list of quotes and folder of backgrounds
+use crate::{
+    generator::{self, Assignment},
+    globals, utils,
+};
+use fltk::{
+    app,
+    button::Button,
+    dialog::{FileDialogOptions, FileDialogType, NativeFileChooser},
+    enums::{self, Align},
+    frame::Frame,
+    group::Flex,
+    image::SvgImage,
+    input::IntInput,
+    menu::Choice,
+    output::Output,
+    prelude::*,
+    window::Window,
+};
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+pub(crate) struct GenerateWindow {
+    pub(crate) win: Window,
+    quotes_file: Output,
+    quotes_browse: Button,
+    backgrounds_dir: Output,
+    backgrounds_browse: Button,
+    output_dir: Output,
+    output_browse: Button,
+    assignment: Choice,
+    seed: IntInput,
+    info: Frame,
+    generate_btn: Button,
+    close_btn: Button,
+    /// Folder of made images, if they were made
+    generated: Rc<RefCell<Option<PathBuf>>>,
+}
+
+impl GenerateWindow {
+    pub(crate) fn new() -> Self {
+        let mut win = Window::new(0, 0, 560, 250, "Generate from Quotes").center_screen();
+        win.set_icon(Some(
+            SvgImage::from_data(globals::ICON.to_str().unwrap()).unwrap(),
+        ));
+
+        let mut main_flex = Flex::default().size_of_parent().column();
+        main_flex.set_margin(5);
+
+        let mut row = Flex::default().row();
+        row.set_size(
+            &Frame::default()
+                .with_label("Quotes file")
+                .with_align(Align::Right | Align::Inside),
+            130,
+        );
+        let quotes_file = Output::default();
+        let quotes_browse = Button::default().with_label("Pick");
+        row.set_size(&quotes_browse, 50);
+        row.end();
+        main_flex.set_size(&row, 30);
+
+        let mut row = Flex::default().row();
+        row.set_size(
+            &Frame::default()
+                .with_label("Backgrounds")
+                .with_align(Align::Right | Align::Inside),
+            130,
+        );
+        let backgrounds_dir = Output::default();
+        let backgrounds_browse = Button::default().with_label("Pick");
+        row.set_size(&backgrounds_browse, 50);
+        row.end();
+        main_flex.set_size(&row, 30);
+
+        let mut row = Flex::default().row();
+        row.set_size(
+            &Frame::default()
+                .with_label("Output folder")
+                .with_align(Align::Right | Align::Inside),
+            130,
+        );
+        let output_dir = Output::default();
+        let output_browse = Button::default().with_label("Pick");
+        row.set_size(&output_browse, 50);
+        row.end();
+        main_flex.set_size(&row, 30);
+
+        let mut row = Flex::default().row();
+        row.set_size(
+            &Frame::default()
+                .with_label("Assign")
+                .with_align(Align::Right | Align::Inside),
+            130,
+        );
+        let mut assignment = Choice::default();
+        assignment.add_choice("Round robin|Random|Best fit|Best contrast");
+        assignment.set_value(0);
+        assignment.set_tooltip(
+            "Best fit: background on which quote can be largest and still readable.\n\
+            Best contrast: background on which quote is most readable",
+        );
+        row.set_size(&assignment, 150);
+        row.set_size(
+            &Frame::default()
+                .with_label("Seed")
+                .with_align(Align::Right | Align::Inside),
+            60,
+        );
+        let mut seed = IntInput::default();
+        seed.set_value("1");
+        seed.deactivate();
+        row.set_size(&seed, 100);
+        Frame::default();
+        row.end();
+        main_flex.set_size(&row, 30);
+
+        let info = Frame::default().with_align(enums::Align::Left | enums::Align::Inside);
+
+        let mut row = Flex::default().row();
+        Frame::default();
+        let generate_btn = Button::default().with_label("Generate");
+        row.set_size(&generate_btn, 100);
+        let close_btn = Button::default().with_label("Cancel");
+        row.set_size(&close_btn, 100);
+        row.end();
+        main_flex.set_size(&row, 30);
+
+        main_flex.end();
+        win.end();
+        win.make_modal(true);
+
+        let mut generate_window = Self {
+            win,
+            quotes_file,
+            quotes_browse,
+            backgrounds_dir,
+            backgrounds_browse,
+            output_dir,
+            output_browse,
+            assignment,
+            seed,
+            info,
+            generate_btn,
+            close_btn,
+            generated: Rc::new(RefCell::new(None)),
+        };
+        generate_window.event();
+
+        generate_window
+    }
+
+    /// Show window, returns folder of made images if they were made
+    pub(crate) fn show(&mut self) -> Option<PathBuf> {
+        self.info.set_label("");
+        *self.generated.borrow_mut() = None;
+        self.win.show();
+        while self.win.shown() {
+            app::wait();
+        }
+        self.generated.borrow_mut().take()
+    }
+
+    // Set callbacks of elements
+    fn event(&mut self) {
+        // Browse for quotes file
+        let mut quotes_file = self.quotes_file.clone();
+        self.quotes_browse.set_callback(move |_| {
+            let mut chooser = NativeFileChooser::new(FileDialogType::BrowseFile);
+            chooser.set_option(FileDialogOptions::UseFilterExt);
+            chooser.set_filter("*.txt");
+            chooser.show();
+            let path = chooser.filename();
+            if path.is_file() {
+                quotes_file.set_value(&path.to_string_lossy());
+            }
+        });
+
+        // Browse for backgrounds folder
+        let mut backgrounds_dir = self.backgrounds_dir.clone();
+        let mut output_dir = self.output_dir.clone();
+        self.backgrounds_browse.set_callback(move |_| {
+            let mut chooser = NativeFileChooser::new(FileDialogType::BrowseDir);
+            chooser.show();
+            let path = chooser.filename();
+            if !path.is_dir() {
+                return;
+            }
+            backgrounds_dir.set_value(&path.to_string_lossy());
+            if output_dir.value().is_empty() {
+                output_dir.set_value(&path.join("generated").to_string_lossy());
+            }
+        });
+
+        // Browse for output folder
+        let mut output_dir = self.output_dir.clone();
+        self.output_browse.set_callback(move |_| {
+            let mut chooser = NativeFileChooser::new(FileDialogType::BrowseDir);
+            chooser.set_option(FileDialogOptions::NewFolder);
+            chooser.show();
+            let path = chooser.filename();
+            if !path.as_os_str().is_empty() {
+                output_dir.set_value(&path.to_string_lossy());
+            }
+        });
+
+        // Seed is only for random
+        let mut seed = self.seed.clone();
+        self.assignment.set_callback(move |f| {
+            if f.value() == 1 {
+                seed.activate();
+            } else {
+                seed.deactivate();
+            }
+        });
+
+        // Generate Button
+        let quotes_file = self.quotes_file.clone();
+        let backgrounds_dir = self.backgrounds_dir.clone();
+        let output_dir = self.output_dir.clone();
+        let assignment = self.assignment.clone();
+        let seed = self.seed.clone();
+        let mut info = self.info.clone();
+        let generated = Rc::clone(&self.generated);
+        let mut win = self.win.clone();
+        self.generate_btn.set_callback(move |_| {
+            let quotes = match generator::read_quotes(Path::new(&quotes_file.value())) {
+                Ok(quotes) => quotes,
+                Err(e) => {
+                    utils::show_alert(&e.to_string());
+                    return;
+                }
+            };
+            let backgrounds = generator::backgrounds(Path::new(&backgrounds_dir.value()));
+            if backgrounds.is_empty() {
+                utils::show_alert("No images in backgrounds folder!");
+                return;
+            }
+            let output = PathBuf::from(output_dir.value());
+            if output.as_os_str().is_empty() {
+                utils::show_alert("Output folder is not picked!");
+                return;
+            }
+            let assignment = match assignment.value() {
+                1 => Assignment::Random(seed.value().parse().unwrap_or(0)),
+                2 => Assignment::BestFit,
+                3 => Assignment::BestContrast,
+                _ => Assignment::RoundRobin,
+            };
+
+            info.set_label("Assigning quotes...");
+            win.redraw();
+            app::flush();
+            let assigned = generator::assign(&quotes, &backgrounds, assignment);
+            info.set_label("Generating...");
+            app::flush();
+            match generator::generate(&quotes, &backgrounds, &assigned, &output) {
+                Ok(_) => {
+                    *generated.borrow_mut() = Some(output);
+                    win.hide();
+                }
+                Err(e) => {
+                    info.set_label("");
+                    utils::show_alert(&e.to_string());
+                }
+            }
+        });
+
+        // Close Button
+        let mut win = self.win.clone();
+        self.close_btn.set_callback(move |_| {
+            win.hide();
+        });
+    }
+}
@@ -0,0 +1,297 @@
+/*
+    This file is part of Post Maker.
+    Post Maker is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+    Post Maker is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with Post Maker.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Make images from list of quotes and a few background images
+use crate::{
+    error::Error,
+    globals, legibility, properties_store,
+    result_ext::ResultExt,
+    utils::{self, ImageInfo, ImageProperties, ImagePropertiesFile, ImageType},
+};
+use image::{DynamicImage, GenericImageView};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Way to pick background of every quote
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Assignment {
+    /// Backgrounds one after another
+    RoundRobin,
+    /// Random background, same seed gives same result
+    Random(u64),
+    /// Background with largest auto-fit size of quote, then best contrast. Backgrounds are used
+    /// nearly equally
+    BestFit,
+    /// Background on which quote is most readable, backgrounds are used nearly equally
+    BestContrast,
+}
+
+/// Quotes in text file. Paragraphs are quotes if file has empty lines, otherwise every line
+pub(crate) fn read_quotes(path: &Path) -> Result<Vec<String>, Error> {
+    let text =
+        fs::read_to_string(path).map_err(|e| Error::Io("Failed to read quotes".to_owned(), e))?;
+    let has_paragraphs = text.trim().lines().any(|line| line.trim().is_empty());
+    let mut quotes = vec![];
+    let mut quote: Vec<&str> = vec![];
+    for line in text.lines().map(str::trim) {
+        if !line.is_empty() {
+            quote.push(line);
+        }
+        if (!has_paragraphs || line.is_empty()) && !quote.is_empty() {
+            quotes.push(quote.join("\n"));
+            quote.clear();
+        }
+    }
+    if !quote.is_empty() {
+        quotes.push(quote.join("\n"));
+    }
+    if quotes.is_empty() {
+        return Err(Error::Input("No quotes in file".to_owned()));
+    }
+    Ok(quotes)
+}
+
+/// Images in folder, sorted by name
+pub(crate) fn backgrounds(dir: &Path) -> Vec<ImageInfo> {
+    let mut files: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(dir) => dir
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .collect(),
+        Err(_) => return vec![],
+    };
+    files.sort();
+    files
+        .into_iter()
+        .filter_map(|path| {
+            let mime = infer::get_from_path(&path).ok()??.mime_type();
+            match ImageType::from_mime(mime) {
+                ImageType::None => None,
+                image_type => Some(ImageInfo { path, image_type }),
+            }
+        })
+        .collect()
+}
+
+/// Index of background for every quote
+pub(crate) fn assign(
+    quotes: &[String],
+    backgrounds: &[ImageInfo],
+    assignment: Assignment,
+) -> Vec<usize> {
+    let count = backgrounds.len();
+    if count == 0 {
+        return vec![];
+    }
+    match assignment {
+        Assignment::RoundRobin => (0..quotes.len()).map(|i| i % count).collect(),
+        Assignment::Random(seed) => {
+            let mut state = seed;
+            (0..quotes.len())
+                .map(|_| (splitmix64(&mut state) % count as u64) as usize)
+                .collect()
+        }
+        Assignment::BestFit => best(quotes, backgrounds, |image, prop| {
+            (auto_fit_size(image, prop), contrast(image, prop))
+        }),
+        Assignment::BestContrast => best(quotes, backgrounds, |image, prop| {
+            (contrast(image, prop), 0.0)
+        }),
+    }
+}
+
+/// Smallest part of quote size in config tried by auto-fit
+const MIN_FIT_SCALE: f64 = 0.5;
+
+/// Part of width of image which quote can take
+const FIT_WIDTH: f64 = 0.9;
+
+/// Pick background with highest score of quote, but not more than its share of quotes. Score
+/// is compared by first value, then by second
+fn best(
+    quotes: &[String],
+    backgrounds: &[ImageInfo],
+    score: impl Fn(&DynamicImage, &ImageProperties) -> (f64, f64),
+) -> Vec<usize> {
+    let mut samples: Vec<Option<(DynamicImage, ImageProperties)>> =
+        backgrounds.iter().map(sample_background).collect();
+    let share = quotes.len().div_ceil(backgrounds.len());
+    let mut used = vec![0; backgrounds.len()];
+
+    quotes
+        .iter()
+        .map(|quote| {
+            let best = samples
+                .iter_mut()
+                .enumerate()
+                .filter(|(i, _)| used[*i] < share)
+                .map(|(i, sample)| {
+                    // Background which can't be opened is picked last
+                    let score = sample.as_mut().map_or((-1.0, -1.0), |(image, prop)| {
+                        prop.quote = quote.clone();
+                        score(image, prop)
+                    });
+                    (i, score)
+                })
+                .max_by(|(_, a), (_, b)| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)))
+                .map_or(0, |(i, _)| i);
+            used[best] += 1;
+            best
+        })
+        .collect()
+}
+
+/// Contrast of least readable text of properties on background
+fn contrast(image: &DynamicImage, prop: &ImageProperties) -> f64 {
+    legibility::worst_contrast(image, prop).map_or(0.0, |(_, r)| r)
+}
+
+/// Auto-fit size of quote on background, as part of quote size in config. It is largest size at
+/// which quote fits in width of image and in image below its position, and its contrast reaches
+/// minimum in config. Larger quote covers more of background, so it can get less readable.
+/// 0 if quote does not fit even at `MIN_FIT_SCALE`
+fn auto_fit_size(image: &DynamicImage, prop: &ImageProperties) -> f64 {
+    let (width, height) = prop.original_dimension;
+    let (minimum, boxed) = {
+        let config = rw_read!(globals::CONFIG);
+        (config.minimum_contrast_ratio, config.draw_box_around_quote)
+    };
+    let size = utils::quote_from_height(height);
+    let steps = ((1.0 - MIN_FIT_SCALE) / 0.05).round() as usize;
+    (0..=steps)
+        .map(|i| 1.0 - i as f64 * 0.05)
+        .find(|scale| {
+            let (text_width, line_height, lines) =
+                utils::measure_block(&globals::FONT_QUOTE, size * scale, &prop.quote);
+            let text_height = line_height * (1.0 + lines.saturating_sub(1) as f64 * 1.12);
+            let area = (
+                (width - text_width) / 2.0,
+                prop.quote_position,
+                text_width,
+                text_height,
+            );
+            text_width <= width * FIT_WIDTH
+                && prop.quote_position + text_height <= height
+                && legibility::area_contrast(image, prop, area, boxed)
+                    .is_some_and(|ratio| ratio >= minimum)
+        })
+        .unwrap_or(0.0)
+}
+
+/// Small cropped background and properties with default positions, to check contrast.
+/// `None` if background can't be opened
+fn sample_background(image_info: &ImageInfo) -> Option<(DynamicImage, ImageProperties)> {
+    let image = match utils::load_image(image_info) {
+        Ok(image) => image,
+        Err(e) => {
+            warn!("Failed to open {}: {}", image_info.path.display(), e);
+            return None;
+        }
+    };
+    let (width, height) = image.dimensions();
+    let (crop_width, crop_height) = utils::croped_ratio(width as f64, height as f64);
+    let image = image
+        .crop_imm(
+            ((width as f64 - crop_width) / 2.0) as u32,
+            ((height as f64 - crop_height) / 2.0) as u32,
+            crop_width as u32,
+            crop_height as u32,
+        )
+        .thumbnail((crop_width * 500.0 / crop_height) as u32, 500);
+
+    let (width, height) = image.dimensions();
+    let (width, height) = (width as f64, height as f64);
+    let config = rw_read!(globals::CONFIG);
+    let prop = ImageProperties {
+        original_dimension: (width, height),
+        quote_position: height * config.quote_position_ratio,
+        subquote_position: height * config.subquote_position_ratio,
+        subquote2_position: height * config.subquote2_position_ratio,
+        tag_position: height * config.tag_y_position_ratio,
+        tag2_position: height * config.tag2_position_ratio,
+        translucent_layer_color: config.color_layer,
+        ..Default::default()
+    };
+    Some((image, prop))
+}
+
+/// Random number generator, good enough to shuffle quotes
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// Paths of images which will be made in output folder, in order of quotes.
+/// Files already in folder are kept, new image gets suffix in place of replacing them
+pub(crate) fn output_images(
+    quotes: &[String],
+    backgrounds: &[ImageInfo],
+    assigned: &[usize],
+    output_dir: &Path,
+) -> Vec<ImageInfo> {
+    let width = quotes.len().to_string().len();
+    assigned
+        .iter()
+        .enumerate()
+        .map(|(i, background)| {
+            let background = &backgrounds[*background];
+            let file_name = format!(
+                "{:0width$}-{}",
+                i + 1,
+                background
+                    .path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy(),
+                width = width
+            );
+            let path = output_dir.join(file_name);
+            ImageInfo {
+                path: if path.exists() {
+                    utils::free_path(&path)
+                } else {
+                    path
+                },
+                image_type: background.image_type.clone(),
+            }
+        })
+        .collect()
+}
+
+/// Copy background for every quote in output folder and write its properties
+pub(crate) fn generate(
+    quotes: &[String],
+    backgrounds: &[ImageInfo],
+    assigned: &[usize],
+    output_dir: &Path,
+) -> Result<usize, Error> {
+    fs::create_dir_all(output_dir)
+        .map_err(|e| Error::Io("Failed to create folder".to_owned(), e))?;
+    let outputs = output_images(quotes, backgrounds, assigned, output_dir);
+    for ((quote, background), output) in quotes.iter().zip(assigned).zip(&outputs) {
+        utils::copy_image(&backgrounds[*background], output)?;
+        let prop = ImagePropertiesFile {
+            quote: Some(quote.clone()),
+            ..Default::default()
+        };
+        properties_store::write(output, &prop)?;
+    }
+    properties_store::flush()?;
+    Ok(outputs.len())
+}
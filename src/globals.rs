@@ -12,10 +12,10 @@
     along with Post Maker.  If not, see <https://www.gnu.org/licenses/>
 */
 
-use crate::{config, result_ext::ResultExt};
+use crate::{config, error::Error, project, result_ext::ResultExt};
 use lazy_static::lazy_static;
 use rusttype::Font;
-use std::{ffi::OsString, io::Read, sync::RwLock};
+use std::{ffi::OsString, io::Read, path::PathBuf, sync::RwLock};
 
 lazy_static! {
     /// Theme for the GUI
@@ -29,6 +29,9 @@ lazy_static! {
     pub(crate) static ref CONFIG: RwLock<config::ConfigFile> =
         RwLock::new(config::ConfigFile::load());
 
+    /// Open project file and its contents
+    pub(crate) static ref PROJECT: RwLock<Option<(PathBuf, project::Project)>> = RwLock::new(None);
+
     /// Main Sender
     pub(crate) static ref MAIN_SENDER: RwLock<Option<fltk::app::Sender<crate::AppMessage>>> = RwLock::new(None);
 
@@ -72,5 +75,11 @@ fn load_font(path: &str) -> Font<'static> {
             }
         }
     }
+    // Empty path is default font
+    if !path.is_empty() {
+        Result::<(), _>::Err(Error::Font(format!("{} can't be read as font", path))).warn_log(
+            &format!("Failed to load font {}, default font is used!", path),
+        );
+    }
     rusttype::Font::try_from_vec(include_bytes!("../assets/OpenSans-Regular.ttf").to_vec()).unwrap()
 }
@@ -0,0 +1,208 @@
+/*
+    This file is part of Post Maker.
+    Post Maker is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+    Post Maker is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with Post Maker.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Undo and redo of edits made in main window
+use crate::utils::ImageProperties;
+use std::{collections::HashMap, path::PathBuf};
+
+/// Maximum steps remembered for an image
+const LIMIT: usize = 100;
+
+/// Control which made the change. Consecutive changes from same control are merged in one step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Edit {
+    NamePrefix,
+    Quote,
+    Subquote,
+    Subquote2,
+    Tag,
+    Tag2,
+    QuotePosition,
+    SubquotePosition,
+    Subquote2Position,
+    TagPosition,
+    Tag2Position,
+    LayerAlpha,
+    /// Change which is never merged, like reset buttons
+    Single,
+}
+
+/// Undo and redo stack of one image
+#[derive(Debug, Default)]
+struct Stack {
+    undo: Vec<ImageProperties>,
+    redo: Vec<ImageProperties>,
+}
+
+/// Snapshots of properties of every image edited in this session
+#[derive(Debug, Default)]
+pub(crate) struct History {
+    stacks: HashMap<PathBuf, Stack>,
+    last_edit: Option<Edit>,
+}
+
+impl History {
+    /// Remember properties before they are changed
+    pub(crate) fn checkpoint(&mut self, prop: &ImageProperties, edit: Edit) {
+        if edit != Edit::Single && self.last_edit == Some(edit) {
+            return;
+        }
+        self.last_edit = match edit {
+            Edit::Single => None,
+            _ => Some(edit),
+        };
+
+        if let Some(stack) = self.stack(prop) {
+            stack.undo.push(prop.clone());
+            if stack.undo.len() > LIMIT {
+                stack.undo.remove(0);
+            }
+            stack.redo.clear();
+        }
+    }
+
+    /// Start a new step on next change, even if it is from same control
+    pub(crate) fn seal(&mut self) {
+        self.last_edit = None;
+    }
+
+    /// Get properties before last step
+    pub(crate) fn undo(&mut self, prop: &ImageProperties) -> Option<ImageProperties> {
+        self.seal();
+        let stack = self.stack(prop)?;
+        let previous = stack.undo.pop()?;
+        stack.redo.push(prop.clone());
+        Some(previous)
+    }
+
+    /// Get properties after last undone step
+    pub(crate) fn redo(&mut self, prop: &ImageProperties) -> Option<ImageProperties> {
+        self.seal();
+        let stack = self.stack(prop)?;
+        let next = stack.redo.pop()?;
+        stack.undo.push(prop.clone());
+        Some(next)
+    }
+
+    fn stack(&mut self, prop: &ImageProperties) -> Option<&mut Stack> {
+        let path = prop.image_info.as_ref()?.path.clone();
+        Some(self.stacks.entry(path).or_default())
+    }
+}
+
+/// Copy edited values of snapshot to properties, keeping loaded image as it is
+pub(crate) fn restore(prop: &mut ImageProperties, snapshot: ImageProperties) {
+    prop.crop_position = snapshot.crop_position;
+    prop.name_prefix = snapshot.name_prefix;
+    prop.quote = snapshot.quote;
+    prop.subquote = snapshot.subquote;
+    prop.subquote2 = snapshot.subquote2;
+    prop.tag = snapshot.tag;
+    prop.tag2 = snapshot.tag2;
+    prop.quote_position = snapshot.quote_position;
+    prop.subquote_position = snapshot.subquote_position;
+    prop.subquote2_position = snapshot.subquote2_position;
+    prop.tag_position = snapshot.tag_position;
+    prop.tag2_position = snapshot.tag2_position;
+    prop.translucent_layer_color = snapshot.translucent_layer_color;
+    prop.text_color = snapshot.text_color;
+    prop.is_saved = false;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{ImageInfo, ImageType};
+
+    fn image(path: &str, quote: &str) -> ImageProperties {
+        ImageProperties {
+            image_info: Some(ImageInfo {
+                path: PathBuf::from(path),
+                image_type: ImageType::Jpeg,
+            }),
+            quote: quote.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn undo_and_redo_steps() {
+        let mut history = History::default();
+        history.checkpoint(&image("a.jpg", "one"), Edit::Single);
+        history.checkpoint(&image("a.jpg", "two"), Edit::Single);
+
+        let current = image("a.jpg", "three");
+        let previous = history.undo(&current).unwrap();
+        assert_eq!(previous.quote, "two");
+        assert_eq!(history.undo(&previous).unwrap().quote, "one");
+        assert!(history.undo(&image("a.jpg", "one")).is_none());
+
+        assert_eq!(history.redo(&image("a.jpg", "one")).unwrap().quote, "two");
+        assert_eq!(history.redo(&previous).unwrap().quote, "three");
+        assert!(history.redo(&current).is_none());
+    }
+
+    #[test]
+    fn edits_of_same_control_are_merged_until_sealed() {
+        let mut history = History::default();
+        history.checkpoint(&image("a.jpg", "one"), Edit::Quote);
+        history.checkpoint(&image("a.jpg", "one t"), Edit::Quote);
+        history.checkpoint(&image("a.jpg", "one tw"), Edit::Quote);
+        history.seal();
+        history.checkpoint(&image("a.jpg", "one two"), Edit::Quote);
+
+        let previous = history.undo(&image("a.jpg", "one two!")).unwrap();
+        assert_eq!(previous.quote, "one two");
+        assert_eq!(history.undo(&previous).unwrap().quote, "one");
+        assert!(history.undo(&image("a.jpg", "one")).is_none());
+    }
+
+    #[test]
+    fn checkpoint_clears_redo() {
+        let mut history = History::default();
+        history.checkpoint(&image("a.jpg", "one"), Edit::Single);
+        history.undo(&image("a.jpg", "two")).unwrap();
+        history.checkpoint(&image("a.jpg", "one"), Edit::Single);
+        assert!(history.redo(&image("a.jpg", "three")).is_none());
+    }
+
+    #[test]
+    fn images_have_own_steps() {
+        let mut history = History::default();
+        history.checkpoint(&image("a.jpg", "a"), Edit::Single);
+        history.checkpoint(&image("b.jpg", "b"), Edit::Single);
+        assert_eq!(history.undo(&image("a.jpg", "")).unwrap().quote, "a");
+        assert!(history.undo(&image("a.jpg", "")).is_none());
+        assert_eq!(history.undo(&image("b.jpg", "")).unwrap().quote, "b");
+        // Properties without image are not remembered
+        history.checkpoint(&ImageProperties::default(), Edit::Single);
+        assert!(history.undo(&ImageProperties::default()).is_none());
+    }
+
+    #[test]
+    fn steps_are_limited() {
+        let mut history = History::default();
+        for i in 0..LIMIT + 5 {
+            history.checkpoint(&image("a.jpg", &i.to_string()), Edit::Single);
+        }
+        let mut prop = image("a.jpg", "last");
+        let mut count = 0;
+        while let Some(previous) = history.undo(&prop) {
+            prop = previous;
+            count += 1;
+        }
+        assert_eq!(count, LIMIT);
+        assert_eq!(prop.quote, "5");
+    }
+}
@@ -0,0 +1,174 @@
+/*
+    This file is part of Post Maker.
+    Post Maker is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+    Post Maker is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with Post Maker.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Colour profile of JPEG images, copied to exported image so its colours are not shifted.
+//!
+//! Pixels are not converted between profiles, exported image keeps profile of its source.
+use crate::{error::Error, exif};
+use flate2::{write::ZlibEncoder, Compression};
+use std::{io::Write, path::Path};
+
+/// Start of APP2 segments which have colour profile
+const JPEG_SIGNATURE: &[u8] = b"ICC_PROFILE\0";
+
+/// Most bytes of profile in one APP2 segment, with signature and chunk numbers it is 65533
+const JPEG_CHUNK_SIZE: usize = 65519;
+
+/// Colour profile of JPEG image, `None` if it has none or it is not a JPEG
+pub(crate) fn read(path: &Path) -> Option<Vec<u8>> {
+    from_jpeg(&exif::read_header(path)?)
+}
+
+/// Join profile from its APP2 segments, which are numbered from 1
+fn from_jpeg(data: &[u8]) -> Option<Vec<u8>> {
+    let mut chunks: Vec<(u8, u8, &[u8])> = exif::jpeg_segments(data)
+        .into_iter()
+        .filter(|(marker, segment)| *marker == 0xE2 && segment.starts_with(JPEG_SIGNATURE))
+        .filter_map(|(_, segment)| {
+            let rest = &segment[JPEG_SIGNATURE.len()..];
+            Some((*rest.first()?, *rest.get(1)?, rest.get(2..)?))
+        })
+        .collect();
+    let count = chunks.first()?.1;
+    if chunks.len() != count as usize {
+        return None;
+    }
+    chunks.sort_by_key(|(number, _, _)| *number);
+    if chunks
+        .iter()
+        .enumerate()
+        .any(|(i, (number, _, _))| *number as usize != i + 1)
+    {
+        return None;
+    }
+    Some(
+        chunks
+            .into_iter()
+            .flat_map(|(_, _, data)| data)
+            .copied()
+            .collect(),
+    )
+}
+
+/// Data of APP2 segments to write profile in JPEG
+pub(crate) fn jpeg_segments(profile: &[u8]) -> Vec<Vec<u8>> {
+    let chunks: Vec<&[u8]> = profile.chunks(JPEG_CHUNK_SIZE).collect();
+    // Chunks are numbered in one byte
+    if chunks.len() > u8::MAX as usize {
+        return vec![];
+    }
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            let mut segment = JPEG_SIGNATURE.to_vec();
+            segment.push(i as u8 + 1);
+            segment.push(chunks.len() as u8);
+            segment.extend_from_slice(chunk);
+            segment
+        })
+        .collect()
+}
+
+/// Add iCCP chunk with profile to encoded PNG
+pub(crate) fn embed_in_png(png: Vec<u8>, profile: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut data = b"ICC Profile\0".to_vec();
+    // Compression method, only zlib is allowed
+    data.push(0);
+    let mut encoder = ZlibEncoder::new(data, Compression::best());
+    encoder
+        .write_all(profile)
+        .map_err(|e| Error::Encode(format!("Failed to compress colour profile: {}", e)))?;
+    let data = encoder
+        .finish()
+        .map_err(|e| Error::Encode(format!("Failed to compress colour profile: {}", e)))?;
+    exif::insert_png_chunk(png, b"iCCP", &data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::ImageEncoder;
+
+    /// JPEG with APP2 segments, and start of scan after them
+    fn jpeg(segments: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = vec![0xFF, 0xD8];
+        for segment in segments {
+            data.extend([0xFF, 0xE2]);
+            data.extend(((segment.len() + 2) as u16).to_be_bytes());
+            data.extend(segment);
+        }
+        data.extend([0xFF, 0xDA, 0x00, 0x02]);
+        data
+    }
+
+    fn profile(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn profile_in_many_segments_is_joined() {
+        let profile = profile(JPEG_CHUNK_SIZE * 2 + 100);
+        let segments = jpeg_segments(&profile);
+        assert_eq!(segments.len(), 3);
+        assert_eq!(from_jpeg(&jpeg(&segments)), Some(profile.clone()));
+
+        // Segments can be in any order
+        let reversed: Vec<Vec<u8>> = segments.into_iter().rev().collect();
+        assert_eq!(from_jpeg(&jpeg(&reversed)), Some(profile));
+    }
+
+    #[test]
+    fn missing_or_malformed_segments_have_no_profile() {
+        let segments = jpeg_segments(&profile(JPEG_CHUNK_SIZE + 10));
+        assert_eq!(from_jpeg(&jpeg(&segments[..1])), None);
+
+        // Numbers of chunks do not start from 1
+        let mut renumbered = segments.clone();
+        for segment in &mut renumbered {
+            segment[JPEG_SIGNATURE.len()] += 1;
+        }
+        assert_eq!(from_jpeg(&jpeg(&renumbered)), None);
+
+        // Signature without chunk numbers
+        assert_eq!(from_jpeg(&jpeg(&[JPEG_SIGNATURE.to_vec()])), None);
+        assert_eq!(from_jpeg(&jpeg(&[])), None);
+    }
+
+    #[test]
+    fn truncated_jpeg_has_no_profile() {
+        let mut data = jpeg(&jpeg_segments(&profile(200)));
+        data.truncate(100);
+        assert_eq!(from_jpeg(&data), None);
+    }
+
+    #[test]
+    fn profile_is_embedded_after_header_of_png() {
+        let mut png = vec![];
+        image::codecs::png::PngEncoder::new(&mut png)
+            .write_image(&[255, 0, 0], 1, 1, image::ColorType::Rgb8)
+            .unwrap();
+        let output = embed_in_png(png.clone(), &profile(300)).unwrap();
+        assert_eq!(&output[..33], &png[..33]);
+        assert_eq!(&output[37..41], b"iCCP");
+        assert_eq!(&output[41..53], b"ICC Profile\0");
+        assert!(image::load_from_memory(&output).is_ok());
+    }
+
+    #[test]
+    fn invalid_png_is_error() {
+        assert!(embed_in_png(vec![], &profile(10)).is_err());
+        assert!(embed_in_png(vec![0; 40], &profile(10)).is_err());
+    }
+}
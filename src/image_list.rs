@@ -0,0 +1,295 @@
+/*
+    This file is part of Post Maker.
+    Post Maker is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+    Post Maker is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with Post Maker.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! All loaded images, and sort and filters which make the list of images shown in file choice
+use crate::{
+    exif::{self, Exif},
+    properties_store,
+    result_ext::ResultExt,
+    utils::{self, ImageInfo, ImagePropertiesFile},
+};
+use lazy_static::lazy_static;
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::RwLock,
+    time::SystemTime,
+};
+
+lazy_static! {
+    /// Images of opened folder or project, in order they were loaded
+    static ref LOADED: RwLock<Vec<ImageInfo>> = RwLock::new(vec![]);
+
+    /// Sort and filters of shown images
+    static ref VIEW: RwLock<ListView> = RwLock::new(ListView::default());
+
+    /// Files of opened folder or project which are images in a format that can't be opened
+    static ref UNSUPPORTED: RwLock<Vec<UnsupportedImage>> = RwLock::new(vec![]);
+}
+
+/// Image file which is left out as its format can't be opened
+#[derive(Debug, Clone)]
+pub(crate) struct UnsupportedImage {
+    pub(crate) path: PathBuf,
+    /// Name of format, like `HEIC`
+    pub(crate) format: &'static str,
+}
+
+/// Order of shown images
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum SortMode {
+    /// As folder or project was loaded
+    #[default]
+    Loaded,
+    Name,
+    /// Latest first
+    Modified,
+    /// EXIF date photo was taken, latest first. Images without it are kept at end
+    CaptureDate,
+    /// Largest first
+    Dimensions,
+    /// Images without quote first
+    HasQuote,
+}
+
+impl SortMode {
+    pub(crate) const ALL: [SortMode; 6] = [
+        SortMode::Loaded,
+        SortMode::Name,
+        SortMode::Modified,
+        SortMode::CaptureDate,
+        SortMode::Dimensions,
+        SortMode::HasQuote,
+    ];
+
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            SortMode::Loaded => "Loaded Order",
+            SortMode::Name => "Name",
+            SortMode::Modified => "Modified Time",
+            SortMode::CaptureDate => "Capture Date",
+            SortMode::Dimensions => "Dimensions",
+            SortMode::HasQuote => "Has Quote",
+        }
+    }
+}
+
+/// Sort and filters of shown images, filters are combined
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ListView {
+    pub(crate) sort: SortMode,
+    pub(crate) without_quote: bool,
+    pub(crate) not_exported: bool,
+    pub(crate) too_small: bool,
+    /// Text to find in quotes, subquotes and tags, case is ignored
+    pub(crate) search: String,
+}
+
+impl ListView {
+    pub(crate) fn is_filtered(&self) -> bool {
+        self.without_quote || self.not_exported || self.too_small || !self.search.is_empty()
+    }
+}
+
+/// What sort and filters need to know about an image
+struct ImageDetails {
+    image: ImageInfo,
+    /// Position in loaded images, from 1
+    index: usize,
+    properties: Option<ImagePropertiesFile>,
+    /// Width and height, read only if needed
+    dimensions: Option<(u32, u32)>,
+}
+
+impl ImageDetails {
+    fn new(image: &ImageInfo, index: usize, view: &ListView) -> Self {
+        let needs_dimensions = view.too_small || view.sort == SortMode::Dimensions;
+        Self {
+            image: image.clone(),
+            index,
+            properties: properties_store::read(image).ok().flatten(),
+            dimensions: needs_dimensions
+                .then(|| exif::upright_dimensions(&image.path))
+                .flatten(),
+        }
+    }
+
+    fn has_quote(&self) -> bool {
+        self.properties
+            .as_ref()
+            .and_then(|p| p.quote.as_ref())
+            .is_some_and(|quote| !quote.trim().is_empty())
+    }
+
+    fn is_exported(&self) -> bool {
+        self.properties
+            .as_ref()
+            .is_some_and(|p| utils::get_export_image_path(&self.image, p, self.index).exists())
+    }
+
+    fn is_too_small(&self) -> bool {
+        self.dimensions
+            .is_some_and(|(width, height)| utils::is_too_small(width as f64, height as f64))
+    }
+
+    /// Any text of image has `search`, which is lowercase
+    fn has_text(&self, search: &str) -> bool {
+        let p = match &self.properties {
+            Some(p) => p,
+            None => return false,
+        };
+        [&p.quote, &p.subquote, &p.subquote2, &p.tag, &p.tag2]
+            .iter()
+            .filter_map(|text| text.as_ref())
+            .any(|text| text.to_lowercase().contains(search))
+    }
+
+    fn matches(&self, view: &ListView, search: &str) -> bool {
+        (!view.without_quote || !self.has_quote())
+            && (!view.not_exported || !self.is_exported())
+            && (!view.too_small || self.is_too_small())
+            && (search.is_empty() || self.has_text(search))
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// Keep images of newly opened folder or project
+pub(crate) fn set_loaded(images: Vec<ImageInfo>) {
+    *rw_write!(LOADED) = images;
+}
+
+/// All images of opened folder or project
+pub(crate) fn loaded() -> Vec<ImageInfo> {
+    rw_read!(LOADED).clone()
+}
+
+/// Keep images of newly opened folder or project which can't be opened
+pub(crate) fn set_unsupported(images: Vec<UnsupportedImage>) {
+    *rw_write!(UNSUPPORTED) = images;
+}
+
+pub(crate) fn unsupported() -> Vec<UnsupportedImage> {
+    rw_read!(UNSUPPORTED).clone()
+}
+
+/// Position of image in loaded images from 1, 0 if it is not loaded
+pub(crate) fn position(image: &ImageInfo) -> usize {
+    rw_read!(LOADED)
+        .iter()
+        .position(|i| i.path == image.path)
+        .map_or(0, |i| i + 1)
+}
+
+/// Positions of all loaded images from 1, to look up many images
+pub(crate) fn positions() -> HashMap<PathBuf, usize> {
+    rw_read!(LOADED)
+        .iter()
+        .enumerate()
+        .map(|(i, image)| (image.path.clone(), i + 1))
+        .collect()
+}
+
+pub(crate) fn loaded_len() -> usize {
+    rw_read!(LOADED).len()
+}
+
+/// Add copy of image just before it, as file choice shows it
+pub(crate) fn insert_before(image: &ImageInfo, new_image: ImageInfo) {
+    let mut loaded = rw_write!(LOADED);
+    let idx = loaded
+        .iter()
+        .position(|i| i.path == image.path)
+        .unwrap_or(loaded.len());
+    loaded.insert(idx, new_image);
+}
+
+pub(crate) fn remove(image: &ImageInfo) {
+    rw_write!(LOADED).retain(|i| i.path != image.path);
+}
+
+/// Make loaded images of watched folders same as `found` in them. New images are put next to
+/// images of their folder by name. Returns true if any image was added or removed
+pub(crate) fn sync(found: &[ImageInfo], is_watched: impl Fn(&Path) -> bool) -> bool {
+    let mut loaded = rw_write!(LOADED);
+    let len = loaded.len();
+    loaded.retain(|i| !is_watched(&i.path) || found.iter().any(|f| f.path == i.path));
+    let mut changed = loaded.len() != len;
+
+    for image in found {
+        if loaded.iter().any(|i| i.path == image.path) {
+            continue;
+        }
+        let folder = image.path.parent();
+        let same_folder = |i: &ImageInfo| i.path.parent() == folder;
+        let idx = loaded
+            .iter()
+            .position(|i| same_folder(i) && i.path.file_name() > image.path.file_name())
+            .or_else(|| loaded.iter().rposition(same_folder).map(|i| i + 1))
+            .unwrap_or(loaded.len());
+        loaded.insert(idx, image.clone());
+        changed = true;
+    }
+    changed
+}
+
+pub(crate) fn view() -> ListView {
+    rw_read!(VIEW).clone()
+}
+
+pub(crate) fn set_view(view: ListView) {
+    *rw_write!(VIEW) = view;
+}
+
+/// Loaded images, filtered and sorted as per view
+pub(crate) fn shown() -> Vec<ImageInfo> {
+    let view = view();
+    let loaded = loaded();
+    if view.sort == SortMode::Loaded && !view.is_filtered() {
+        return loaded;
+    }
+
+    let search = view.search.trim().to_lowercase();
+    let mut details: Vec<ImageDetails> = loaded
+        .iter()
+        .enumerate()
+        .map(|(i, image)| ImageDetails::new(image, i + 1, &view))
+        .filter(|details| details.matches(&view, &search))
+        .collect();
+    match view.sort {
+        SortMode::Loaded => (),
+        SortMode::Name => details.sort_by_key(|d| {
+            d.image
+                .path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_lowercase()
+        }),
+        SortMode::Modified => details.sort_by_cached_key(|d| Reverse(modified(&d.image.path))),
+        SortMode::CaptureDate => details.sort_by_cached_key(|d| {
+            let date = Exif::read(&d.image.path).and_then(|exif| exif.capture_date());
+            (date.is_none(), Reverse(date))
+        }),
+        SortMode::Dimensions => {
+            details.sort_by_key(|d| Reverse(d.dimensions.map(|(w, h)| w as u64 * h as u64)))
+        }
+        SortMode::HasQuote => details.sort_by_key(|d| d.has_quote()),
+    }
+    details.into_iter().map(|d| d.image).collect()
+}
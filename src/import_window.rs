@@ -0,0 +1,145 @@
+/*
+    This file is part of Post Maker.
+    Post Maker is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+    Post Maker is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with Post Maker.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Preview of quotes import, which are written only after confirming
+use crate::{
+    globals,
+    quote_import::{self, ImportChange},
+    result_ext::ResultExt,
+};
+use fltk::{
+    app, browser::Browser, button::Button, enums, frame::Frame, group::Flex, image::SvgImage,
+    prelude::*, window::Window,
+};
+use std::{cell::RefCell, rc::Rc};
+
+pub(crate) struct ImportWindow {
+    pub(crate) win: Window,
+    info: Frame,
+    preview: Browser,
+    import_btn: Button,
+    close_btn: Button,
+    changes: Rc<RefCell<Vec<ImportChange>>>,
+    did_import: Rc<RefCell<bool>>,
+}
+
+impl ImportWindow {
+    pub(crate) fn new() -> Self {
+        let mut win = Window::new(0, 0, 640, 420, "Import Quotes").center_screen();
+        win.set_icon(Some(
+            SvgImage::from_data(globals::ICON.to_str().unwrap()).unwrap(),
+        ));
+
+        let mut main_flex = Flex::default().size_of_parent().column();
+
+        //info
+        let mut panel_flex = Flex::default().row();
+        panel_flex.set_size(&Frame::default(), 5);
+        let info = Frame::default().with_align(enums::Align::Left | enums::Align::Inside);
+        panel_flex.end();
+        main_flex.set_size(&panel_flex, 30);
+
+        // changes to images
+        let mut panel_flex = Flex::default().row();
+        panel_flex.set_size(&Frame::default(), 5);
+        let mut preview = Browser::default();
+        preview.set_column_widths(&[50, 220, 90]);
+        preview.set_column_char('\t');
+        preview.set_format_char('\u{1}');
+        panel_flex.set_size(&Frame::default(), 5);
+        panel_flex.end();
+
+        //buttons
+        let mut panel_flex = Flex::default().row();
+        Frame::default();
+        let import_btn = Button::default().with_label("Import");
+        panel_flex.set_size(&import_btn, 100);
+        let close_btn = Button::default().with_label("Cancel");
+        panel_flex.set_size(&close_btn, 100);
+        panel_flex.set_size(&Frame::default(), 1);
+        panel_flex.end();
+        main_flex.set_size(&panel_flex, 30);
+
+        main_flex.end();
+
+        win.end();
+        win.make_resizable(true);
+        win.make_modal(true);
+
+        let mut import_window = Self {
+            win,
+            info,
+            preview,
+            import_btn,
+            close_btn,
+            changes: Rc::new(RefCell::new(vec![])),
+            did_import: Rc::new(RefCell::new(false)),
+        };
+        import_window.event();
+
+        import_window
+    }
+
+    /// Show changes import would make, returns true if they were written
+    pub(crate) fn show(&mut self, changes: Vec<ImportChange>) -> bool {
+        self.preview.clear();
+        for change in &changes {
+            self.preview.add(&change.to_row());
+        }
+        let count = changes.iter().filter(|c| c.is_change()).count();
+        let not_found = changes.iter().filter(|c| c.image.is_none()).count();
+        let mut info = format!(
+            "{} images will change, {} unchanged",
+            count,
+            changes.len() - count - not_found
+        );
+        if not_found > 0 {
+            info = format!("{}, {} not found", info, not_found);
+        }
+        self.info.set_label(&info);
+        if count > 0 {
+            self.import_btn.activate();
+        } else {
+            self.import_btn.deactivate();
+        }
+        *self.changes.borrow_mut() = changes;
+        *self.did_import.borrow_mut() = false;
+
+        self.win.show();
+        while self.win.shown() {
+            app::wait();
+        }
+        *self.did_import.borrow()
+    }
+
+    // Set callbacks of elements
+    fn event(&mut self) {
+        // Import Button
+        let changes = Rc::clone(&self.changes);
+        let did_import = Rc::clone(&self.did_import);
+        let mut win = self.win.clone();
+        self.import_btn.set_callback(move |_| {
+            let result = quote_import::apply(&changes.borrow());
+            result.warn_log("Failed to import some quotes!");
+            *did_import.borrow_mut() = true;
+            win.hide();
+        });
+
+        // Close Button
+        let mut win = self.win.clone();
+        self.close_btn.set_callback(move |_| {
+            win.hide();
+        });
+    }
+}
@@ -0,0 +1,319 @@
+/*
+    This file is part of Post Maker.
+    Post Maker is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+    Post Maker is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with Post Maker.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Check if text is readable on the image and fix it if asked
+use crate::{
+    config::LegibilityFix,
+    utils::{self, Coord, ImageProperties, TextElement},
+};
+use image::{DynamicImage, GenericImageView};
+
+const WHITE: [u8; 3] = [255, 255, 255];
+const BLACK: [u8; 3] = [0, 0, 0];
+
+/// Average colour of image under a text element
+struct Background {
+    element: TextElement,
+    boxed: bool,
+    rgb: [f64; 3],
+}
+
+/// Contrast ratio of every text element against the pixels under it, after the translucent
+/// layer (and the box, if drawn) is applied over `image`.
+///
+/// `image` is the cropped image without layer and text, it can be of any size.
+pub(crate) fn contrast_ratios(
+    image: &DynamicImage,
+    prop: &ImageProperties,
+) -> Vec<(TextElement, f64)> {
+    let backgrounds = sample_backgrounds(image, prop);
+    ratios(
+        &backgrounds,
+        &prop.translucent_layer_color,
+        &prop.text_color,
+    )
+}
+
+/// Least legible text element and its contrast ratio
+pub(crate) fn worst_contrast(
+    image: &DynamicImage,
+    prop: &ImageProperties,
+) -> Option<(TextElement, f64)> {
+    lowest(&contrast_ratios(image, prop))
+}
+
+/// Change text colour or opacity of the layer, so that contrast of every text element reaches
+/// `minimum`. Returns true if properties were changed. If an opaque layer isn't enough,
+/// `LayerAlpha` falls back to changing text colour.
+pub(crate) fn fix(
+    image: &DynamicImage,
+    prop: &mut ImageProperties,
+    method: &LegibilityFix,
+    minimum: f64,
+) -> bool {
+    let backgrounds = sample_backgrounds(image, prop);
+    let layer = prop.translucent_layer_color;
+    let worst = match lowest(&ratios(&backgrounds, &layer, &prop.text_color)) {
+        Some((_, ratio)) if ratio < minimum => ratio,
+        _ => return false,
+    };
+
+    match method {
+        LegibilityFix::None => false,
+        LegibilityFix::TextColour => {
+            let (white, black) = (
+                lowest(&ratios(&backgrounds, &layer, &WHITE)).map_or(0.0, |(_, r)| r),
+                lowest(&ratios(&backgrounds, &layer, &BLACK)).map_or(0.0, |(_, r)| r),
+            );
+            let color = if white >= black { WHITE } else { BLACK };
+            if color == prop.text_color || white.max(black) <= worst {
+                return false;
+            }
+            prop.text_color = color;
+            true
+        }
+        LegibilityFix::LayerAlpha => {
+            let mut layer = layer;
+            while layer[3] < 255 {
+                layer[3] += 1;
+                match lowest(&ratios(&backgrounds, &layer, &prop.text_color)) {
+                    Some((_, ratio)) if ratio < minimum => (),
+                    _ => {
+                        prop.translucent_layer_color = layer;
+                        return true;
+                    }
+                }
+            }
+            fix(image, prop, &LegibilityFix::TextColour, minimum)
+        }
+    }
+}
+
+/// Contrast ratio of text of `prop` against area of `image` as `(x, y, width, height)`, after
+/// the layer (and the box, if `boxed`) is applied. `None` if area is outside image
+pub(crate) fn area_contrast(
+    image: &DynamicImage,
+    prop: &ImageProperties,
+    area: (f64, f64, f64, f64),
+    boxed: bool,
+) -> Option<f64> {
+    let background = Background {
+        element: TextElement::Quote,
+        boxed,
+        rgb: average(image, area)?,
+    };
+    lowest(&ratios(
+        &[background],
+        &prop.translucent_layer_color,
+        &prop.text_color,
+    ))
+    .map(|(_, ratio)| ratio)
+}
+
+/// Get average colour under every text element
+fn sample_backgrounds(image: &DynamicImage, prop: &ImageProperties) -> Vec<Background> {
+    let (width, height): (f64, f64) = Coord::from(image.dimensions()).into();
+    utils::text_blocks(prop, width, height)
+        .into_iter()
+        .filter_map(|block| {
+            Some(Background {
+                element: block.element,
+                boxed: block.boxed,
+                rgb: average(image, (block.x, block.y, block.width, block.height))?,
+            })
+        })
+        .collect()
+}
+
+/// Average colour of area of image, `None` if area is outside image
+fn average(image: &DynamicImage, (x, y, w, h): (f64, f64, f64, f64)) -> Option<[f64; 3]> {
+    let (width, height): (f64, f64) = Coord::from(image.dimensions()).into();
+    let (x0, y0) = (x.max(0.0) as u32, y.max(0.0) as u32);
+    let (x1, y1) = ((x + w).min(width) as u32, (y + h).min(height) as u32);
+    if x0 >= x1 || y0 >= y1 {
+        return None;
+    }
+
+    // Every second pixel is enough to get the average
+    let (mut sum, mut count) = ([0.0; 3], 0.0);
+    for y in (y0..y1).step_by(2) {
+        for x in (x0..x1).step_by(2) {
+            let pixel = image.get_pixel(x, y);
+            for i in 0..3 {
+                sum[i] += pixel[i] as f64;
+            }
+            count += 1.0;
+        }
+    }
+    Some(sum.map(|c| c / count))
+}
+
+fn ratios(backgrounds: &[Background], layer: &[u8; 4], text: &[u8; 3]) -> Vec<(TextElement, f64)> {
+    let text = relative_luminance(&text.map(|c| c as f64));
+    backgrounds
+        .iter()
+        .map(|background| {
+            let mut rgb = blend(&background.rgb, layer);
+            if background.boxed {
+                rgb = blend(&rgb, &utils::BOX_COLOR);
+            }
+            (
+                background.element,
+                contrast_ratio(text, relative_luminance(&rgb)),
+            )
+        })
+        .collect()
+}
+
+fn lowest(ratios: &[(TextElement, f64)]) -> Option<(TextElement, f64)> {
+    ratios
+        .iter()
+        .copied()
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+}
+
+/// Blend colour with rgba on top, same as overlay of layer
+fn blend(rgb: &[f64; 3], rgba: &[u8; 4]) -> [f64; 3] {
+    let alpha = rgba[3] as f64 / 255.0;
+    let mut out = [0.0; 3];
+    for i in 0..3 {
+        out[i] = rgba[i] as f64 * alpha + rgb[i] * (1.0 - alpha);
+    }
+    out
+}
+
+/// Relative luminance as defined by WCAG
+fn relative_luminance(rgb: &[f64; 3]) -> f64 {
+    let [r, g, b] = rgb.map(|c| {
+        let c = c / 255.0;
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    });
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+/// Contrast ratio as defined by WCAG, ranges from 1 to 21
+fn contrast_ratio(a: f64, b: f64) -> f64 {
+    let (light, dark) = if a > b { (a, b) } else { (b, a) };
+    (light + 0.05) / (dark + 0.05)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    /// Plain image with only tag 2, which is never drawn in a box
+    fn plain(rgb: [u8; 3], text_color: [u8; 3], layer: [u8; 4]) -> (DynamicImage, ImageProperties) {
+        let image = DynamicImage::ImageRgb8(RgbImage::from_pixel(400, 400, Rgb(rgb)));
+        let prop = ImageProperties {
+            original_dimension: (400.0, 400.0),
+            tag2: "Tag".to_owned(),
+            tag2_position: 200.0,
+            translucent_layer_color: layer,
+            text_color,
+            ..Default::default()
+        };
+        (image, prop)
+    }
+
+    fn worst(image: &DynamicImage, prop: &ImageProperties) -> f64 {
+        worst_contrast(image, prop).unwrap().1
+    }
+
+    #[test]
+    fn contrast_ratio_of_wcag() {
+        let (white, black) = (
+            relative_luminance(&[255.0; 3]),
+            relative_luminance(&[0.0; 3]),
+        );
+        assert!((white - 1.0).abs() < 1e-9);
+        assert!(black.abs() < 1e-9);
+        assert!((contrast_ratio(white, black) - 21.0).abs() < 1e-9);
+        assert!((contrast_ratio(black, white) - 21.0).abs() < 1e-9);
+        assert!((contrast_ratio(white, white) - 1.0).abs() < 1e-9);
+        // Grey of 118 is the lightest with 4.5:1 against white
+        let grey = relative_luminance(&[118.0; 3]);
+        assert!(contrast_ratio(white, grey) > 4.5);
+        assert!(contrast_ratio(white, relative_luminance(&[119.0; 3])) < 4.5);
+    }
+
+    #[test]
+    fn layer_is_blended_over_image() {
+        assert_eq!(blend(&[200.0; 3], &[0, 0, 0, 0]), [200.0; 3]);
+        assert_eq!(blend(&[200.0; 3], &[0, 0, 0, 255]), [0.0; 3]);
+        let half = blend(&[0.0; 3], &[255, 255, 255, 51]);
+        assert!((half[0] - 51.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ratio_is_measured_under_text() {
+        let (image, prop) = plain([255; 3], BLACK, [0; 4]);
+        let ratios = contrast_ratios(&image, &prop);
+        assert_eq!(ratios.len(), 1);
+        assert_eq!(ratios[0].0, TextElement::Tag2);
+        assert!((ratios[0].1 - 21.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn legible_text_is_not_changed() {
+        let (image, mut prop) = plain([0; 3], WHITE, [0; 4]);
+        for method in [LegibilityFix::TextColour, LegibilityFix::LayerAlpha] {
+            assert!(!fix(&image, &mut prop, &method, 4.5));
+        }
+        assert_eq!(prop.text_color, WHITE);
+        assert_eq!(prop.translucent_layer_color, [0; 4]);
+    }
+
+    #[test]
+    fn none_does_not_fix() {
+        let (image, mut prop) = plain([255; 3], WHITE, [0; 4]);
+        assert!(!fix(&image, &mut prop, &LegibilityFix::None, 4.5));
+        assert_eq!(prop.text_color, WHITE);
+    }
+
+    #[test]
+    fn text_colour_is_changed() {
+        let (image, mut prop) = plain([230; 3], WHITE, [0; 4]);
+        assert!(fix(&image, &mut prop, &LegibilityFix::TextColour, 4.5));
+        assert_eq!(prop.text_color, BLACK);
+        assert!(worst(&image, &prop) >= 4.5);
+    }
+
+    #[test]
+    fn layer_alpha_is_raised_until_minimum() {
+        let (image, mut prop) = plain([255; 3], WHITE, [0; 4]);
+        assert!(fix(&image, &mut prop, &LegibilityFix::LayerAlpha, 4.5));
+        assert_eq!(prop.text_color, WHITE);
+        let alpha = prop.translucent_layer_color[3];
+        assert!(alpha > 0 && alpha < 255);
+        assert!(worst(&image, &prop) >= 4.5);
+        // One step less is not enough
+        prop.translucent_layer_color[3] -= 1;
+        assert!(worst(&image, &prop) < 4.5);
+    }
+
+    #[test]
+    fn layer_alpha_falls_back_to_text_colour() {
+        // White layer can't darken the background
+        let (image, mut prop) = plain([255; 3], WHITE, [255, 255, 255, 0]);
+        assert!(fix(&image, &mut prop, &LegibilityFix::LayerAlpha, 4.5));
+        assert_eq!(prop.translucent_layer_color, [255, 255, 255, 0]);
+        assert_eq!(prop.text_color, BLACK);
+        assert!(worst(&image, &prop) >= 4.5);
+    }
+}
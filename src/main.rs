@@ -27,12 +27,31 @@ mod config_window;
 mod crop_window;
 mod dialog;
 mod draw_thread;
+mod error;
+mod exif;
 mod export_all_window;
+mod export_preview_window;
+mod export_report;
+mod folder_watcher;
+mod generate_window;
+mod generator;
 mod globals;
+mod history;
+mod icc_profile;
+mod image_list;
+mod import_window;
+mod legibility;
 mod main_window;
+mod manifest;
+mod metadata_export;
+mod project;
+mod properties_store;
+mod quote_import;
 mod result_ext;
+mod thumbnails;
 mod utils;
 
+use export_preview_window::ExportPreviewWindow;
 use fltk::{
     app::{channel, App},
     prelude::*,
@@ -47,10 +66,17 @@ use std::sync::{Arc, RwLock};
 pub(crate) enum AppMessage {
     /// Copy recived image buffer from draw_thread to Buffer for fltk frame
     RedrawMainWindowImage(Option<Vec<u8>>),
+    /// Encoded image to show in export preview
+    PreviewExport(Vec<u8>),
     Message(String),
     Alert(String),
+    /// Error shown in status bar of main window, which does not stop work of user
+    Status(String),
     ProgramPanicMessage(String),
 
+    /// Images in watched folders were added, removed or changed
+    FolderChanged(folder_watcher::FolderChange),
+
     // Only for Main windows
     DeleteImage,
 }
@@ -72,6 +98,10 @@ fn main() {
         panic!("Failed to start logger\n{:?}", e);
     }
 
+    if config::args().log_errors {
+        error::set_reporter(error::Reporter::Log);
+    }
+
     lazy_static::initialize(&globals::CONFIG);
 
     // Buffer which will br drawin on fltk frame
@@ -80,6 +110,7 @@ fn main() {
     let (main_sender, main_receiver) = channel::<AppMessage>();
     *rw_write!(globals::MAIN_SENDER) = Some(main_sender);
     let mut main_window = MainWindow::new(Arc::clone(&draw_buff));
+    let mut export_preview = ExportPreviewWindow::new();
 
     while app.wait() {
         if let Some(msg) = main_receiver.recv() {
@@ -89,10 +120,13 @@ fn main() {
                     *buff = data;
                     main_window.win.redraw();
                 }
+                AppMessage::PreviewExport(data) => export_preview.show(&data),
                 AppMessage::Message(msg) => {
                     dialog::message_default(&msg);
                 }
                 AppMessage::Alert(msg) => dialog::alert_default(&msg),
+                AppMessage::Status(msg) => main_window.show_status(&msg),
+                AppMessage::FolderChanged(change) => main_window.folder_changed(change),
                 AppMessage::ProgramPanicMessage(msg) => {
                     dialog::message_default(&msg);
                     std::process::exit(1);
@@ -116,4 +150,5 @@ fn main() {
             }
         }
     }
+    properties_store::flush().warn_log("Failed to save properties index!");
 }
@@ -15,16 +15,28 @@
 //! Main window where you do all editing
 use crate::{
     about_window::About,
+    config::{self, PropertiesStorage},
     config_window::ConfigWindow,
     crop_window::CropWindow,
     dialog,
     draw_thread::*,
     export_all_window::ExportAllWindow,
+    export_report,
+    folder_watcher::{self, FolderChange},
+    generate_window::GenerateWindow,
     globals,
+    history::{Edit, History},
+    image_list::{self, ListView, SortMode, UnsupportedImage},
+    import_window::ImportWindow,
+    metadata_export,
+    project::{self, Project},
+    properties_store, quote_import,
     result_ext::ResultExt,
-    utils::{self, ImageInfo, ImageProperties, ImageType},
+    thumbnails::Thumbnails,
+    utils::{self, ImageInfo, ImageProperties, ImagePropertiesFile, ImageType, TextElement},
 };
 use fltk::{
+    app,
     button::Button,
     dialog::NativeFileChooser,
     draw as dr, enums,
@@ -40,10 +52,12 @@ use fltk::{
     window::Window,
 };
 use std::{
+    cell::Cell,
     ffi::OsStr,
     fs,
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::Command,
+    rc::Rc,
     sync::Arc,
     sync::{mpsc, RwLock},
 };
@@ -90,9 +104,14 @@ pub(crate) struct MainWindow {
     pub(crate) count: Frame,
     pub(crate) dimension: Frame,
     pub(crate) page: Page,
+    pub(crate) thumbnails: Thumbnails,
     pub(crate) images_list: Arc<RwLock<Vec<ImageInfo>>>,
     pub(crate) draw_buff: Arc<RwLock<Option<Vec<u8>>>>,
     pub(crate) properties: Arc<RwLock<ImageProperties>>,
+    /// Undo and redo of edits
+    pub(crate) history: Arc<RwLock<History>>,
+    /// Open Folder also loads images in subfolders
+    pub(crate) include_subfolders: Rc<Cell<bool>>,
     pub(crate) sender: mpsc::Sender<DrawMessage>,
 }
 
@@ -102,11 +121,79 @@ pub(crate) struct Page {
     pub(crate) image: Frame,
     pub(crate) row_flex: Flex,
     pub(crate) col_flex: Flex,
+    /// Text element selected by clicking on image
+    pub(crate) selected: Arc<RwLock<Option<TextElement>>>,
+    /// Zoom and visible part of image
+    pub(crate) view: Arc<RwLock<View>>,
+    /// Why image could not be opened, shown in place of it
+    pub(crate) error: Arc<RwLock<Option<String>>>,
+}
+
+/// Zoom level of image in page
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum Zoom {
+    /// Whole image in page
+    #[default]
+    Fit,
+    /// One pixel of exported image is one pixel on screen
+    Actual,
+    /// One pixel of exported image is two pixels on screen
+    Double,
+}
+
+/// Part of image shown in page
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct View {
+    pub(crate) zoom: Zoom,
+    /// Top left corner of page on zoomed image
+    pub(crate) pan: (f64, f64),
+}
+
+impl View {
+    /// Size of whole image when drawn at current zoom
+    pub(crate) fn image_size(&self, prop: &ImageProperties) -> (f64, f64) {
+        match self.zoom {
+            Zoom::Fit => prop.dimension,
+            Zoom::Actual => utils::export_dimension(prop),
+            Zoom::Double => {
+                let (width, height) = utils::export_dimension(prop);
+                (width * 2.0, height * 2.0)
+            }
+        }
+    }
+
+    /// Change zoom, keeping the center of page at same place on image
+    pub(crate) fn set_zoom(&mut self, prop: &ImageProperties, zoom: Zoom) {
+        let (width, height) = self.image_size(prop);
+        let (page_width, page_height) = prop.dimension;
+        let center = (
+            (self.pan.0 + page_width / 2.0) / width,
+            (self.pan.1 + page_height / 2.0) / height,
+        );
+
+        self.zoom = zoom;
+        let (width, height) = self.image_size(prop);
+        self.pan = (
+            center.0 * width - page_width / 2.0,
+            center.1 * height - page_height / 2.0,
+        );
+        self.pan_by(prop, 0.0, 0.0);
+    }
+
+    /// Move page on image, without going out of it
+    pub(crate) fn pan_by(&mut self, prop: &ImageProperties, dx: f64, dy: f64) {
+        let (width, height) = self.image_size(prop);
+        let (page_width, page_height) = prop.dimension;
+        self.pan = (
+            (self.pan.0 + dx).clamp(0.0, (width - page_width).max(0.0)),
+            (self.pan.1 + dy).clamp(0.0, (height - page_height).max(0.0)),
+        );
+    }
 }
 
 impl MainWindow {
     pub(crate) fn new(draw_buff: Arc<RwLock<Option<Vec<u8>>>>) -> Self {
-        let mut win = Window::new(0, 0, 1100, 700, "Post Maker").center_screen();
+        let mut win = Window::new(0, 0, 1245, 700, "Post Maker").center_screen();
         win.set_icon(Some(
             SvgImage::from_data(globals::ICON.to_str().unwrap()).unwrap(),
         ));
@@ -348,6 +435,13 @@ impl MainWindow {
         right_controls_flex.end();
         workspace_flex.set_size(&right_controls_flex, 270);
 
+        // Thumbnails
+        let mut thumbnails_flex = Flex::default().row();
+        let thumbnails = Thumbnails::new();
+        thumbnails_flex.set_size(&thumbnails.scrollbar, 15);
+        thumbnails_flex.end();
+        workspace_flex.set_size(&thumbnails_flex, 145);
+
         workspace_flex.end();
 
         main_flex.end();
@@ -399,28 +493,101 @@ impl MainWindow {
             images_list: Arc::new(RwLock::new(vec![])),
             draw_buff,
             properties: Arc::clone(&properties),
+            history: Arc::new(RwLock::new(History::default())),
+            include_subfolders: Rc::new(Cell::new(false)),
             page: Page {
                 image: img_view,
                 row_flex: center_row_flex,
                 col_flex: center_col_flex,
+                selected: Arc::new(RwLock::new(None)),
+                view: Arc::new(RwLock::new(View::default())),
+                error: Arc::new(RwLock::new(None)),
             },
+            thumbnails,
             sender: rx,
         };
 
         if let Some(a) = &*rw_read!(globals::MAIN_SENDER) {
             spawn_image_thread(tx, a.to_owned(), Arc::clone(&properties), &main_win);
+            folder_watcher::spawn_watcher_thread(a.to_owned());
         }
         main_win.menu();
         main_win.draw();
+        main_win.drag();
         main_win.events();
         main_win
     }
 
-    /// Set menubar in window
+    /// Show error in status bar, whole message is in its tooltip
+    pub(crate) fn show_status(&mut self, msg: &str) {
+        self.status
+            .set_label(msg.lines().next().unwrap_or_default());
+        self.status.set_tooltip(msg);
+        self.status.redraw();
+    }
+
+    /// Update images after watched folders changed on disk. Open image is kept, and opened again
+    /// if it or its properties changed
+    pub(crate) fn folder_changed(&mut self, change: FolderChange) {
+        if change.generation != folder_watcher::generation() {
+            return;
+        }
+        let current = rw_read!(self.images_list)
+            .get(self.file_choice.value() as usize)
+            .cloned();
+        let current_path = current.as_ref().map(|image| image.path.clone());
+        self.thumbnails.forget(&change.changed);
+        self.thumbnails.forget(&change.properties_changed);
+
+        if image_list::sync(&change.images, folder_watcher::is_watched) {
+            let found = fill_file_choice(
+                image_list::shown(),
+                current_path.as_deref(),
+                Arc::clone(&self.images_list),
+                &mut self.file_choice,
+            );
+            if !found {
+                // Open image was removed
+                self.sender.send_it(DrawMessage::Open);
+                self.sender.send_it(DrawMessage::CheckImage);
+                return;
+            }
+            let shown = rw_read!(self.images_list).len();
+            set_count_label(&mut self.count, self.file_choice.value(), shown);
+        }
+
+        let current = match current {
+            Some(current) => current,
+            None => return,
+        };
+        let image_changed = change.changed.contains(&current.path);
+        let properties_changed = change.properties_changed.contains(&current.path) && {
+            // Saving open image changes its properties on disk too, to same as open ones
+            let open = ImagePropertiesFile::from(&*rw_read!(self.properties));
+            let on_disk = properties_store::read(&current).ok().flatten();
+            serde_json::to_string(&on_disk).ok() != serde_json::to_string(&Some(open)).ok()
+        };
+        if !image_changed && !properties_changed {
+            return;
+        }
+        if !rw_read!(self.properties).is_saved {
+            let choice = dialog::choice_default(
+                "Open image changed on disk, open it again and lose unsaved edits?",
+                "Open Again",
+                "Keep Edits",
+            );
+            if choice != 0 {
+                return;
+            }
+        }
+        self.sender.send_it(DrawMessage::Open);
+    }
+
     fn menu(&mut self) {
         let mut file_choice = self.file_choice.clone();
         let sender = self.sender.clone();
         let imgs = Arc::clone(&self.images_list);
+        let include_subfolders = Rc::clone(&self.include_subfolders);
         let mut win = self.win.clone();
         self.menubar.add(
             "&File/Open Folder...\t",
@@ -441,10 +608,144 @@ impl MainWindow {
                         .unwrap_or(OsStr::new("Unknown"))
                         .to_string_lossy()
                 ));
-                load_dir(&path, Arc::clone(&imgs), &mut file_choice, &sender);
+                *rw_write!(globals::PROJECT) = None;
+                load_dir(
+                    &path,
+                    include_subfolders.get(),
+                    Arc::clone(&imgs),
+                    &mut file_choice,
+                    &sender,
+                );
+            },
+        );
+
+        let include_subfolders = Rc::clone(&self.include_subfolders);
+        self.menubar.add(
+            "&File/Include Subfolders\t",
+            Shortcut::None,
+            menu::MenuFlag::Toggle,
+            move |m| {
+                if let Some(item) = m.at(m.value()) {
+                    include_subfolders.set(item.value());
+                }
+            },
+        );
+
+        self.menubar.add(
+            "&File/New Project...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            move |_| {
+                if image_list::loaded_len() == 0 {
+                    utils::show_alert("Open a folder to make project of it!");
+                    return;
+                }
+                let mut chooser =
+                    NativeFileChooser::new(fltk::dialog::FileDialogType::BrowseSaveFile);
+                chooser.set_option(fltk::dialog::FileDialogOptions::SaveAsConfirm);
+                chooser.set_filter(&format!("*.{}", project::PROJECT_EXTENSION));
+                chooser.show();
+                let mut path = chooser.filename();
+                if path.as_os_str().is_empty() {
+                    return;
+                }
+                if path.extension().is_none() {
+                    path.set_extension(project::PROJECT_EXTENSION);
+                }
+                let project = Project::new(&path, &image_list::loaded());
+                match project.save(&path) {
+                    Ok(_) => {
+                        project::add_recent(&path);
+                        *rw_write!(globals::PROJECT) = Some((path, project));
+                    }
+                    Err(e) => utils::show_alert(&e.to_string()),
+                }
             },
         );
 
+        let file_choice = self.file_choice.clone();
+        let sender = self.sender.clone();
+        let properties = Arc::clone(&self.properties);
+        let imgs = Arc::clone(&self.images_list);
+        let win = self.win.clone();
+        self.menubar.add(
+            "&File/Open Project...\t",
+            Shortcut::Ctrl | Shortcut::Shift | 'o',
+            menu::MenuFlag::Normal,
+            move |menubar| {
+                let mut chooser = NativeFileChooser::new(fltk::dialog::FileDialogType::BrowseFile);
+                chooser.set_option(fltk::dialog::FileDialogOptions::UseFilterExt);
+                chooser.set_filter(&format!("*.{}", project::PROJECT_EXTENSION));
+                chooser.show();
+                let path = chooser.filename();
+                if path.is_file() {
+                    let path = fs::canonicalize(&path).unwrap_or(path);
+                    open_project(
+                        &path,
+                        &imgs,
+                        &properties,
+                        &file_choice,
+                        &sender,
+                        &win,
+                        menubar,
+                    );
+                }
+            },
+        );
+
+        self.menubar.add(
+            "&File/Save Project\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            move |_| {
+                let mut project = rw_write!(globals::PROJECT);
+                match &mut *project {
+                    Some((path, project)) => {
+                        project.set_images(path, &image_list::loaded());
+                        project.save(path).warn_log("Failed to save project!");
+                    }
+                    None => utils::show_alert("No project is open, use New Project"),
+                }
+            },
+        );
+
+        self.menubar.add(
+            "&File/Project Export Folder...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            move |_| {
+                let current = match &*rw_read!(globals::PROJECT) {
+                    Some((_, project)) => project.export_dir.clone().unwrap_or_default(),
+                    None => {
+                        utils::show_alert("No project is open, use New Project");
+                        return;
+                    }
+                };
+                // Lock is not held while dialog is open, as windows are drawn meanwhile
+                let dir = match dialog::input_default(
+                    "Folder of exported images, relative to project file.\n\
+                    Empty to use folder of config",
+                    &current,
+                ) {
+                    Some(dir) => dir.trim().to_owned(),
+                    None => return,
+                };
+                if let Some((path, project)) = &mut *rw_write!(globals::PROJECT) {
+                    project.export_dir = (!dir.is_empty()).then_some(dir);
+                    project.save(path).warn_log("Failed to save project!");
+                }
+            },
+        );
+
+        update_recent_projects(
+            &mut self.menubar,
+            &self.images_list,
+            &self.properties,
+            &self.file_choice,
+            &self.sender,
+            &self.win,
+        );
+
         let sender = self.sender.clone();
         let properties = Arc::clone(&self.properties);
         self.menubar.add(
@@ -458,6 +759,161 @@ impl MainWindow {
             },
         );
 
+        let sender = self.sender.clone();
+        self.menubar.add(
+            "&File/Preview Export...\t",
+            Shortcut::Ctrl | 'p',
+            menu::MenuFlag::Normal,
+            move |_| {
+                sender.send_it(DrawMessage::PreviewExport);
+            },
+        );
+
+        let sender = self.sender.clone();
+        let properties = Arc::clone(&self.properties);
+        let mut import_window = ImportWindow::new();
+        self.menubar.add(
+            "&File/Import Quotes...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            move |_| {
+                if !rw_read!(properties).is_saved {
+                    let save = fltk::dialog::choice2_default("Save?", "yes", "no", "cancel");
+                    match save.unwrap_or(-1) {
+                        0 => sender.send_it(DrawMessage::Save),
+                        1 => {}
+                        _ => return,
+                    }
+                }
+
+                let mut chooser = NativeFileChooser::new(fltk::dialog::FileDialogType::BrowseFile);
+                chooser.set_option(fltk::dialog::FileDialogOptions::UseFilterExt);
+                chooser.set_filter("*.{csv,json}");
+                chooser.show();
+                let path = chooser.filename();
+                if !path.is_file() {
+                    return;
+                }
+                let rows = match quote_import::read(&path) {
+                    Ok(rows) => rows,
+                    Err(e) => {
+                        utils::show_alert(&e.to_string());
+                        return;
+                    }
+                };
+                let changes = quote_import::plan(&rows, &image_list::loaded());
+                if import_window.show(changes) {
+                    sender.send_it(DrawMessage::Open);
+                }
+            },
+        );
+
+        let imgs = Arc::clone(&self.images_list);
+        self.menubar.add(
+            "&File/Export Metadata...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            move |_| {
+                let mut chooser =
+                    NativeFileChooser::new(fltk::dialog::FileDialogType::BrowseSaveFile);
+                chooser.set_option(fltk::dialog::FileDialogOptions::SaveAsConfirm);
+                chooser.set_filter("*.{csv,json}");
+                chooser.set_preset_file("metadata.csv");
+                chooser.show();
+                let mut path = chooser.filename();
+                if path.as_os_str().is_empty() {
+                    return;
+                }
+                if path.extension().is_none() {
+                    path.set_extension("csv");
+                }
+                match metadata_export::write(&path, &rw_read!(imgs)) {
+                    Ok(count) => dialog::message_default(&format!(
+                        "Wrote properties of {} images to {}",
+                        count,
+                        path.display()
+                    )),
+                    Err(e) => utils::show_alert(&e.to_string()),
+                }
+            },
+        );
+
+        let imgs = Arc::clone(&self.images_list);
+        self.menubar.add(
+            "&File/Move Properties to Index...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            move |_| {
+                let imgs = rw_read!(imgs);
+                if imgs.is_empty() {
+                    utils::show_alert("No images are opened!");
+                    return;
+                }
+                let delete = match fltk::dialog::choice2_default(
+                    "Move .prop files of opened images to index file?",
+                    "Keep files",
+                    "Delete files",
+                    "Cancel",
+                ) {
+                    Some(0) => false,
+                    Some(1) => true,
+                    _ => return,
+                };
+                let result = properties_store::migrate(&imgs, delete);
+
+                // Index has properties now, so use it
+                if rw_read!(globals::CONFIG).properties_storage != PropertiesStorage::Index {
+                    rw_write!(globals::CONFIG).properties_storage = PropertiesStorage::Index;
+                    let mut configs = config::get_configs().unwrap_or_default();
+                    if let Some(conf) = configs.get_mut(&*rw_read!(globals::CONFIG_NAME)) {
+                        conf.properties_storage = PropertiesStorage::Index;
+                    }
+                    config::save_configs(configs);
+                }
+
+                match result {
+                    Ok(count) => dialog::message_default(&format!(
+                        "Moved properties of {} images to index",
+                        count
+                    )),
+                    Err(e) => utils::show_alert(&e.to_string()),
+                }
+            },
+        );
+
+        let mut file_choice = self.file_choice.clone();
+        let sender = self.sender.clone();
+        let properties = Arc::clone(&self.properties);
+        let imgs = Arc::clone(&self.images_list);
+        let mut win = self.win.clone();
+        let mut generate_window = GenerateWindow::new();
+        self.menubar.add(
+            "&File/Generate from Quotes...\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            move |_| {
+                if !rw_read!(properties).is_saved {
+                    let save = fltk::dialog::choice2_default("Save?", "yes", "no", "cancel");
+                    match save.unwrap_or(-1) {
+                        0 => sender.send_it(DrawMessage::Save),
+                        1 => {}
+                        _ => return,
+                    }
+                }
+
+                if let Some(path) = generate_window.show() {
+                    let path = fs::canonicalize(&path).unwrap_or(path);
+                    win.set_label(&format!(
+                        "{} - Post Maker",
+                        path.file_name()
+                            .unwrap_or(OsStr::new("Unknown"))
+                            .to_string_lossy()
+                    ));
+                    load_dir(&path, false, Arc::clone(&imgs), &mut file_choice, &sender);
+                }
+            },
+        );
+
         let sender = self.sender.clone();
         self.menubar.add(
             "&Actions/Show Details...\t",
@@ -476,7 +932,7 @@ impl MainWindow {
             move |_| {
                 let props = rw_read!(properties);
                 if let Some(prop) = &props.image_info {
-                    let export = prop.path.parent().unwrap().join("export");
+                    let export = utils::get_export_dir(&prop.path);
                     if export.exists() {
                         if cfg!(windows) {
                             Command::new("explorer")
@@ -502,7 +958,8 @@ impl MainWindow {
         );
 
         let mut win = self.win.clone();
-        let mut export_all = ExportAllWindow::new(Arc::clone(&self.images_list));
+        let mut export_all =
+            ExportAllWindow::new(Arc::clone(&self.images_list), Arc::clone(&self.properties));
         self.menubar.add(
             "&Actions/Export All with Quotes...\t",
             Shortcut::None,
@@ -522,13 +979,21 @@ impl MainWindow {
             menu::MenuFlag::Normal,
             move |_| {
                 win.deactivate();
-                if dialog::choice_default("Do you want to remove exports?", "Yes", "No") == 0 {
-                    let props = rw_read!(properties);
-                    if let Some(prop) = &props.image_info {
-                        let export = prop.path.parent().unwrap().join("export");
-                        if export.exists() {
-                            fs::remove_dir_all(&export)
-                                .warn_log("Failed to remove export directory");
+                let export = rw_read!(properties)
+                    .image_info
+                    .as_ref()
+                    .map(|prop| utils::get_export_dir(&prop.path));
+                if let Some(export) = export.filter(|export| export.exists()) {
+                    if has_loaded_images(&export) {
+                        dialog::alert_default(&format!(
+                            "{} has images which are open, exports are not deleted",
+                            export.display()
+                        ));
+                    } else {
+                        let msg = format!("Do you want to remove exports in {}?", export.display());
+                        if dialog::choice_default(&msg, "Yes", "No") == 0 {
+                            export_report::delete_exports(&export)
+                                .warn_log("Failed to remove exported images");
                         }
                     }
                 }
@@ -538,6 +1003,26 @@ impl MainWindow {
             },
         );
 
+        let sender = self.sender.clone();
+        self.menubar.add(
+            "&Edit/Undo\t",
+            Shortcut::Ctrl | 'z',
+            menu::MenuFlag::Normal,
+            move |_| {
+                sender.send_it(DrawMessage::Undo);
+            },
+        );
+
+        let sender = self.sender.clone();
+        self.menubar.add(
+            "&Edit/Redo\t",
+            Shortcut::Ctrl | Shortcut::Shift | 'z',
+            menu::MenuFlag::MenuDivider,
+            move |_| {
+                sender.send_it(DrawMessage::Redo);
+            },
+        );
+
         let mut config_window = ConfigWindow::new();
         let sender = self.sender.clone();
         let mut image = self.page.image.clone();
@@ -554,6 +1039,119 @@ impl MainWindow {
             },
         );
 
+        for (label, shortcut, zoom) in [
+            ("&View/Fit\t", Shortcut::Ctrl | '0', Zoom::Fit),
+            ("&View/100%\t", Shortcut::Ctrl | '1', Zoom::Actual),
+            ("&View/200%\t", Shortcut::Ctrl | '2', Zoom::Double),
+        ] {
+            let properties = Arc::clone(&self.properties);
+            let view = Arc::clone(&self.page.view);
+            let sender = self.sender.clone();
+            let mut image = self.page.image.clone();
+            let idx = self
+                .menubar
+                .add(label, shortcut, menu::MenuFlag::Radio, move |_| {
+                    rw_write!(view).set_zoom(&rw_read!(properties), zoom);
+                    sender.send_it(DrawMessage::Flush);
+                    image.redraw();
+                });
+            if zoom == Zoom::Fit {
+                if let Some(mut item) = self.menubar.at(idx) {
+                    item.set();
+                }
+            }
+        }
+
+        for sort in SortMode::ALL {
+            let imgs = Arc::clone(&self.images_list);
+            let properties = Arc::clone(&self.properties);
+            let mut file_choice = self.file_choice.clone();
+            let sender = self.sender.clone();
+            let idx = self.menubar.add(
+                &format!("&View/Sort By/{}\t", sort.label()),
+                Shortcut::None,
+                menu::MenuFlag::Radio,
+                move |_| {
+                    image_list::set_view(ListView {
+                        sort,
+                        ..image_list::view()
+                    });
+                    refresh_images(Arc::clone(&imgs), &properties, &mut file_choice, &sender);
+                },
+            );
+            if sort == SortMode::Loaded {
+                if let Some(mut item) = self.menubar.at(idx) {
+                    item.set();
+                }
+            }
+        }
+
+        type Filter = fn(&mut ListView) -> &mut bool;
+        let filters: [(&str, Filter); 3] = [
+            ("&View/Filter/Without Quote\t", |view| {
+                &mut view.without_quote
+            }),
+            ("&View/Filter/Not Exported\t", |view| &mut view.not_exported),
+            ("&View/Filter/Too Small\t", |view| &mut view.too_small),
+        ];
+        let mut filter_items = vec![];
+        for (label, filter) in filters {
+            let imgs = Arc::clone(&self.images_list);
+            let properties = Arc::clone(&self.properties);
+            let mut file_choice = self.file_choice.clone();
+            let sender = self.sender.clone();
+            let idx = self
+                .menubar
+                .add(label, Shortcut::None, menu::MenuFlag::Toggle, move |m| {
+                    let mut view = image_list::view();
+                    *filter(&mut view) = m.at(m.value()).is_some_and(|item| item.value());
+                    image_list::set_view(view);
+                    refresh_images(Arc::clone(&imgs), &properties, &mut file_choice, &sender);
+                });
+            filter_items.push(idx);
+        }
+
+        let imgs = Arc::clone(&self.images_list);
+        let properties = Arc::clone(&self.properties);
+        let mut file_choice = self.file_choice.clone();
+        let sender = self.sender.clone();
+        self.menubar.add(
+            "&View/Filter/Search Texts...\t",
+            Shortcut::Ctrl | 'f',
+            menu::MenuFlag::Normal,
+            move |_| {
+                let view = image_list::view();
+                let search = match dialog::input_default("Show images having text", &view.search) {
+                    Some(search) => search.trim().to_owned(),
+                    None => return,
+                };
+                image_list::set_view(ListView { search, ..view });
+                refresh_images(Arc::clone(&imgs), &properties, &mut file_choice, &sender);
+            },
+        );
+
+        let imgs = Arc::clone(&self.images_list);
+        let properties = Arc::clone(&self.properties);
+        let mut file_choice = self.file_choice.clone();
+        let sender = self.sender.clone();
+        self.menubar.add(
+            "&View/Filter/Clear Filters\t",
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            move |m| {
+                for idx in &filter_items {
+                    if let Some(mut item) = m.at(*idx) {
+                        item.clear();
+                    }
+                }
+                image_list::set_view(ListView {
+                    sort: image_list::view().sort,
+                    ..Default::default()
+                });
+                refresh_images(Arc::clone(&imgs), &properties, &mut file_choice, &sender);
+            },
+        );
+
         let mut about_win = About::new();
         self.menubar.add(
             "&Help/About...\t",
@@ -569,8 +1167,12 @@ impl MainWindow {
     fn draw(&mut self) {
         let buff = Arc::clone(&self.draw_buff);
         let properties = Arc::clone(&self.properties);
+        let selected = Arc::clone(&self.page.selected);
+        let view = Arc::clone(&self.page.view);
+        let error = Arc::clone(&self.page.error);
         self.page.image.draw(move |f| {
-            let (width, height) = rw_read!(properties).dimension;
+            let prop = rw_read!(properties);
+            let (width, height) = prop.dimension;
             if let Some(image) = &*rw_read!(buff) {
                 dr::draw_image(
                     &image,
@@ -581,22 +1183,217 @@ impl MainWindow {
                     enums::ColorDepth::Rgb8,
                 )
                 .unwrap();
+            } else {
+                if let Some(error) = &*rw_read!(error) {
+                    draw_load_error(f, error);
+                }
+                return;
             }
+
+            let element = match *rw_read!(selected) {
+                Some(element) => element,
+                None => return,
+            };
+            let view = *rw_read!(view);
+            let (image_width, image_height) = view.image_size(&prop);
+            let block = match utils::text_blocks(&prop, image_width, image_height)
+                .into_iter()
+                .find(|b| b.element == element)
+            {
+                Some(block) => block,
+                None => return,
+            };
+            let (pan_x, pan_y) = view.pan;
+
+            dr::push_clip(f.x(), f.y(), width as i32, height as i32);
+
+            // Guides at center and thirds, highlighted when element is snapped to it
+            let mid = block.y + block.height / 2.0;
+            dr::set_line_style(dr::LineStyle::Dash, 1);
+            for guide in snap_guides(image_height) {
+                if (mid - guide).abs() < 1.0 {
+                    dr::set_draw_color(enums::Color::Cyan);
+                } else {
+                    dr::set_draw_color(enums::Color::from_rgb(160, 160, 160));
+                }
+                let y = f.y() + (guide - pan_y) as i32;
+                dr::draw_line(f.x(), y, f.x() + width as i32, y);
+            }
+            dr::set_draw_color(enums::Color::from_rgb(160, 160, 160));
+            let x = f.x() + (image_width / 2.0 - pan_x) as i32;
+            dr::draw_line(x, f.y(), x, f.y() + height as i32);
+            dr::set_line_style(dr::LineStyle::Solid, 0);
+
+            // Outline of selected element
+            dr::set_draw_color(enums::Color::Yellow);
+            dr::draw_rect(
+                f.x() + (block.x - pan_x) as i32 - 2,
+                f.y() + (block.y - pan_y) as i32 - 2,
+                block.width as i32 + 4,
+                block.height as i32 + 4,
+            );
+
+            dr::pop_clip();
         })
     }
 
+    /// Select and move text elements with mouse on image, or move the view when zoomed
+    fn drag(&mut self) {
+        let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
+        let selected = Arc::clone(&self.page.selected);
+        let view = Arc::clone(&self.page.view);
+        let sender = self.sender.clone();
+        let mut spinners = [
+            self.quote_position.clone(),
+            self.subquote_position.clone(),
+            self.subquote2_position.clone(),
+            self.tag_position.clone(),
+            self.tag2_position.clone(),
+        ];
+        let mut sliders = [
+            self.quote_position_slider.clone(),
+            self.subquote_position_slider.clone(),
+            self.subquote2_position_slider.clone(),
+            self.tag_position_slider.clone(),
+            self.tag2_position_slider.clone(),
+        ];
+        // Distance of mouse from top of selected element when it was clicked
+        let mut grab = 0.0;
+        // Last mouse position while moving the view
+        let mut panning: Option<(i32, i32)> = None;
+        self.page.image.handle(move |f, ev| match ev {
+            enums::Event::Push => {
+                let prop = rw_read!(properties);
+                let view = *rw_read!(view);
+                let (image_width, image_height) = view.image_size(&prop);
+                let (x, y) = (
+                    (app::event_x() - f.x()) as f64 + view.pan.0,
+                    (app::event_y() - f.y()) as f64 + view.pan.1,
+                );
+                // Last drawn element is on top
+                let block = utils::text_blocks(&prop, image_width, image_height)
+                    .into_iter()
+                    .rev()
+                    .find(|b| x >= b.x && x <= b.x + b.width && y >= b.y && y <= b.y + b.height);
+                *rw_write!(selected) = block.map(|b| b.element);
+                panning = None;
+                match block {
+                    Some(block) => grab = y - block.y,
+                    None if view.zoom != Zoom::Fit => {
+                        panning = Some((app::event_x(), app::event_y()));
+                    }
+                    None => (),
+                }
+                f.redraw();
+                true
+            }
+            enums::Event::Drag => {
+                if let Some((last_x, last_y)) = panning {
+                    let (x, y) = (app::event_x(), app::event_y());
+                    rw_write!(view).pan_by(
+                        &rw_read!(properties),
+                        (last_x - x) as f64,
+                        (last_y - y) as f64,
+                    );
+                    panning = Some((x, y));
+                    sender.send_it(DrawMessage::Flush);
+                    return true;
+                }
+
+                let element = match *rw_read!(selected) {
+                    Some(element) => element,
+                    None => return false,
+                };
+                let mut prop = rw_write!(properties);
+                let view = *rw_read!(view);
+                let (image_width, image_height) = view.image_size(&prop);
+                let block = match utils::text_blocks(&prop, image_width, image_height)
+                    .into_iter()
+                    .find(|b| b.element == element)
+                {
+                    Some(block) => block,
+                    None => return false,
+                };
+
+                let mut top = (app::event_y() - f.y()) as f64 + view.pan.1 - grab;
+                let mid = top + block.height / 2.0;
+                if let Some(guide) = snap_guides(image_height)
+                    .into_iter()
+                    .find(|guide| (mid - guide).abs() < SNAP_DISTANCE)
+                {
+                    top = guide - block.height / 2.0;
+                }
+                let original_height = prop.original_dimension.1;
+                let position = (top * original_height / image_height).clamp(0.0, original_height);
+                if position == *prop.position_mut(element) {
+                    return true;
+                }
+
+                rw_write!(history).checkpoint(&prop, drag_edit(element));
+                *prop.position_mut(element) = position;
+                prop.is_saved = false;
+                let i = element as usize;
+                spinners[i].set_value(position);
+                sliders[i].set_value(position);
+                sender.send_it(DrawMessage::RedrawToBuffer);
+                sender.send_it(DrawMessage::Flush);
+                f.redraw();
+                true
+            }
+            enums::Event::Released => {
+                rw_write!(history).seal();
+                panning.take().is_some() || rw_read!(selected).is_some()
+            }
+            enums::Event::MouseWheel => {
+                let mut view = rw_write!(view);
+                if view.zoom == Zoom::Fit {
+                    return false;
+                }
+                let step = match app::event_dy() {
+                    app::MouseWheel::Up => -50.0,
+                    app::MouseWheel::Down => 50.0,
+                    _ => return false,
+                };
+                view.pan_by(&rw_read!(properties), 0.0, step);
+                sender.send_it(DrawMessage::Flush);
+                true
+            }
+            _ => false,
+        });
+    }
+
     /// Set callbacks of elements
     fn events(&mut self) {
+        self.thumbnails.event(
+            Arc::clone(&self.images_list),
+            Arc::clone(&self.properties),
+            self.file_choice.clone(),
+            self.sender.clone(),
+        );
+
         // Resest Button for FileChoice
         let mut file_choice = self.file_choice.clone();
         let sender = self.sender.clone();
         let imgs = Arc::clone(&self.images_list);
+        let include_subfolders = Rc::clone(&self.include_subfolders);
+        let mut thumbnails = self.thumbnails.clone();
         self.reset_file_choice.set_callback(move |_| {
-            let path = match rw_read!(imgs).first() {
-                Some(image_info) => image_info.path.parent().unwrap().to_path_buf(),
-                None => return,
+            let path = {
+                let imgs = rw_read!(imgs);
+                if imgs.is_empty() {
+                    return;
+                }
+                utils::common_folder(&imgs)
             };
-            load_dir(&path, Arc::clone(&imgs), &mut file_choice, &sender);
+            thumbnails.clear();
+            load_dir(
+                &path,
+                include_subfolders.get(),
+                Arc::clone(&imgs),
+                &mut file_choice,
+                &sender,
+            );
         });
 
         // Reset Button for Translucent Layer
@@ -605,8 +1402,10 @@ impl MainWindow {
         let mut image = self.page.image.clone();
         let sender = self.sender.clone();
         let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
         self.reset_translucent_layer_btn.set_callback(move |_| {
             let mut prop = rw_write!(properties);
+            rw_write!(history).checkpoint(&prop, Edit::Single);
             let color = rw_read!(globals::CONFIG).color_layer;
             prop.translucent_layer_color = color;
             prop.is_saved = false;
@@ -623,8 +1422,10 @@ impl MainWindow {
         let mut image = self.page.image.clone();
         let sender = self.sender.clone();
         let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
         self.reset_quote_position_btn.set_callback(move |_| {
             let mut prop = rw_write!(properties);
+            rw_write!(history).checkpoint(&prop, Edit::Single);
             let height = prop.original_dimension.1;
             let pos = height * rw_read!(globals::CONFIG).quote_position_ratio;
             prop.quote_position = pos;
@@ -643,8 +1444,10 @@ impl MainWindow {
         let mut image = self.page.image.clone();
         let sender = self.sender.clone();
         let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
         self.reset_subquote_position_btn.set_callback(move |_| {
             let mut prop = rw_write!(properties);
+            rw_write!(history).checkpoint(&prop, Edit::Single);
             let height = prop.original_dimension.1;
             let pos = height * rw_read!(globals::CONFIG).subquote_position_ratio;
             prop.subquote_position = pos;
@@ -663,8 +1466,10 @@ impl MainWindow {
         let mut image = self.page.image.clone();
         let sender = self.sender.clone();
         let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
         self.reset_subquote2_position_btn.set_callback(move |_| {
             let mut prop = rw_write!(properties);
+            rw_write!(history).checkpoint(&prop, Edit::Single);
             let height = prop.original_dimension.1;
             let pos = height * rw_read!(globals::CONFIG).subquote2_position_ratio;
             prop.subquote2_position = pos;
@@ -683,8 +1488,10 @@ impl MainWindow {
         let mut image = self.page.image.clone();
         let sender = self.sender.clone();
         let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
         self.reset_tag_position_btn.set_callback(move |_| {
             let mut prop = rw_write!(properties);
+            rw_write!(history).checkpoint(&prop, Edit::Single);
             let height = prop.original_dimension.1;
             let pos = height * rw_read!(globals::CONFIG).tag_y_position_ratio;
             prop.tag_position = pos;
@@ -703,8 +1510,10 @@ impl MainWindow {
         let mut image = self.page.image.clone();
         let sender = self.sender.clone();
         let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
         self.reset_tag2_position_btn.set_callback(move |_| {
             let mut prop = rw_write!(properties);
+            rw_write!(history).checkpoint(&prop, Edit::Single);
             let height = prop.original_dimension.1;
             let pos = height * rw_read!(globals::CONFIG).tag2_position_ratio;
             prop.tag2_position = pos;
@@ -758,12 +1567,14 @@ impl MainWindow {
 
         // Crop Button
         let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
         let mut crop_win = CropWindow::new();
         let sender = self.sender.clone();
         self.crop_btn.set_callback(move |_| {
             let mut prop = rw_write!(properties);
             if let Some(image_info) = &prop.image_info {
                 if let Some((x, y)) = crop_win.load_to_crop(&image_info, prop.crop_position) {
+                    rw_write!(history).checkpoint(&prop, Edit::Single);
                     sender.send_it(DrawMessage::ChangeCrop((x, y)));
                     prop.is_saved = false;
                 }
@@ -837,11 +1648,18 @@ impl MainWindow {
 
         // Name Prefix Input
         let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
         self.name_prefix.handle(move |f, ev| {
             if ev == enums::Event::KeyUp {
                 let mut prop = rw_write!(properties);
+                if prop.name_prefix == f.value() {
+                    return true;
+                }
+                rw_write!(history).checkpoint(&prop, Edit::NamePrefix);
                 prop.name_prefix = f.value();
                 prop.is_saved = false;
+            } else if ev == enums::Event::Unfocus {
+                rw_write!(history).seal();
             }
             true
         });
@@ -849,15 +1667,22 @@ impl MainWindow {
         // Quote Input
         let mut image = self.page.image.clone();
         let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
         let sender = self.sender.clone();
         self.quote.handle(move |f, ev| {
             if ev == enums::Event::KeyUp {
                 let mut prop = rw_write!(properties);
+                if prop.quote == f.value() {
+                    return true;
+                }
+                rw_write!(history).checkpoint(&prop, Edit::Quote);
                 prop.quote = f.value();
                 prop.is_saved = false;
                 sender.send_it(DrawMessage::RedrawToBuffer);
                 sender.send_it(DrawMessage::Flush);
                 image.redraw();
+            } else if ev == enums::Event::Unfocus {
+                rw_write!(history).seal();
             }
             true
         });
@@ -865,15 +1690,22 @@ impl MainWindow {
         // Subquote Input
         let mut image = self.page.image.clone();
         let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
         let sender = self.sender.clone();
         self.subquote.handle(move |f, ev| {
             if ev == enums::Event::KeyUp {
                 let mut prop = rw_write!(properties);
+                if prop.subquote == f.value() {
+                    return true;
+                }
+                rw_write!(history).checkpoint(&prop, Edit::Subquote);
                 prop.subquote = f.value();
                 prop.is_saved = false;
                 sender.send_it(DrawMessage::RedrawToBuffer);
                 sender.send_it(DrawMessage::Flush);
                 image.redraw();
+            } else if ev == enums::Event::Unfocus {
+                rw_write!(history).seal();
             }
             true
         });
@@ -881,15 +1713,22 @@ impl MainWindow {
         // Subquote2 Input
         let mut image = self.page.image.clone();
         let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
         let sender = self.sender.clone();
         self.subquote2.handle(move |f, ev| {
             if ev == enums::Event::KeyUp {
                 let mut prop = rw_write!(properties);
+                if prop.subquote2 == f.value() {
+                    return true;
+                }
+                rw_write!(history).checkpoint(&prop, Edit::Subquote2);
                 prop.subquote2 = f.value();
                 prop.is_saved = false;
                 sender.send_it(DrawMessage::RedrawToBuffer);
                 sender.send_it(DrawMessage::Flush);
                 image.redraw();
+            } else if ev == enums::Event::Unfocus {
+                rw_write!(history).seal();
             }
             true
         });
@@ -897,15 +1736,22 @@ impl MainWindow {
         // Tag Input
         let mut image = self.page.image.clone();
         let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
         let sender = self.sender.clone();
         self.tag.handle(move |f, ev| {
             if ev == enums::Event::KeyUp {
                 let mut prop = rw_write!(properties);
+                if prop.tag == f.value() {
+                    return true;
+                }
+                rw_write!(history).checkpoint(&prop, Edit::Tag);
                 prop.tag = f.value();
                 prop.is_saved = false;
                 sender.send_it(DrawMessage::RedrawToBuffer);
                 sender.send_it(DrawMessage::Flush);
                 image.redraw();
+            } else if ev == enums::Event::Unfocus {
+                rw_write!(history).seal();
             }
             true
         });
@@ -913,15 +1759,22 @@ impl MainWindow {
         // Tag2 Input
         let mut image = self.page.image.clone();
         let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
         let sender = self.sender.clone();
         self.tag2.handle(move |f, ev| {
             if ev == enums::Event::KeyUp {
                 let mut prop = rw_write!(properties);
+                if prop.tag2 == f.value() {
+                    return true;
+                }
+                rw_write!(history).checkpoint(&prop, Edit::Tag2);
                 prop.tag2 = f.value();
                 prop.is_saved = false;
                 sender.send_it(DrawMessage::RedrawToBuffer);
                 sender.send_it(DrawMessage::Flush);
                 image.redraw();
+            } else if ev == enums::Event::Unfocus {
+                rw_write!(history).seal();
             }
             true
         });
@@ -929,10 +1782,12 @@ impl MainWindow {
         // Quote Position Input
         let mut image = self.page.image.clone();
         let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
         let sender = self.sender.clone();
         let mut quote_position_slider = self.quote_position_slider.clone();
         self.quote_position.set_callback(move |f| {
             let mut prop = rw_write!(properties);
+            rw_write!(history).checkpoint(&prop, Edit::QuotePosition);
             prop.quote_position = f.value();
             quote_position_slider.set_value(f.value());
             prop.is_saved = false;
@@ -944,10 +1799,12 @@ impl MainWindow {
         // Quote Position Slider
         let mut image = self.page.image.clone();
         let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
         let sender = self.sender.clone();
         let mut quote_position = self.quote_position.clone();
         self.quote_position_slider.set_callback(move |f| {
             let mut prop = rw_write!(properties);
+            rw_write!(history).checkpoint(&prop, Edit::QuotePosition);
             prop.quote_position = f.value();
             quote_position.set_value(f.value());
             prop.is_saved = false;
@@ -959,10 +1816,12 @@ impl MainWindow {
         // Subquote Position Input
         let mut image = self.page.image.clone();
         let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
         let sender = self.sender.clone();
         let mut subquote_position_slider = self.subquote_position_slider.clone();
         self.subquote_position.set_callback(move |f| {
             let mut prop = rw_write!(properties);
+            rw_write!(history).checkpoint(&prop, Edit::SubquotePosition);
             prop.subquote_position = f.value();
             subquote_position_slider.set_value(f.value());
             prop.is_saved = false;
@@ -974,10 +1833,12 @@ impl MainWindow {
         // Subquote Position Slider
         let mut image = self.page.image.clone();
         let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
         let sender = self.sender.clone();
         let mut subquote_position = self.subquote_position.clone();
         self.subquote_position_slider.set_callback(move |f| {
             let mut prop = rw_write!(properties);
+            rw_write!(history).checkpoint(&prop, Edit::SubquotePosition);
             prop.subquote_position = f.value();
             subquote_position.set_value(f.value());
             prop.is_saved = false;
@@ -989,10 +1850,12 @@ impl MainWindow {
         // Subquote2 Position Input
         let mut image = self.page.image.clone();
         let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
         let sender = self.sender.clone();
         let mut subquote2_position_slider = self.subquote2_position_slider.clone();
         self.subquote2_position.set_callback(move |f| {
             let mut prop = rw_write!(properties);
+            rw_write!(history).checkpoint(&prop, Edit::Subquote2Position);
             prop.subquote2_position = f.value();
             subquote2_position_slider.set_value(f.value());
             prop.is_saved = false;
@@ -1004,10 +1867,12 @@ impl MainWindow {
         // Subquote2 Position Slider
         let mut image = self.page.image.clone();
         let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
         let sender = self.sender.clone();
         let mut subquote2_position = self.subquote2_position.clone();
         self.subquote2_position_slider.set_callback(move |f| {
             let mut prop = rw_write!(properties);
+            rw_write!(history).checkpoint(&prop, Edit::Subquote2Position);
             prop.subquote2_position = f.value();
             subquote2_position.set_value(f.value());
             prop.is_saved = false;
@@ -1019,10 +1884,12 @@ impl MainWindow {
         // Tag Position Input
         let mut image = self.page.image.clone();
         let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
         let sender = self.sender.clone();
         let mut tag_position_slider = self.tag_position_slider.clone();
         self.tag_position.set_callback(move |f| {
             let mut prop = rw_write!(properties);
+            rw_write!(history).checkpoint(&prop, Edit::TagPosition);
             prop.tag_position = f.value();
             tag_position_slider.set_value(f.value());
             prop.is_saved = false;
@@ -1034,10 +1901,12 @@ impl MainWindow {
         // Tag Position Slider
         let mut image = self.page.image.clone();
         let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
         let sender = self.sender.clone();
         let mut tag_position = self.tag_position.clone();
         self.tag_position_slider.set_callback(move |f| {
             let mut prop = rw_write!(properties);
+            rw_write!(history).checkpoint(&prop, Edit::TagPosition);
             prop.tag_position = f.value();
             tag_position.set_value(f.value());
             prop.is_saved = false;
@@ -1049,10 +1918,12 @@ impl MainWindow {
         // Tag2 Position Input
         let mut image = self.page.image.clone();
         let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
         let sender = self.sender.clone();
         let mut tag2_position_slider = self.tag2_position_slider.clone();
         self.tag2_position.set_callback(move |f| {
             let mut prop = rw_write!(properties);
+            rw_write!(history).checkpoint(&prop, Edit::Tag2Position);
             prop.tag2_position = f.value();
             tag2_position_slider.set_value(f.value());
             prop.is_saved = false;
@@ -1064,10 +1935,12 @@ impl MainWindow {
         // Tag2 Position Slider
         let mut image = self.page.image.clone();
         let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
         let sender = self.sender.clone();
         let mut tag2_position = self.tag2_position.clone();
         self.tag2_position_slider.set_callback(move |f| {
             let mut prop = rw_write!(properties);
+            rw_write!(history).checkpoint(&prop, Edit::Tag2Position);
             prop.tag2_position = f.value();
             tag2_position.set_value(f.value());
             prop.is_saved = false;
@@ -1079,6 +1952,7 @@ impl MainWindow {
         // Translucent Layer RGB
         let mut image = self.page.image.clone();
         let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
         let sender = self.sender.clone();
         self.translucent_layer_rgb.set_callback(move |mut f| {
             let mut prop = rw_write!(properties);
@@ -1091,6 +1965,10 @@ impl MainWindow {
                     prop.translucent_layer_color[2],
                 ),
             );
+            if [r, g, b] == prop.translucent_layer_color[..3] {
+                return;
+            }
+            rw_write!(history).checkpoint(&prop, Edit::Single);
             prop.translucent_layer_color = [r, g, b, prop.translucent_layer_color[3]];
             utils::set_color_btn_rgba(prop.translucent_layer_color, &mut f);
             f.redraw();
@@ -1103,65 +1981,386 @@ impl MainWindow {
         // Translucent Layer Opacity
         let mut image = self.page.image.clone();
         let properties = Arc::clone(&self.properties);
+        let history = Arc::clone(&self.history);
         let sender = self.sender.clone();
         self.translucent_layer_alpha.set_callback(move |f| {
             let mut prop = rw_write!(properties);
+            rw_write!(history).checkpoint(&prop, Edit::LayerAlpha);
             prop.translucent_layer_color[3] = f.value() as u8;
             prop.is_saved = false;
             sender.send_it(DrawMessage::RedrawToBuffer);
             sender.send_it(DrawMessage::Flush);
             image.redraw();
         });
+
+        // Slider drag or spinner clicks till mouse is released are one step in history
+        seal_history_on_release(&mut self.quote_position, &self.history);
+        seal_history_on_release(&mut self.subquote_position, &self.history);
+        seal_history_on_release(&mut self.subquote2_position, &self.history);
+        seal_history_on_release(&mut self.tag_position, &self.history);
+        seal_history_on_release(&mut self.tag2_position, &self.history);
+        seal_history_on_release(&mut self.quote_position_slider, &self.history);
+        seal_history_on_release(&mut self.subquote_position_slider, &self.history);
+        seal_history_on_release(&mut self.subquote2_position_slider, &self.history);
+        seal_history_on_release(&mut self.tag_position_slider, &self.history);
+        seal_history_on_release(&mut self.tag2_position_slider, &self.history);
+        seal_history_on_release(&mut self.translucent_layer_alpha, &self.history);
     }
 }
 
 /// Load all iamges in a directory
 fn load_dir(
     path: &PathBuf,
+    recursive: bool,
     imgs: Arc<RwLock<Vec<ImageInfo>>>,
     file_choice: &mut menu::Choice,
     sender: &mpsc::Sender<DrawMessage>,
 ) {
-    let mut files = fs::read_dir(path)
-        .unwrap()
-        .map(|r| r.unwrap())
-        .collect::<Vec<fs::DirEntry>>();
-    files.sort_by_key(|i| i.file_name());
-    let mut text = String::new();
-    let mut imgs_b = rw_write!(imgs);
-    *imgs_b = vec![];
-    for file in files {
-        let path = file.path();
+    let mut unsupported = vec![];
+    let images = dir_images(path, recursive, &mut unsupported);
+    image_list::set_unsupported(unsupported);
+    set_images(
+        images,
+        vec![(path.clone(), recursive)],
+        imgs,
+        file_choice,
+        sender,
+    );
+}
+
+/// Images in folder, and in its subfolders if `recursive`, except hidden and export folders.
+/// Images of a folder come before those of its subfolders. Images in formats which can't be
+/// opened are added to `unsupported`
+fn dir_images(
+    path: &Path,
+    recursive: bool,
+    unsupported: &mut Vec<UnsupportedImage>,
+) -> Vec<ImageInfo> {
+    let (files, folders) = match utils::folder_entries(path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            warn!("Failed to read folder {}: {}", path.display(), e);
+            return vec![];
+        }
+    };
+    let mut images = vec![];
+    for path in files {
         if let Ok(Some(ty)) = infer::get_from_path(&path) {
             let mime = ty.mime_type();
             match ImageType::from_mime(mime) {
-                ImageType::None => (),
-                _ => {
-                    text = format!("{}|{}", text, path.file_name().unwrap().to_str().unwrap());
-                    imgs_b.push(ImageInfo {
-                        path,
-                        image_type: ImageType::from_mime(mime),
-                    });
+                ImageType::None => {
+                    if let Some(format) = utils::unsupported_format(mime) {
+                        unsupported.push(UnsupportedImage { path, format });
+                    }
                 }
+                image_type => images.push(ImageInfo { path, image_type }),
             }
         }
     }
-    if text.len() == 0 {
+    if recursive {
+        for folder in folders {
+            images.extend(dir_images(&folder, true, unsupported));
+        }
+    }
+    images
+}
+
+/// Keep images of opened folder or project, show them as per sort and filters and open first.
+/// `folders` are watched for changes, with whether their subfolders are watched too
+fn set_images(
+    images: Vec<ImageInfo>,
+    folders: Vec<(PathBuf, bool)>,
+    imgs: Arc<RwLock<Vec<ImageInfo>>>,
+    file_choice: &mut menu::Choice,
+    sender: &mpsc::Sender<DrawMessage>,
+) {
+    folder_watcher::watch(folders);
+    let is_empty = images.is_empty();
+    image_list::set_loaded(images);
+    if is_empty {
+        rw_write!(imgs).clear();
         return;
     }
-    file_choice.clear();
-    file_choice.add_choice(&text[1..]);
-    file_choice.set_value(0);
+    show_images(image_list::shown(), None, imgs, file_choice, sender);
+}
+
+/// Show images in file choice and open `open` if it is in them, or else first of them
+fn show_images(
+    images: Vec<ImageInfo>,
+    open: Option<&Path>,
+    imgs: Arc<RwLock<Vec<ImageInfo>>>,
+    file_choice: &mut menu::Choice,
+    sender: &mpsc::Sender<DrawMessage>,
+) {
+    fill_file_choice(images, open, imgs, file_choice);
     sender.send_it(DrawMessage::Open);
     sender.send_it(DrawMessage::CheckImage);
 }
 
-trait SenderExt {
+/// Put images in file choice and select `select` if it is in them, or else first of them.
+/// Returns true if `select` was found
+fn fill_file_choice(
+    images: Vec<ImageInfo>,
+    select: Option<&Path>,
+    imgs: Arc<RwLock<Vec<ImageInfo>>>,
+    file_choice: &mut menu::Choice,
+) -> bool {
+    let root = utils::common_folder(&images);
+    let found = select.and_then(|path| images.iter().position(|image| image.path == path));
+    file_choice.clear();
+    for image in &images {
+        file_choice.add(
+            &utils::image_label(image, &root),
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            |a| a.do_callback(),
+        );
+    }
+    *rw_write!(imgs) = images;
+    file_choice.set_value(found.unwrap_or(0) as i32);
+    file_choice.redraw();
+    found.is_some()
+}
+
+/// Show loaded images again after sort or filters changed
+fn refresh_images(
+    imgs: Arc<RwLock<Vec<ImageInfo>>>,
+    properties: &Arc<RwLock<ImageProperties>>,
+    file_choice: &mut menu::Choice,
+    sender: &mpsc::Sender<DrawMessage>,
+) {
+    if image_list::loaded_len() == 0 {
+        return;
+    }
+    if !rw_read!(properties).is_saved {
+        let save = fltk::dialog::choice2_default("Save?", "yes", "no", "cancel");
+        match save.unwrap_or(-1) {
+            0 => sender.send_it(DrawMessage::Save),
+            1 => {}
+            _ => return,
+        }
+    }
+    let current = rw_read!(imgs)
+        .get(file_choice.value() as usize)
+        .map(|image| image.path.clone());
+    show_images(
+        image_list::shown(),
+        current.as_deref(),
+        imgs,
+        file_choice,
+        sender,
+    );
+}
+
+/// Export folder is, or has in it, a folder of loaded images, like when export folder is `.`
+fn has_loaded_images(export: &Path) -> bool {
+    let canonical = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let export = canonical(export);
+    image_list::loaded()
+        .iter()
+        .filter_map(|image| image.path.parent())
+        .any(|folder| canonical(folder).starts_with(&export))
+}
+
+/// Open project file: use its config and load images of its folders in its order
+fn open_project(
+    path: &Path,
+    imgs: &Arc<RwLock<Vec<ImageInfo>>>,
+    properties: &Arc<RwLock<ImageProperties>>,
+    file_choice: &menu::Choice,
+    sender: &mpsc::Sender<DrawMessage>,
+    win: &Window,
+    menubar: &mut menu::SysMenuBar,
+) {
+    if !rw_read!(properties).is_saved {
+        let save = fltk::dialog::choice2_default("Save?", "yes", "no", "cancel");
+        match save.unwrap_or(-1) {
+            0 => sender.send_it(DrawMessage::Save),
+            1 => {}
+            _ => return,
+        }
+    }
+
+    let project = match Project::load(path) {
+        Ok(project) => project,
+        Err(e) => {
+            utils::show_alert(&e.to_string());
+            return;
+        }
+    };
+    if !project::apply_config(&project) {
+        utils::show_alert(&format!(
+            "Config \"{}\" of project is not found, current config is used",
+            project.config
+        ));
+    }
+
+    let folders = project.folders(path);
+    let mut unsupported = vec![];
+    let mut images: Vec<ImageInfo> = folders
+        .iter()
+        .flat_map(|folder| dir_images(folder, false, &mut unsupported))
+        .collect();
+    image_list::set_unsupported(unsupported);
+    project.sort(path, &mut images);
+    *rw_write!(globals::PROJECT) = Some((path.to_path_buf(), project));
+
+    win.clone().set_label(&format!(
+        "{} - Post Maker",
+        path.file_stem()
+            .unwrap_or(OsStr::new("Unknown"))
+            .to_string_lossy()
+    ));
+    set_images(
+        images,
+        folders.into_iter().map(|folder| (folder, false)).collect(),
+        Arc::clone(imgs),
+        &mut file_choice.clone(),
+        sender,
+    );
+
+    project::add_recent(path);
+    // Menu can't be changed while its callback is running
+    let mut menubar = menubar.clone();
+    let imgs = Arc::clone(imgs);
+    let properties = Arc::clone(properties);
+    let file_choice = file_choice.clone();
+    let sender = sender.clone();
+    let win = win.clone();
+    app::add_timeout3(0.0, move |_| {
+        update_recent_projects(
+            &mut menubar,
+            &imgs,
+            &properties,
+            &file_choice,
+            &sender,
+            &win,
+        );
+    });
+}
+
+/// Fill File > Recent Projects with recently opened projects
+fn update_recent_projects(
+    menubar: &mut menu::SysMenuBar,
+    imgs: &Arc<RwLock<Vec<ImageInfo>>>,
+    properties: &Arc<RwLock<ImageProperties>>,
+    file_choice: &menu::Choice,
+    sender: &mpsc::Sender<DrawMessage>,
+    win: &Window,
+) {
+    let idx = menubar.find_index("&File/Recent Projects");
+    if idx >= 0 {
+        menubar
+            .clear_submenu(idx)
+            .warn_log("Failed to update recent projects");
+    }
+
+    let recent = config::get_recent_projects();
+    if recent.is_empty() {
+        menubar.add(
+            "&File/Recent Projects/(None)\t",
+            Shortcut::None,
+            menu::MenuFlag::Inactive,
+            |_| {},
+        );
+    }
+    for path in recent {
+        // `/` makes submenu and `&` shortcut in label
+        let label = path
+            .display()
+            .to_string()
+            .replace('\\', "\\\\")
+            .replace('/', "\\/")
+            .replace('&', "&&");
+        let imgs = Arc::clone(imgs);
+        let properties = Arc::clone(properties);
+        let file_choice = file_choice.clone();
+        let sender = sender.clone();
+        let win = win.clone();
+        menubar.add(
+            &format!("&File/Recent Projects/{}\t", label),
+            Shortcut::None,
+            menu::MenuFlag::Normal,
+            move |menubar| {
+                if !path.is_file() {
+                    utils::show_alert("Project file does not exist anymore!");
+                    return;
+                }
+                open_project(
+                    &path,
+                    &imgs,
+                    &properties,
+                    &file_choice,
+                    &sender,
+                    &win,
+                    menubar,
+                );
+            },
+        );
+    }
+}
+
+/// Placeholder in page for image which could not be opened
+fn draw_load_error(f: &Frame, error: &str) {
+    dr::draw_box(
+        enums::FrameType::FlatBox,
+        f.x(),
+        f.y(),
+        f.w(),
+        f.h(),
+        enums::Color::from_rgb(60, 60, 60),
+    );
+    dr::set_draw_color(enums::Color::from_rgb(230, 230, 230));
+    dr::set_font(enums::Font::Helvetica, 14);
+    dr::draw_text2(
+        &format!("Failed to open image\n\n{}", error),
+        f.x() + 20,
+        f.y(),
+        f.w() - 40,
+        f.h(),
+        enums::Align::Center | enums::Align::Wrap,
+    );
+}
+
+/// Distance in pixels of preview within which dragged element snaps to a guide
+const SNAP_DISTANCE: f64 = 6.0;
+
+/// Center and thirds of image of given height
+fn snap_guides(height: f64) -> [f64; 3] {
+    [height / 3.0, height / 2.0, height * 2.0 / 3.0]
+}
+
+/// Edit of history made by dragging text element
+fn drag_edit(element: TextElement) -> Edit {
+    match element {
+        TextElement::Quote => Edit::QuotePosition,
+        TextElement::Subquote => Edit::SubquotePosition,
+        TextElement::Subquote2 => Edit::Subquote2Position,
+        TextElement::Tag => Edit::TagPosition,
+        TextElement::Tag2 => Edit::Tag2Position,
+    }
+}
+
+/// Start new step in history when mouse is released from widget
+fn seal_history_on_release<W: WidgetBase>(widget: &mut W, history: &Arc<RwLock<History>>) {
+    let history = Arc::clone(history);
+    widget.handle(move |_, ev| {
+        if ev == enums::Event::Released {
+            rw_write!(history).seal();
+        }
+        false
+    });
+}
+
+pub(crate) trait SenderExt {
     fn send_it(&self, a: DrawMessage);
 }
 
 impl SenderExt for mpsc::Sender<DrawMessage> {
     fn send_it(&self, a: DrawMessage) {
+        if let DrawMessage::RedrawToBuffer = a {
+            new_redraw_generation();
+        }
         self.send(a).expect_log("Program panic!");
     }
 }
@@ -0,0 +1,346 @@
+/*
+    This file is part of Post Maker.
+    Post Maker is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+    Post Maker is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with Post Maker.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Remember inputs of exported images, so that Export All can skip images which have not changed
+use crate::{globals, result_ext::ResultExt};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Name of manifest file in export folder
+const MANIFEST_FILE: &str = ".manifest.json";
+
+/// Hashes of inputs of images exported in a folder
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Manifest {
+    /// Exported image of every source image, by path of source image
+    #[serde(default)]
+    exports: HashMap<String, Export>,
+    /// File name of exported image and hash of its inputs, written by older versions. Only used
+    /// to find exported images to delete
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    images: HashMap<String, String>,
+}
+
+/// Exported image of one source image
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Export {
+    /// File name of exported image in export folder
+    output: String,
+    /// Hash of inputs of exported image
+    hash: String,
+}
+
+impl Manifest {
+    /// Read manifest of export folder, empty if it does not exist or is corrupt
+    pub(crate) fn load(export_dir: &Path) -> Self {
+        fs::read(export_dir.join(MANIFEST_FILE))
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn save(&self, export_dir: &Path) -> io::Result<()> {
+        fs::write(
+            export_dir.join(MANIFEST_FILE),
+            serde_json::to_string_pretty(self).unwrap(),
+        )
+    }
+
+    /// Exported image of source image in `export_dir`, if it still exists
+    pub(crate) fn output(&self, image: &Path, export_dir: &Path) -> Option<PathBuf> {
+        let export = self.exports.get(&image_key(image))?;
+        Some(export_dir.join(&export.output))
+            .filter(|_| is_plain_name(&export.output))
+            .filter(|path| path.is_file())
+    }
+
+    /// Exported image of source image which exists and was made from same inputs. `hash` is from
+    /// `input_hash`, so image is exported again if name given by template changes
+    pub(crate) fn up_to_date_output(
+        &self,
+        image: &Path,
+        export_dir: &Path,
+        hash: u64,
+    ) -> Option<PathBuf> {
+        let export = self.exports.get(&image_key(image))?;
+        if export.hash != format!("{:016x}", hash) {
+            return None;
+        }
+        self.output(image, export_dir)
+    }
+
+    /// Exported images in manifest which still exist. Names which are not plain file names are
+    /// left out, so a changed manifest can't point outside folder
+    pub(crate) fn files(&self, export_dir: &Path) -> Vec<PathBuf> {
+        let mut names: Vec<&String> = self.exports.values().map(|e| &e.output).collect();
+        names.extend(self.images.keys());
+        names.sort();
+        names.dedup();
+        names
+            .into_iter()
+            .filter(|name| is_plain_name(name))
+            .map(|name| export_dir.join(name))
+            .filter(|path| path.is_file())
+            .collect()
+    }
+
+    /// Remember file written for source image, in place of its earlier export
+    pub(crate) fn insert(&mut self, image: &Path, output: &Path, hash: u64) {
+        let output = file_name(output);
+        self.images.remove(&output);
+        self.exports.insert(
+            image_key(image),
+            Export {
+                output,
+                hash: format!("{:016x}", hash),
+            },
+        );
+    }
+}
+
+/// Manifests of every export folder touched in one Export All
+#[derive(Debug, Default)]
+pub(crate) struct Manifests {
+    manifests: HashMap<PathBuf, Manifest>,
+}
+
+impl Manifests {
+    /// Manifest of folder in which image is exported
+    pub(crate) fn get(&mut self, export_path: &Path) -> &mut Manifest {
+        let dir = export_dir(export_path);
+        self.manifests
+            .entry(dir.clone())
+            .or_insert_with(|| Manifest::load(&dir))
+    }
+
+    pub(crate) fn save(&self) -> io::Result<()> {
+        for (dir, manifest) in &self.manifests {
+            manifest.save(dir)?;
+        }
+        Ok(())
+    }
+}
+
+/// Hash of config and fonts, which are same for every image
+pub(crate) fn config_hash() -> u64 {
+    let mut config = rw_read!(globals::CONFIG).clone();
+    // Does not change how image looks. Name of exported image is in `input_hash`, and every
+    // export folder has own manifest
+    config.export_threads = 0;
+    config.export_filename.clear();
+    config.export_dir.clear();
+    config.export_collision = Default::default();
+    config.properties_storage = Default::default();
+
+    let mut hash = fnv1a(FNV_OFFSET, &serde_json::to_vec(&config).unwrap());
+    for font in [
+        &config.quote_font,
+        &config.subquote_font,
+        &config.subquote2_font,
+        &config.tag_font,
+        &config.tag2_font,
+    ] {
+        hash = match fs::read(font) {
+            Ok(data) => fnv1a(hash, &data),
+            Err(_) => fnv1a(hash, font.as_bytes()),
+        };
+    }
+    hash
+}
+
+/// Hash of contents of file
+pub(crate) fn file_hash(path: &Path) -> io::Result<u64> {
+    Ok(bytes_hash(&fs::read(path)?))
+}
+
+/// Hash of bytes, same on every run and platform
+pub(crate) fn bytes_hash(data: &[u8]) -> u64 {
+    fnv1a(FNV_OFFSET, data)
+}
+
+/// Hash of everything exported image depends on: source image, its properties, config, fonts
+/// and name given to it by template
+pub(crate) fn input_hash(
+    image_hash: u64,
+    properties: &[u8],
+    config_hash: u64,
+    export_path: &Path,
+) -> u64 {
+    let hash = fnv1a(FNV_OFFSET, &image_hash.to_le_bytes());
+    let hash = fnv1a(hash, &config_hash.to_le_bytes());
+    let hash = fnv1a(hash, properties);
+    fnv1a(hash, file_name(export_path).as_bytes())
+}
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// FNV-1a hash, continued from `hash`
+fn fnv1a(mut hash: u64, data: &[u8]) -> u64 {
+    for byte in data {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+/// Path of manifest file of export folder
+pub(crate) fn manifest_path(export_dir: &Path) -> PathBuf {
+    export_dir.join(MANIFEST_FILE)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Key of source image, same for every relative path to it
+fn image_key(image: &Path) -> String {
+    image
+        .canonicalize()
+        .unwrap_or_else(|_| image.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn is_plain_name(name: &str) -> bool {
+    Path::new(name).file_name() == Some(name.as_ref())
+}
+
+fn export_dir(export_path: &Path) -> PathBuf {
+    export_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils;
+
+    #[test]
+    fn fnv1a_test_vectors() {
+        assert_eq!(bytes_hash(b""), 0xcbf29ce484222325);
+        assert_eq!(bytes_hash(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(bytes_hash(b"foobar"), 0x85944171f73967e8);
+        // Continued hash is same as hash of joined bytes
+        assert_eq!(fnv1a(bytes_hash(b"foo"), b"bar"), bytes_hash(b"foobar"));
+    }
+
+    #[test]
+    fn input_hash_depends_on_every_input() {
+        let path = Path::new("/export/photo.jpg");
+        let hash = input_hash(1, b"{}", 2, path);
+        assert_eq!(hash, input_hash(1, b"{}", 2, path));
+        // Only file name of exported image counts
+        assert_eq!(hash, input_hash(1, b"{}", 2, Path::new("/other/photo.jpg")));
+        assert_ne!(hash, input_hash(3, b"{}", 2, path));
+        assert_ne!(hash, input_hash(1, b"{ }", 2, path));
+        assert_ne!(hash, input_hash(1, b"{}", 3, path));
+        assert_ne!(
+            hash,
+            input_hash(1, b"{}", 2, Path::new("/export/photo.png"))
+        );
+    }
+
+    #[test]
+    fn up_to_date_output_needs_same_hash_and_file() {
+        let folder = utils::temp_folder("up_to_date_output");
+        let image = folder.join("photo.jpg");
+        let export_dir = folder.join("export");
+        fs::create_dir_all(&export_dir).unwrap();
+        let output = export_dir.join("photo-2.jpg");
+
+        let mut manifest = Manifest::default();
+        manifest.insert(&image, &output, 10);
+        // Not written yet
+        assert_eq!(manifest.up_to_date_output(&image, &export_dir, 10), None);
+
+        fs::write(&output, b"export").unwrap();
+        assert_eq!(
+            manifest.up_to_date_output(&image, &export_dir, 10),
+            Some(output.clone())
+        );
+        assert_eq!(manifest.up_to_date_output(&image, &export_dir, 11), None);
+        // Other image exported with same name and inputs is not up to date
+        let other = folder.join("other.jpg");
+        assert_eq!(manifest.up_to_date_output(&other, &export_dir, 10), None);
+        assert_eq!(manifest.output(&image, &export_dir), Some(output.clone()));
+
+        manifest.save(&export_dir).unwrap();
+        let loaded = Manifest::load(&export_dir);
+        assert_eq!(
+            loaded.up_to_date_output(&image, &export_dir, 10),
+            Some(output.clone())
+        );
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn relative_path_of_image_is_same_key() {
+        let folder = utils::temp_folder("relative_path_of_image_is_same_key");
+        let image = folder.join("photo.jpg");
+        fs::write(&image, b"photo").unwrap();
+        let export_dir = folder.join("export");
+        fs::create_dir_all(&export_dir).unwrap();
+        let output = export_dir.join("photo.jpg");
+        fs::write(&output, b"export").unwrap();
+
+        let mut manifest = Manifest::default();
+        manifest.insert(&folder.join(".").join("photo.jpg"), &output, 1);
+        assert_eq!(manifest.output(&image, &export_dir), Some(output));
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn files_are_plain_names_which_exist() {
+        let folder = utils::temp_folder("files_are_plain_names_which_exist");
+        for name in ["new.jpg", "old.jpg", "legacy.jpg"] {
+            fs::write(folder.join(name), b"export").unwrap();
+        }
+        fs::write(
+            folder.join(MANIFEST_FILE),
+            r#"{
+                "exports": {
+                    "/a.jpg": {"output": "new.jpg", "hash": "1"},
+                    "/b.jpg": {"output": "new.jpg", "hash": "2"},
+                    "/c.jpg": {"output": "../photo.jpg", "hash": "3"},
+                    "/d.jpg": {"output": "missing.jpg", "hash": "4"}
+                },
+                "images": {"old.jpg": "5", "legacy.jpg": "6"}
+            }"#,
+        )
+        .unwrap();
+
+        let mut manifest = Manifest::load(&folder);
+        assert_eq!(
+            manifest.files(&folder),
+            ["legacy.jpg", "new.jpg", "old.jpg"].map(|name| folder.join(name))
+        );
+        assert_eq!(manifest.output(Path::new("/c.jpg"), &folder), None);
+
+        // Image exported again takes place of file of older version
+        manifest.insert(Path::new("/e.jpg"), &folder.join("old.jpg"), 7);
+        assert!(!manifest.images.contains_key("old.jpg"));
+        assert!(manifest.images.contains_key("legacy.jpg"));
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
@@ -0,0 +1,134 @@
+/*
+    This file is part of Post Maker.
+    Post Maker is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+    Post Maker is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with Post Maker.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Write texts and properties of every image in one CSV or JSON file, which can be imported back
+use crate::{error::Error, export_report::csv_field, properties_store, utils::ImageInfo};
+use serde::Serialize;
+use std::{fs, path::Path};
+
+/// Properties of one image, with same names of columns as import
+#[derive(Debug, Default, Serialize)]
+struct MetadataRow {
+    image: String,
+    name_prefix: String,
+    quote: String,
+    subquote: String,
+    subquote2: String,
+    tag: String,
+    tag2: String,
+    quote_position: Option<f64>,
+    subquote_position: Option<f64>,
+    subquote2_position: Option<f64>,
+    tag_position: Option<f64>,
+    tag2_position: Option<f64>,
+    crop_x: Option<f64>,
+    crop_y: Option<f64>,
+    /// As `#rrggbbaa`
+    layer_color: String,
+    /// As `#rrggbb`
+    text_color: String,
+}
+
+impl MetadataRow {
+    fn new(image: &ImageInfo) -> Self {
+        let mut row = Self {
+            image: image
+                .path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            ..Default::default()
+        };
+        let read = match properties_store::read(image) {
+            Ok(Some(read)) => read,
+            Ok(None) => return row,
+            Err(e) => {
+                warn!("Properties of {} are corrupt: {}", row.image, e);
+                return row;
+            }
+        };
+
+        row.name_prefix = read.name_prefix.unwrap_or_default();
+        row.quote = read.quote.unwrap_or_default();
+        row.subquote = read.subquote.unwrap_or_default();
+        row.subquote2 = read.subquote2.unwrap_or_default();
+        row.tag = read.tag.unwrap_or_default();
+        row.tag2 = read.tag2.unwrap_or_default();
+        row.quote_position = read.quote_position;
+        row.subquote_position = read.subquote_position;
+        row.subquote2_position = read.subquote2_position;
+        row.tag_position = read.tag_position;
+        row.tag2_position = read.tag2_position;
+        row.crop_x = read.crop_position.map(|(x, _)| x);
+        row.crop_y = read.crop_position.map(|(_, y)| y);
+        row.layer_color = read
+            .translucent_layer_color
+            .map(|[r, g, b, a]| format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a))
+            .unwrap_or_default();
+        row.text_color = read
+            .text_color
+            .map(|[r, g, b]| format!("#{:02x}{:02x}{:02x}", r, g, b))
+            .unwrap_or_default();
+        row
+    }
+
+    fn to_csv(&self) -> String {
+        let number = |n: Option<f64>| n.map(|n| n.to_string()).unwrap_or_default();
+        [
+            csv_field(&self.image),
+            csv_field(&self.name_prefix),
+            csv_field(&self.quote),
+            csv_field(&self.subquote),
+            csv_field(&self.subquote2),
+            csv_field(&self.tag),
+            csv_field(&self.tag2),
+            number(self.quote_position),
+            number(self.subquote_position),
+            number(self.subquote2_position),
+            number(self.tag_position),
+            number(self.tag2_position),
+            number(self.crop_x),
+            number(self.crop_y),
+            self.layer_color.clone(),
+            self.text_color.clone(),
+        ]
+        .join(",")
+    }
+}
+
+/// Write one row for every image, as JSON if file has `.json` extension or else as CSV.
+/// Returns count of rows
+pub(crate) fn write(path: &Path, images: &[ImageInfo]) -> Result<usize, Error> {
+    let rows: Vec<MetadataRow> = images.iter().map(MetadataRow::new).collect();
+    let is_json = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let data = if is_json {
+        serde_json::to_string_pretty(&rows).unwrap()
+    } else {
+        let mut csv = String::from(
+            "image,name_prefix,quote,subquote,subquote2,tag,tag2,quote_position,\
+            subquote_position,subquote2_position,tag_position,tag2_position,crop_x,crop_y,\
+            layer_color,text_color\n",
+        );
+        for row in &rows {
+            csv.push_str(&row.to_csv());
+            csv.push('\n');
+        }
+        csv
+    };
+    fs::write(path, data).map_err(|e| Error::Io("Failed to write file".to_owned(), e))?;
+    Ok(rows.len())
+}
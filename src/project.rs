@@ -0,0 +1,160 @@
+/*
+    This file is part of Post Maker.
+    Post Maker is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+    Post Maker is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with Post Maker.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! `.postmaker` project file, which keeps folders, config and export settings together
+use crate::{config, dialog, error::Error, globals, result_ext::ResultExt, utils::ImageInfo};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Extension of project files
+pub(crate) const PROJECT_EXTENSION: &str = "postmaker";
+
+/// Most projects kept in recent projects
+const RECENT_PROJECTS_LIMIT: usize = 10;
+
+/// Project file, paths are relative to folder of project file if they are inside it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct Project {
+    /// Folders of images
+    pub(crate) folders: Vec<PathBuf>,
+    /// Name of config profile
+    pub(crate) config: String,
+    /// Folder of exported images, in place of one in config. Relative path is in folder of
+    /// project file
+    #[serde(default)]
+    pub(crate) export_dir: Option<String>,
+    /// Images in order they are shown, new images are added after them
+    #[serde(default)]
+    pub(crate) order: Vec<PathBuf>,
+}
+
+impl Project {
+    /// Project of currently open images and config
+    pub(crate) fn new(path: &Path, images: &[ImageInfo]) -> Self {
+        let mut project = Self {
+            config: rw_read!(globals::CONFIG_NAME).clone(),
+            ..Default::default()
+        };
+        project.set_images(path, images);
+        project
+    }
+
+    /// Remember folders and order of images
+    pub(crate) fn set_images(&mut self, path: &Path, images: &[ImageInfo]) {
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        self.folders.clear();
+        for image in images {
+            if let Some(folder) = image.path.parent() {
+                let folder = relative(base, folder);
+                if !self.folders.contains(&folder) {
+                    self.folders.push(folder);
+                }
+            }
+        }
+        self.order = images
+            .iter()
+            .map(|image| relative(base, &image.path))
+            .collect();
+    }
+
+    pub(crate) fn load(path: &Path) -> Result<Self, Error> {
+        let data = fs::read(path).map_err(|e| Error::Io("Failed to read project".to_owned(), e))?;
+        serde_json::from_slice(&data)
+            .map_err(|e| Error::Corrupt(format!("Project file is corrupt: {}", e)))
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<(), Error> {
+        fs::write(path, serde_json::to_string_pretty(self).unwrap())
+            .map_err(|e| Error::Io("Failed to save project".to_owned(), e))
+    }
+
+    /// Absolute paths of folders
+    pub(crate) fn folders(&self, path: &Path) -> Vec<PathBuf> {
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        self.folders
+            .iter()
+            .map(|folder| base.join(folder))
+            .collect()
+    }
+
+    /// Absolute path of folder of exported images, `None` if folder of config is used
+    pub(crate) fn export_folder(&self, path: &Path) -> Option<PathBuf> {
+        let dir = self.export_dir.as_deref()?.trim();
+        if dir.is_empty() {
+            return None;
+        }
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        Some(base.join(dir))
+    }
+
+    /// Sort images as per saved order, images which are not in it are kept after, in same order
+    pub(crate) fn sort(&self, path: &Path, images: &mut [ImageInfo]) {
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        let order: Vec<PathBuf> = self.order.iter().map(|image| base.join(image)).collect();
+        images.sort_by_key(|image| {
+            order
+                .iter()
+                .position(|path| *path == image.path)
+                .unwrap_or(order.len())
+        });
+    }
+}
+
+/// Path relative to base, if it is inside it
+fn relative(base: &Path, path: &Path) -> PathBuf {
+    match path.strip_prefix(base) {
+        Ok(path) if path.as_os_str().is_empty() => PathBuf::from("."),
+        Ok(path) => path.to_path_buf(),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// Use config profile of project, returns false if there is no such config
+pub(crate) fn apply_config(project: &Project) -> bool {
+    let configs = config::get_configs().unwrap_or_default();
+    let config = match configs.get(&project.config) {
+        Some(config) => config.clone(),
+        None => return false,
+    };
+
+    let old = rw_read!(globals::CONFIG).clone();
+    let fonts_changed = [
+        (&old.quote_font, &config.quote_font),
+        (&old.subquote_font, &config.subquote_font),
+        (&old.subquote2_font, &config.subquote2_font),
+        (&old.tag_font, &config.tag_font),
+        (&old.tag2_font, &config.tag2_font),
+    ]
+    .iter()
+    .any(|(old, new)| old != new);
+    if fonts_changed {
+        dialog::message_default("Fonts of this config will be used after restart");
+    }
+
+    *rw_write!(globals::CONFIG_NAME) = project.config.clone();
+    *rw_write!(globals::CONFIG) = config;
+    true
+}
+
+/// Put project on top of recent projects
+pub(crate) fn add_recent(path: &Path) {
+    let mut recent = config::get_recent_projects();
+    recent.retain(|p| p != path);
+    recent.insert(0, path.to_path_buf());
+    recent.truncate(RECENT_PROJECTS_LIMIT);
+    config::save_recent_projects(&recent);
+}
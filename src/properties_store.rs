@@ -0,0 +1,450 @@
+/*
+    This file is part of Post Maker.
+    Post Maker is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+    Post Maker is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with Post Maker.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Read and write properties of images, as `.prop` file next to every image or in one index file.
+//!
+//! Index is kept in config folder for every folder of images, so folders of images are not
+//! touched, and properties are same with or without a project open. Images in index are found by
+//! path, and by hash of their content if they are renamed or moved in folder. Changes of index are
+//! kept in memory and written by `flush`, so batch work writes every index file once.
+use crate::{
+    config::{self, PropertiesStorage},
+    error::Error,
+    globals, manifest,
+    result_ext::ResultExt,
+    utils::{self, ImageInfo, ImagePropertiesFile},
+};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::RwLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+lazy_static! {
+    /// Indexes read till now, by path of index file
+    static ref INDEXES: RwLock<HashMap<PathBuf, Index>> = RwLock::new(HashMap::new());
+}
+
+/// Properties of images of a folder or project
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Index {
+    /// Folder of images of index
+    #[serde(default)]
+    source: PathBuf,
+    #[serde(default)]
+    images: Vec<IndexEntry>,
+    /// Images which were hashed and are not in index, with their size and modified time
+    #[serde(skip)]
+    not_found: HashMap<PathBuf, (u64, u64)>,
+    /// Entries were changed since index was written
+    #[serde(skip)]
+    changed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexEntry {
+    /// Path of image when it was last seen
+    path: PathBuf,
+    /// Hash of content of image
+    hash: String,
+    /// Size of image, to not hash it again if size and modified time are same
+    size: u64,
+    /// Modified time of image in seconds
+    modified: u64,
+    /// Time properties were saved in seconds
+    saved: u64,
+    properties: ImagePropertiesFile,
+}
+
+impl Index {
+    /// Read index, empty if it does not exist. Corrupt index is kept aside as `.corrupt`
+    fn load(path: &Path, source: PathBuf) -> Self {
+        let index = match fs::read(path) {
+            Ok(data) => serde_json::from_slice::<Self>(&data).map_err(|e| {
+                warn!("Index {:?} is corrupt: {}", path, e);
+                fs::rename(path, path.with_extension("corrupt"))
+                    .warn_log("Failed to keep aside corrupt index!");
+            }),
+            Err(_) => Err(()),
+        };
+        index.unwrap_or(Self {
+            source,
+            ..Default::default()
+        })
+    }
+
+    /// Position of image in index by path, if image is not changed since it was seen.
+    /// `None` if hash of content of image is needed to find it
+    fn find_by_stamp(&self, path: &Path, stamp: Option<(u64, u64)>) -> Option<Option<usize>> {
+        let (size, modified) = match stamp {
+            Some(stamp) => stamp,
+            // Image is deleted, only path can be used
+            None => return Some(self.images.iter().position(|e| e.path == path)),
+        };
+        if let Some(i) = self
+            .images
+            .iter()
+            .position(|e| e.path == path && e.size == size && e.modified == modified)
+        {
+            return Some(Some(i));
+        }
+        if self.not_found.get(path) == Some(&(size, modified)) {
+            return Some(None);
+        }
+        None
+    }
+
+    /// Position of image in index by hash of its content, entry gets current path and stamp
+    fn find_by_hash(
+        &mut self,
+        path: &Path,
+        (size, modified): (u64, u64),
+        hash: &str,
+    ) -> Option<usize> {
+        let found = self
+            .images
+            .iter()
+            .position(|e| e.path == path && e.hash == hash)
+            // Renamed or moved
+            .or_else(|| {
+                self.images
+                    .iter()
+                    .position(|e| e.hash == hash && !e.path.exists())
+            })
+            // Edited
+            .or_else(|| self.images.iter().position(|e| e.path == path));
+        match found {
+            Some(i) => {
+                let entry = &mut self.images[i];
+                entry.path = path.to_path_buf();
+                entry.hash = hash.to_owned();
+                entry.size = size;
+                entry.modified = modified;
+                self.changed = true;
+            }
+            None => {
+                self.not_found.insert(path.to_path_buf(), (size, modified));
+            }
+        }
+        found
+    }
+
+    /// Replace properties of image at position
+    fn set(&mut self, i: usize, properties: &ImagePropertiesFile) {
+        self.images[i].properties = properties.clone();
+        self.images[i].saved = seconds(SystemTime::now());
+        self.changed = true;
+    }
+
+    /// Add image which is not in index. Image added meanwhile by other thread is replaced
+    fn insert(
+        &mut self,
+        path: &Path,
+        (size, modified): (u64, u64),
+        hash: String,
+        properties: &ImagePropertiesFile,
+    ) {
+        if let Some(i) = self
+            .images
+            .iter()
+            .position(|e| e.path == path && e.hash == hash)
+        {
+            self.set(i, properties);
+            return;
+        }
+        self.not_found.remove(path);
+        self.images.push(IndexEntry {
+            path: path.to_path_buf(),
+            hash,
+            size,
+            modified,
+            saved: seconds(SystemTime::now()),
+            properties: properties.clone(),
+        });
+        self.changed = true;
+    }
+}
+
+/// Image as it was found in index
+struct Found {
+    /// Position of image in index
+    position: Option<usize>,
+    /// Hash of content of image, if it was needed to find image
+    hash: Option<String>,
+}
+
+/// Run on index of image with position of image in it. Image is hashed without lock of indexes,
+/// so other threads can use them meanwhile
+fn with_image<T>(image_path: &Path, f: impl FnOnce(&mut Index, Found) -> T) -> T {
+    let stamp = stamp(image_path);
+    let f = with_index(image_path, |index| {
+        match index.find_by_stamp(image_path, stamp) {
+            Some(position) => Ok(f(
+                index,
+                Found {
+                    position,
+                    hash: None,
+                },
+            )),
+            None => Err(f),
+        }
+    });
+    let f = match f {
+        Ok(result) => return result,
+        Err(f) => f,
+    };
+
+    let hash = manifest::file_hash(image_path)
+        .ok()
+        .map(|hash| format!("{:016x}", hash));
+    with_index(image_path, |index| {
+        let position = match (stamp, &hash) {
+            (Some(stamp), Some(hash)) => index.find_by_hash(image_path, stamp, hash),
+            _ => None,
+        };
+        f(index, Found { position, hash })
+    })
+}
+
+/// Size and modified time of file
+fn stamp(path: &Path) -> Option<(u64, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.len(), seconds(meta.modified().ok()?)))
+}
+
+fn seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// Path of index in which image is kept, and folder of image
+fn index_path(image_path: &Path) -> (PathBuf, PathBuf) {
+    let folder = image_path.parent().unwrap_or_else(|| Path::new(""));
+    let folder = folder
+        .canonicalize()
+        .unwrap_or_else(|_| folder.to_path_buf());
+    let hash = manifest::bytes_hash(folder.to_string_lossy().as_bytes());
+    (
+        config::index_dir().join(format!("{:016x}.json", hash)),
+        folder,
+    )
+}
+
+/// Run on index of image
+fn with_index<T>(image_path: &Path, f: impl FnOnce(&mut Index) -> T) -> T {
+    let (path, source) = index_path(image_path);
+    let mut indexes = rw_write!(INDEXES);
+    let index = indexes
+        .entry(path.clone())
+        .or_insert_with(|| Index::load(&path, source));
+    f(index)
+}
+
+/// Write indexes which were changed
+pub(crate) fn flush() -> Result<(), Error> {
+    // Files are written without lock, so other threads can use indexes meanwhile
+    let changed: Vec<(PathBuf, String)> = rw_write!(INDEXES)
+        .iter_mut()
+        .filter(|(_, index)| index.changed)
+        .map(|(path, index)| {
+            index.changed = false;
+            (path.clone(), serde_json::to_string(index).unwrap())
+        })
+        .collect();
+    let mut errors = vec![];
+    for (path, data) in changed {
+        if let Err(e) = fs::write(&path, data) {
+            errors.push((
+                path.display().to_string(),
+                Error::Io("Failed to write".to_owned(), e),
+            ));
+            if let Some(index) = rw_write!(INDEXES).get_mut(&path) {
+                index.changed = true;
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Batch("Failed to save index".to_owned(), errors))
+    }
+}
+
+fn storage() -> PropertiesStorage {
+    rw_read!(globals::CONFIG).properties_storage
+}
+
+/// Properties of image, `None` if it has none
+pub(crate) fn read(image: &ImageInfo) -> Result<Option<ImagePropertiesFile>, Error> {
+    match storage() {
+        PropertiesStorage::Files => read_file(&utils::get_properties_path(image)),
+        PropertiesStorage::Index => Ok(with_image(&image.path, |index, found| {
+            found.position.map(|i| index.images[i].properties.clone())
+        })),
+    }
+}
+
+/// Save properties of image, index is written by `flush`
+pub(crate) fn write(image: &ImageInfo, properties: &ImagePropertiesFile) -> Result<(), Error> {
+    match storage() {
+        PropertiesStorage::Files => fs::write(
+            utils::get_properties_path(image),
+            serde_json::to_string(properties).unwrap(),
+        )
+        .map_err(|e| Error::Io("Failed to save properties".to_owned(), e)),
+        PropertiesStorage::Index => write_index(image, properties),
+    }
+}
+
+/// Remove properties of image, if it has any. Index is written by `flush`
+pub(crate) fn remove(image: &ImageInfo) -> Result<(), Error> {
+    match storage() {
+        PropertiesStorage::Files => {
+            let path = utils::get_properties_path(image);
+            if path.exists() {
+                fs::remove_file(path)
+                    .map_err(|e| Error::Io("Failed to delete properties".to_owned(), e))?;
+            }
+            Ok(())
+        }
+        PropertiesStorage::Index => {
+            with_image(&image.path, |index, found| {
+                if let Some(i) = found.position {
+                    index.images.remove(i);
+                    index.changed = true;
+                }
+            });
+            Ok(())
+        }
+    }
+}
+
+/// Give properties of image to its copy
+pub(crate) fn copy(image: &ImageInfo, new_image: &ImageInfo) -> Result<(), Error> {
+    match read(image)? {
+        Some(properties) => write(new_image, &properties),
+        None => Ok(()),
+    }
+}
+
+/// When properties of image were last saved
+pub(crate) fn modified(image: &ImageInfo) -> Option<SystemTime> {
+    match storage() {
+        PropertiesStorage::Files => fs::metadata(utils::get_properties_path(image))
+            .and_then(|meta| meta.modified())
+            .ok(),
+        PropertiesStorage::Index => with_image(&image.path, |index, found| {
+            let i = found.position?;
+            Some(UNIX_EPOCH + Duration::from_secs(index.images[i].saved))
+        }),
+    }
+}
+
+/// Copy `.prop` files of images in index, and delete them if `delete` is true.
+/// Returns count of copied files
+pub(crate) fn migrate(images: &[ImageInfo], delete: bool) -> Result<usize, Error> {
+    let mut copied = vec![];
+    let mut errors = vec![];
+    for image in images {
+        let path = utils::properties_file_path(image);
+        // Files of old versions are named without extension of image
+        let path = if path.exists() {
+            path
+        } else {
+            image.path.with_extension("prop")
+        };
+        let properties = match read_file(&path) {
+            Ok(Some(properties)) => properties,
+            Ok(None) => continue,
+            Err(e) => {
+                errors.push((path.display().to_string(), e));
+                continue;
+            }
+        };
+        if let Err(e) = write_index(image, &properties) {
+            errors.push((image.path.display().to_string(), e));
+            continue;
+        }
+        copied.push(path);
+    }
+    let count = copied.len();
+    match flush() {
+        // Files are deleted only when index with their properties is written
+        Ok(_) if delete => {
+            for path in copied {
+                if let Err(e) = fs::remove_file(&path) {
+                    errors.push((
+                        path.display().to_string(),
+                        Error::Io("Failed to delete".to_owned(), e),
+                    ));
+                }
+            }
+        }
+        Ok(_) => (),
+        Err(e) => errors.push(("Index".to_owned(), e)),
+    }
+    if errors.is_empty() {
+        Ok(count)
+    } else {
+        Err(Error::Batch(
+            format!("Moved {} properties, failed for", count),
+            errors,
+        ))
+    }
+}
+
+fn read_file(path: &Path) -> Result<Option<ImagePropertiesFile>, Error> {
+    match fs::read(path) {
+        Ok(data) => serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(|e| Error::Corrupt(format!("Properties are corrupt: {}", e))),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Error::Io("Failed to read properties".to_owned(), e)),
+    }
+}
+
+fn write_index(image: &ImageInfo, properties: &ImagePropertiesFile) -> Result<(), Error> {
+    let path = &image.path;
+    let stamp = stamp(path).ok_or_else(|| {
+        Error::Io(
+            "Failed to read image".to_owned(),
+            io::ErrorKind::NotFound.into(),
+        )
+    })?;
+    let new = with_image(path, |index, found| match found.position {
+        Some(i) => {
+            index.set(i, properties);
+            None
+        }
+        None => Some(found.hash),
+    });
+
+    // Image is not in index, it is added with hash of its content
+    if let Some(hash) = new {
+        let hash = match hash {
+            Some(hash) => hash,
+            None => format!(
+                "{:016x}",
+                manifest::file_hash(path)
+                    .map_err(|e| Error::Io("Failed to read image".to_owned(), e))?
+            ),
+        };
+        with_index(path, |index| index.insert(path, stamp, hash, properties));
+    }
+    Ok(())
+}
@@ -0,0 +1,380 @@
+/*
+    This file is part of Post Maker.
+    Post Maker is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+    Post Maker is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with Post Maker.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Read quotes of many images from CSV or JSON file and merge them in properties of images
+use crate::{
+    error::Error,
+    properties_store,
+    utils::{ImageInfo, ImagePropertiesFile},
+};
+use serde::Deserialize;
+use std::{fs, path::Path};
+
+/// Texts of one image read from file, fields which are missing or empty are kept as they are
+#[derive(Debug, Default, Clone, Deserialize)]
+pub(crate) struct ImportRow {
+    /// File name of image, rows without it are matched by order of loaded images
+    #[serde(default, alias = "file", alias = "filename")]
+    pub(crate) image: Option<String>,
+    #[serde(default)]
+    pub(crate) quote: Option<String>,
+    #[serde(default)]
+    pub(crate) subquote: Option<String>,
+    #[serde(default)]
+    pub(crate) subquote2: Option<String>,
+    #[serde(default)]
+    pub(crate) tag: Option<String>,
+    #[serde(default)]
+    pub(crate) tag2: Option<String>,
+    #[serde(default, alias = "prefix")]
+    pub(crate) name_prefix: Option<String>,
+}
+
+/// Read rows of JSON file, array of objects, or CSV file with header
+pub(crate) fn read(path: &Path) -> Result<Vec<ImportRow>, Error> {
+    let text =
+        fs::read_to_string(path).map_err(|e| Error::Io("Failed to read file".to_owned(), e))?;
+    let text = text.trim_start_matches('\u{feff}');
+    let is_json = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    if is_json {
+        serde_json::from_str(text)
+            .map_err(|e| Error::Input(format!("File is not valid JSON: {}", e)))
+    } else {
+        parse_csv(text)
+    }
+}
+
+fn parse_csv(text: &str) -> Result<Vec<ImportRow>, Error> {
+    let mut records = csv_records(text, csv_delimiter(text)).into_iter();
+    let header = records
+        .next()
+        .ok_or_else(|| Error::Input("File is empty".to_owned()))?;
+    let columns: Vec<String> = header
+        .iter()
+        .map(|name| name.trim().to_lowercase())
+        .collect();
+    let known = [
+        "image",
+        "file",
+        "filename",
+        "quote",
+        "subquote",
+        "subquote2",
+        "tag",
+        "tag2",
+        "name_prefix",
+        "prefix",
+    ];
+    if !columns.iter().any(|name| known.contains(&name.as_str())) {
+        return Err(Error::Input(format!(
+            "Header has no known column, expected some of: {}",
+            known.join(", ")
+        )));
+    }
+
+    Ok(records
+        .map(|record| {
+            let mut row = ImportRow::default();
+            for (name, value) in columns.iter().zip(record) {
+                if value.trim().is_empty() {
+                    continue;
+                }
+                let field = match name.as_str() {
+                    "image" | "file" | "filename" => &mut row.image,
+                    "quote" => &mut row.quote,
+                    "subquote" => &mut row.subquote,
+                    "subquote2" => &mut row.subquote2,
+                    "tag" => &mut row.tag,
+                    "tag2" => &mut row.tag2,
+                    "name_prefix" | "prefix" => &mut row.name_prefix,
+                    _ => continue,
+                };
+                *field = Some(value);
+            }
+            row
+        })
+        .collect())
+}
+
+/// Most used of comma, semicolon and tab in first line
+fn csv_delimiter(text: &str) -> char {
+    let header = text.lines().next().unwrap_or_default();
+    [',', ';', '\t']
+        .into_iter()
+        .max_by_key(|d| header.matches(*d).count())
+        .unwrap_or(',')
+}
+
+/// Fields of every record, quoted fields may have delimiter, new lines and `""` for quote
+fn csv_records(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                c => field.push(c),
+            }
+        } else {
+            match c {
+                '"' => in_quotes = true,
+                '\r' => (),
+                '\n' => {
+                    record.push(std::mem::take(&mut field));
+                    records.push(std::mem::take(&mut record));
+                }
+                c if c == delimiter => record.push(std::mem::take(&mut field)),
+                c => field.push(c),
+            }
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records.retain(|record| record.iter().any(|field| !field.trim().is_empty()));
+    records
+}
+
+/// What import changes in properties of one image
+#[derive(Debug, Clone)]
+pub(crate) struct ImportChange {
+    /// Position of row in file, from 1
+    pub(crate) row: usize,
+    /// Name of image in file or of image picked by order
+    pub(crate) name: String,
+    /// None if there is no such image in folder
+    pub(crate) image: Option<ImageInfo>,
+    /// Names of fields which change
+    pub(crate) changed: Vec<&'static str>,
+    /// Image has no properties file yet
+    pub(crate) is_new: bool,
+    /// Properties after import
+    properties: ImagePropertiesFile,
+}
+
+impl ImportChange {
+    pub(crate) fn is_change(&self) -> bool {
+        self.image.is_some() && !self.changed.is_empty()
+    }
+
+    /// Line to show in browser, columns separated by tab
+    pub(crate) fn to_row(&self) -> String {
+        let status = match (&self.image, self.changed.is_empty(), self.is_new) {
+            (None, _, _) => "not found",
+            (Some(_), true, _) => "unchanged",
+            (Some(_), false, true) => "new",
+            (Some(_), false, false) => "changed",
+        };
+        format!(
+            "{}\t{}\t{}\t{}",
+            self.row,
+            self.name,
+            status,
+            self.changed.join(", ")
+        )
+    }
+}
+
+/// Changes import would make, without writing anything. `images` are all loaded images, in
+/// order they were loaded, not as sorted or filtered in list
+pub(crate) fn plan(rows: &[ImportRow], images: &[ImageInfo]) -> Vec<ImportChange> {
+    rows.iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let image = row_image(row, i, images);
+            let name = match (&row.image, image) {
+                (Some(name), _) => name.trim().to_owned(),
+                (None, Some(image)) => image
+                    .path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned(),
+                (None, None) => String::new(),
+            };
+
+            let existing = image.and_then(|image| properties_store::read(image).ok().flatten());
+            let is_new = existing.is_none();
+            let mut properties = existing.unwrap_or_default();
+            let mut changed = vec![];
+            for (field_name, value, field) in [
+                ("quote", &row.quote, &mut properties.quote),
+                ("subquote", &row.subquote, &mut properties.subquote),
+                ("subquote2", &row.subquote2, &mut properties.subquote2),
+                ("tag", &row.tag, &mut properties.tag),
+                ("tag2", &row.tag2, &mut properties.tag2),
+                ("name_prefix", &row.name_prefix, &mut properties.name_prefix),
+            ] {
+                if value.is_some() && *value != *field {
+                    *field = value.clone();
+                    changed.push(field_name);
+                }
+            }
+
+            ImportChange {
+                row: i + 1,
+                name,
+                image: image.cloned(),
+                changed,
+                is_new,
+                properties,
+            }
+        })
+        .collect()
+}
+
+/// Image of row at position `i`, by its file name or else by its position
+fn row_image<'a>(row: &ImportRow, i: usize, images: &'a [ImageInfo]) -> Option<&'a ImageInfo> {
+    match &row.image {
+        Some(name) => find_image(images, name.trim()),
+        None => images.get(i),
+    }
+}
+
+/// Image with file name, ignoring case if there is no exact match
+fn find_image<'a>(images: &'a [ImageInfo], name: &str) -> Option<&'a ImageInfo> {
+    let file_name = |image: &ImageInfo| {
+        image
+            .path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()
+    };
+    images
+        .iter()
+        .find(|image| file_name(image) == name)
+        .or_else(|| {
+            images
+                .iter()
+                .find(|image| file_name(image).eq_ignore_ascii_case(name))
+        })
+}
+
+/// Write properties of images which change, returns count of written files
+pub(crate) fn apply(changes: &[ImportChange]) -> Result<usize, Error> {
+    let mut written = 0;
+    let mut errors = vec![];
+    for change in changes.iter().filter(|change| change.is_change()) {
+        let image = change.image.as_ref().unwrap();
+        match properties_store::write(image, &change.properties) {
+            Ok(_) => written += 1,
+            Err(e) => errors.push((change.name.clone(), e)),
+        }
+    }
+    if let Err(e) = properties_store::flush() {
+        errors.push(("Index".to_owned(), e));
+    }
+    if errors.is_empty() {
+        Ok(written)
+    } else {
+        Err(Error::Batch(
+            format!("Imported {} images, failed for", written),
+            errors,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::ImageType;
+    use std::path::PathBuf;
+
+    fn image(path: &str) -> ImageInfo {
+        ImageInfo {
+            path: PathBuf::from(path),
+            image_type: ImageType::Jpeg,
+        }
+    }
+
+    #[test]
+    fn quoted_fields_keep_delimiters_newlines_and_quotes() {
+        let records = csv_records("a,b\n\"x, y\",\"line 1\nline \"\"2\"\"\"\n", ',');
+        assert_eq!(
+            records,
+            vec![
+                vec!["a".to_owned(), "b".to_owned()],
+                vec!["x, y".to_owned(), "line 1\nline \"2\"".to_owned()],
+            ]
+        );
+    }
+
+    #[test]
+    fn carriage_returns_and_empty_lines_are_skipped() {
+        let records = csv_records("a,b\r\n\r\n1,2\r\n,\r\n3,4", ',');
+        assert_eq!(
+            records,
+            vec![
+                vec!["a".to_owned(), "b".to_owned()],
+                vec!["1".to_owned(), "2".to_owned()],
+                vec!["3".to_owned(), "4".to_owned()],
+            ]
+        );
+    }
+
+    #[test]
+    fn delimiter_is_found_from_header() {
+        assert_eq!(csv_delimiter("image;quote;tag\na,b;c;d"), ';');
+        assert_eq!(csv_delimiter("image\tquote\na;b\tc"), '\t');
+        assert_eq!(csv_delimiter("image,quote"), ',');
+        assert_eq!(csv_delimiter("quote"), ',');
+    }
+
+    #[test]
+    fn csv_columns_are_matched_by_name() {
+        let rows =
+            parse_csv("File;Quote;Unknown;Tag\na.jpg;\"Hi; there\";x;\n;Second;;#t\n").unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].image.as_deref(), Some("a.jpg"));
+        assert_eq!(rows[0].quote.as_deref(), Some("Hi; there"));
+        assert_eq!(rows[0].tag, None);
+        assert_eq!(rows[1].image, None);
+        assert_eq!(rows[1].quote.as_deref(), Some("Second"));
+        assert_eq!(rows[1].tag.as_deref(), Some("#t"));
+    }
+
+    #[test]
+    fn csv_without_known_column_is_error() {
+        assert!(parse_csv("name,text\na,b\n").is_err());
+        assert!(parse_csv("").is_err());
+    }
+
+    #[test]
+    fn rows_are_matched_by_name_or_position() {
+        let images = [image("/p/a.jpg"), image("/p/B.jpg"), image("/p/c.jpg")];
+        let named = |name: &str| ImportRow {
+            image: Some(name.to_owned()),
+            ..Default::default()
+        };
+        let unnamed = ImportRow::default();
+
+        let found = |row: &ImportRow, i| row_image(row, i, &images).map(|image| image.path.clone());
+        assert_eq!(found(&unnamed, 0), Some(PathBuf::from("/p/a.jpg")));
+        assert_eq!(found(&unnamed, 2), Some(PathBuf::from("/p/c.jpg")));
+        assert_eq!(found(&unnamed, 3), None);
+        assert_eq!(found(&named(" b.jpg "), 0), Some(PathBuf::from("/p/B.jpg")));
+        assert_eq!(found(&named("d.jpg"), 0), None);
+    }
+}
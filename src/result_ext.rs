@@ -12,7 +12,8 @@
     along with Post Maker.  If not, see <https://www.gnu.org/licenses/>
 */
 
-use crate::utils;
+use crate::error;
+use log::Level;
 use std::{fmt::Debug, panic::Location};
 
 pub trait ResultExt<T, E> {
@@ -27,8 +28,7 @@ impl<T, E: Debug> ResultExt<T, E> for Result<T, E> {
         match self {
             Ok(v) => v,
             Err(e) => {
-                error!("{}\n{:?}\n{}", msg, e, Location::caller());
-                utils::show_program_panic(msg);
+                error::report_panic(msg, &format!("{:?}\n{}", e, Location::caller()));
                 panic!("[panic]");
             }
         }
@@ -37,16 +37,18 @@ impl<T, E: Debug> ResultExt<T, E> for Result<T, E> {
     #[track_caller]
     fn error_log(&self, msg: &str) {
         if let Err(e) = self {
-            error!("{}\n{:?}\n{}", msg, e, Location::caller());
-            utils::show_alert(msg);
+            error::report(
+                Level::Error,
+                msg,
+                &format!("{:?}\n{}", e, Location::caller()),
+            );
         }
     }
 
     #[track_caller]
     fn warn_log(&self, msg: &str) {
         if let Err(e) = self {
-            warn!("{}\n{:?}", msg, e);
-            utils::show_alert(msg);
+            error::report(Level::Warn, msg, &format!("{:?}", e));
         }
     }
 }
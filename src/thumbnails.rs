@@ -0,0 +1,394 @@
+/*
+    This file is part of Post Maker.
+    Post Maker is free software: you can redistribute it and/or modify
+    it under the terms of the GNU General Public License as published by
+    the Free Software Foundation, either version 3 of the License, or
+    (at your option) any later version.
+    Post Maker is distributed in the hope that it will be useful,
+    but WITHOUT ANY WARRANTY; without even the implied warranty of
+    MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+    GNU General Public License for more details.
+    You should have received a copy of the GNU General Public License
+    along with Post Maker.  If not, see <https://www.gnu.org/licenses/>
+*/
+
+//! Strip of thumbnails of opened images, to see and open them quickly.
+//!
+//! Thumbnails are made on a background thread only when they are shown, and kept till images
+//! are opened again. Badges are checked again when they are shown after a while, so changes by
+//! Save, Export All or Import are seen without tracking them.
+use crate::{
+    draw_thread::DrawMessage,
+    image_list,
+    main_window::SenderExt,
+    properties_store,
+    result_ext::ResultExt,
+    utils::{self, ImageInfo, ImageProperties},
+};
+use fltk::{
+    app, draw as dr,
+    enums::{self, Align, Color, ColorDepth, Font, FrameType},
+    frame::Frame,
+    menu,
+    prelude::*,
+    valuator::Scrollbar,
+};
+use image::GenericImageView;
+use std::{
+    cell::Cell,
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    rc::Rc,
+    sync::{mpsc, Arc, RwLock},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Height of space of one image in strip
+const CELL_HEIGHT: i32 = 120;
+
+/// Largest width and height of thumbnail
+const THUMBNAIL_SIZE: u32 = 100;
+
+/// Badges older than this are checked again when they are shown
+const BADGES_AGE: Duration = Duration::from_secs(3);
+
+/// Small image and state of an image
+#[derive(Debug, Default)]
+struct Thumbnail {
+    /// RGB pixels, with width and height
+    image: Option<(Vec<u8>, i32, i32)>,
+    /// Image could not be opened, it is not tried again
+    unreadable: bool,
+    too_small: bool,
+    has_quote: bool,
+    exported: bool,
+    /// When badges were checked
+    checked: Option<Instant>,
+}
+
+/// Work for thumbnails thread
+struct Request {
+    image: ImageInfo,
+    /// Make thumbnail, or else only check badges
+    make_image: bool,
+}
+
+#[derive(Clone)]
+pub(crate) struct Thumbnails {
+    pub(crate) strip: Frame,
+    pub(crate) scrollbar: Scrollbar,
+    /// Thumbnails made till now, by path of image
+    cache: Arc<RwLock<HashMap<PathBuf, Thumbnail>>>,
+    /// Images sent to thumbnails thread and not done yet
+    pending: Arc<RwLock<HashSet<PathBuf>>>,
+    requests: mpsc::Sender<Request>,
+    /// Image which was open at last draw, to scroll to it when it changes
+    shown: Rc<Cell<i32>>,
+}
+
+impl Thumbnails {
+    pub(crate) fn new() -> Self {
+        let mut strip = Frame::default();
+        strip.set_frame(FrameType::FlatBox);
+        strip.set_color(Color::Background2);
+        let mut scrollbar = Scrollbar::default();
+        scrollbar.set_step(1.0, 1);
+
+        let cache = Arc::new(RwLock::new(HashMap::new()));
+        let pending = Arc::new(RwLock::new(HashSet::new()));
+        let (requests, receiver) = mpsc::channel();
+        spawn_thumbnails_thread(
+            receiver,
+            Arc::clone(&cache),
+            Arc::clone(&pending),
+            strip.clone(),
+        );
+
+        Self {
+            strip,
+            scrollbar,
+            cache,
+            pending,
+            requests,
+            shown: Rc::new(Cell::new(-1)),
+        }
+    }
+
+    /// Forget thumbnails, for images which are opened again
+    pub(crate) fn clear(&mut self) {
+        rw_write!(self.cache).clear();
+        self.shown.set(-1);
+        self.scrollbar.set_value(0.0);
+        self.strip.redraw();
+    }
+
+    /// Forget thumbnails of images which changed on disk, so they are made again
+    pub(crate) fn forget(&mut self, paths: &[PathBuf]) {
+        let mut cache = rw_write!(self.cache);
+        for path in paths {
+            cache.remove(path);
+        }
+        drop(cache);
+        self.strip.redraw();
+    }
+
+    /// Set drawing and callbacks of strip
+    pub(crate) fn event(
+        &mut self,
+        images_list: Arc<RwLock<Vec<ImageInfo>>>,
+        properties: Arc<RwLock<ImageProperties>>,
+        file_choice: menu::Choice,
+        sender: mpsc::Sender<DrawMessage>,
+    ) {
+        // Scrollbar
+        let mut strip = self.strip.clone();
+        self.scrollbar.set_callback(move |_| {
+            strip.redraw();
+        });
+
+        // Draw visible thumbnails, and ask for the ones which are not made
+        let imgs = Arc::clone(&images_list);
+        let props = Arc::clone(&properties);
+        let choice = file_choice.clone();
+        let mut scrollbar = self.scrollbar.clone();
+        let thumbnails = self.clone();
+        self.strip.draw(move |f| {
+            let imgs = rw_read!(imgs);
+            let selected = choice.value();
+            let is_saved = rw_read!(props).is_saved;
+
+            // Scroll range, and keep opened image in view when it changes
+            let total = imgs.len() as i32 * CELL_HEIGHT;
+            let max = (total - f.h()).max(0);
+            scrollbar.set_bounds(0.0, max as f64);
+            scrollbar.set_slider_size(if total > 0 {
+                (f.h() as f32 / total as f32).min(1.0)
+            } else {
+                1.0
+            });
+            let mut scroll = (scrollbar.value() as i32).clamp(0, max);
+            if selected != thumbnails.shown.get() && selected >= 0 {
+                let top = selected * CELL_HEIGHT;
+                if top < scroll {
+                    scroll = top;
+                } else if top + CELL_HEIGHT > scroll + f.h() {
+                    scroll = (top + CELL_HEIGHT - f.h()).min(max);
+                }
+                thumbnails.shown.set(selected);
+            }
+            scrollbar.set_value(scroll as f64);
+
+            dr::push_clip(f.x(), f.y(), f.w(), f.h());
+            dr::draw_box(
+                FrameType::FlatBox,
+                f.x(),
+                f.y(),
+                f.w(),
+                f.h(),
+                Color::Background2,
+            );
+            let first = (scroll / CELL_HEIGHT) as usize;
+            let last = ((scroll + f.h()) / CELL_HEIGHT + 1) as usize;
+            for (i, image) in imgs.iter().enumerate().take(last).skip(first) {
+                let y = f.y() + i as i32 * CELL_HEIGHT - scroll;
+                let unsaved = i as i32 == selected && !is_saved;
+                thumbnails.draw_cell(image, f.x(), y, f.w(), i as i32 == selected, unsaved);
+            }
+            dr::pop_clip();
+        });
+
+        // Open clicked image, scroll on wheel
+        let mut scrollbar = self.scrollbar.clone();
+        let mut file_choice = file_choice;
+        self.strip.handle(move |f, ev| match ev {
+            enums::Event::Push => {
+                let idx = (app::event_y() - f.y() + scrollbar.value() as i32) / CELL_HEIGHT;
+                if idx >= rw_read!(images_list).len() as i32 || idx == file_choice.value() {
+                    return true;
+                }
+                let is_saved = rw_read!(properties).is_saved;
+                if !is_saved {
+                    let save = fltk::dialog::choice2_default("Save?", "yes", "no", "cancel");
+                    match save.unwrap_or(-1) {
+                        0 => sender.send_it(DrawMessage::Save),
+                        1 => {}
+                        _ => return true,
+                    }
+                }
+                file_choice.set_value(idx);
+                sender.send_it(DrawMessage::Open);
+                sender.send_it(DrawMessage::CheckImage);
+                f.redraw();
+                true
+            }
+            enums::Event::MouseWheel => {
+                let step = match app::event_dy() {
+                    app::MouseWheel::Up => -CELL_HEIGHT as f64 / 2.0,
+                    app::MouseWheel::Down => CELL_HEIGHT as f64 / 2.0,
+                    _ => return false,
+                };
+                let value = (scrollbar.value() + step).clamp(0.0, scrollbar.maximum());
+                scrollbar.set_value(value);
+                f.redraw();
+                true
+            }
+            _ => false,
+        });
+    }
+
+    /// Draw thumbnail, name and badges of image in cell at `y`
+    fn draw_cell(&self, image: &ImageInfo, x: i32, y: i32, w: i32, selected: bool, unsaved: bool) {
+        if selected {
+            dr::draw_box(
+                FrameType::FlatBox,
+                x,
+                y,
+                w,
+                CELL_HEIGHT,
+                Color::Selection.lighter(),
+            );
+        }
+
+        let cache = rw_read!(self.cache);
+        let thumbnail = cache.get(&image.path);
+        let image_y = y + 4;
+        match thumbnail.and_then(|t| t.image.as_ref()) {
+            Some((data, width, height)) => {
+                let image_x = x + (w - width) / 2;
+                let image_y = image_y + (THUMBNAIL_SIZE as i32 - height) / 2;
+                // List is drawn often, dialog for every failed draw would block app
+                if let Err(e) =
+                    dr::draw_image(data, image_x, image_y, *width, *height, ColorDepth::Rgb8)
+                {
+                    warn!("Failed to draw thumbnail!\n{:?}", e);
+                }
+            }
+            None => {
+                let size = THUMBNAIL_SIZE as i32;
+                dr::draw_box(
+                    FrameType::BorderBox,
+                    x + (w - size) / 2,
+                    image_y,
+                    size,
+                    size,
+                    Color::Background,
+                );
+            }
+        }
+
+        dr::set_font(Font::Helvetica, 11);
+        dr::set_draw_color(Color::Foreground);
+        dr::draw_text2(
+            &image.path.file_name().unwrap_or_default().to_string_lossy(),
+            x + 2,
+            y + THUMBNAIL_SIZE as i32 + 4,
+            w - 4,
+            CELL_HEIGHT - THUMBNAIL_SIZE as i32 - 4,
+            Align::Center | Align::Inside | Align::Clip,
+        );
+
+        // Has quote, exported, too small and unsaved
+        let mut badges = vec![];
+        if let Some(t) = thumbnail {
+            if t.has_quote {
+                badges.push(("Q", Color::from_rgb(40, 150, 70)));
+            }
+            if t.exported {
+                badges.push(("E", Color::from_rgb(40, 100, 190)));
+            }
+            if t.too_small {
+                badges.push(("S", Color::from_rgb(200, 50, 40)));
+            }
+        }
+        if unsaved {
+            badges.push(("*", Color::from_rgb(220, 140, 20)));
+        }
+        dr::set_font(Font::HelveticaBold, 10);
+        for (i, (letter, color)) in badges.iter().enumerate() {
+            let badge_x = x + 4 + i as i32 * 16;
+            dr::draw_box(FrameType::FlatBox, badge_x, y + 4, 14, 14, *color);
+            dr::set_draw_color(Color::White);
+            dr::draw_text2(letter, badge_x, y + 4, 14, 14, Align::Center);
+        }
+
+        // Ask for thumbnail or badges which are not there or are old
+        let make_image = thumbnail.is_none_or(|t| t.image.is_none() && !t.unreadable);
+        let old = thumbnail
+            .and_then(|t| t.checked)
+            .is_none_or(|checked| checked.elapsed() > BADGES_AGE);
+        drop(cache);
+        if (make_image || old) && rw_write!(self.pending).insert(image.path.clone()) {
+            // Image stays pending, so this is logged once for every image
+            if let Err(e) = self.requests.send(Request {
+                image: image.clone(),
+                make_image,
+            }) {
+                warn!("Thumbnail thread is not running: {}", e);
+            }
+        }
+    }
+}
+
+/// Thread which makes thumbnails and checks badges, latest requests first
+fn spawn_thumbnails_thread(
+    receiver: mpsc::Receiver<Request>,
+    cache: Arc<RwLock<HashMap<PathBuf, Thumbnail>>>,
+    pending: Arc<RwLock<HashSet<PathBuf>>>,
+    mut strip: Frame,
+) {
+    thread::spawn(move || {
+        let mut requests: Vec<Request> = vec![];
+        loop {
+            if requests.is_empty() {
+                match receiver.recv() {
+                    Ok(request) => requests.push(request),
+                    Err(_) => return,
+                }
+            }
+            requests.extend(receiver.try_iter());
+            let request = requests.pop().unwrap();
+            let image = &request.image;
+
+            let properties = properties_store::read(image).ok().flatten();
+            let has_quote = properties
+                .as_ref()
+                .and_then(|p| p.quote.as_ref())
+                .is_some_and(|quote| !quote.trim().is_empty());
+            let exported = properties.as_ref().is_some_and(|p| {
+                utils::get_export_image_path(image, p, image_list::position(image)).exists()
+            });
+            let made = request.make_image.then(|| {
+                let img = utils::load_image(image).ok()?;
+                let (width, height) = img.dimensions();
+                let too_small = utils::is_too_small(width as f64, height as f64);
+                let thumb = img.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+                let (width, height) = thumb.dimensions();
+                Some((
+                    (thumb.to_rgb8().into_raw(), width as i32, height as i32),
+                    too_small,
+                ))
+            });
+
+            let mut cache = rw_write!(cache);
+            let thumbnail = cache.entry(image.path.clone()).or_default();
+            thumbnail.has_quote = has_quote;
+            thumbnail.exported = exported;
+            thumbnail.checked = Some(Instant::now());
+            match made {
+                Some(Some((pixels, too_small))) => {
+                    thumbnail.image = Some(pixels);
+                    thumbnail.too_small = too_small;
+                }
+                // Image which can't be opened is shown without thumbnail
+                Some(None) => thumbnail.unreadable = true,
+                None => (),
+            }
+            drop(cache);
+            rw_write!(pending).remove(&image.path);
+
+            strip.redraw();
+            app::awake();
+        }
+    });
+}
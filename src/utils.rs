@@ -13,10 +13,14 @@
 */
 
 use std::{
+    collections::hash_map::DefaultHasher,
     fs::{self, File},
-    io::Read,
+    hash::{Hash, Hasher},
+    io::{BufReader, Write},
+    panic,
     path::{Path, PathBuf},
     sync::{Arc, RwLock},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 use fltk::{button::Button, enums, prelude::*};
@@ -24,7 +28,15 @@ use image::{DynamicImage, GenericImageView, ImageBuffer, ImageEncoder, Pixel};
 use imageproc::rect::Rect;
 use serde::{Deserialize, Serialize};
 
+use crate::config::{ExportCollision, LegibilityFix};
+use crate::error::Error;
+use crate::exif::{self, Exif};
 use crate::globals;
+use crate::icc_profile;
+use crate::image_list;
+use crate::legibility;
+use crate::manifest::{self, Manifest};
+use crate::properties_store;
 use crate::result_ext::ResultExt;
 
 /// helps cast tupels to f64
@@ -83,6 +95,12 @@ pub(crate) enum ImageType {
     Jpeg,
     Png,
     Webp,
+    Tiff,
+    Bmp,
+    /// Only first frame is used
+    Gif,
+    /// Only opened if built with `avif` feature
+    Avif,
     None,
 }
 
@@ -92,6 +110,10 @@ impl ImageType {
             "image/jpeg" | "image/jpg" => Self::Jpeg,
             "image/png" => Self::Png,
             "image/webp" => Self::Webp,
+            "image/tiff" => Self::Tiff,
+            "image/bmp" => Self::Bmp,
+            "image/gif" => Self::Gif,
+            "image/avif" if cfg!(feature = "avif") => Self::Avif,
             _ => Self::None,
         }
     }
@@ -101,23 +123,47 @@ impl ImageType {
             Self::Jpeg => "jpg",
             Self::Png => "png",
             Self::Webp => "webp",
+            Self::Tiff => "tiff",
+            Self::Bmp => "bmp",
+            Self::Gif => "gif",
+            Self::Avif => "avif",
             Self::None => "none",
         }
         .to_owned()
     }
 }
 
+/// Name of image format which is known but can't be opened, as no decoder of it is available
+/// without system libraries. AVIF can be opened if built with `avif` feature
+pub(crate) fn unsupported_format(mime: &str) -> Option<&'static str> {
+    match mime {
+        "image/avif" if !cfg!(feature = "avif") => Some("AVIF"),
+        "image/heif" => Some("HEIC"),
+        _ => None,
+    }
+}
+
 /// Contains Image and its buffer(edited image)
 #[derive(Debug, Clone)]
 pub(crate) struct ImageContainer {
     pub(crate) image: DynamicImage,  //plain
     pub(crate) buffer: DynamicImage, //buffer to show
+    /// Cropped image in full resolution, to zoom in preview
+    pub(crate) full: DynamicImage,
+    /// Full resolution image drawn same as export, made when zoomed in
+    pub(crate) export_buffer: Option<DynamicImage>,
+    /// Result of every drawing stage of buffer with key of its inputs, see `Stage`
+    cache: Vec<(u64, DynamicImage)>,
     pub(crate) properties: Arc<RwLock<ImageProperties>>,
 }
 
 impl ImageContainer {
-    pub(crate) fn new(image_info: &ImageInfo, properties: Arc<RwLock<ImageProperties>>) -> Self {
-        let img = load_image(&image_info);
+    /// Load image, error if it can't be opened
+    pub(crate) fn new(
+        image_info: &ImageInfo,
+        properties: Arc<RwLock<ImageProperties>>,
+    ) -> Result<Self, Error> {
+        let img = load_image(image_info)?;
         let (width, height): (f64, f64) = Coord::from(img.dimensions()).into();
 
         let config = rw_read!(globals::CONFIG);
@@ -130,11 +176,14 @@ impl ImageContainer {
         prop.tag_position = height * config.tag_y_position_ratio;
         prop.tag2_position = height * config.tag2_position_ratio;
 
-        Self {
+        Ok(Self {
             image: img.clone(),
             buffer: img,
+            full: DynamicImage::new_rgb8(0, 0),
+            export_buffer: None,
+            cache: vec![],
             properties: Arc::clone(&properties),
-        }
+        })
     }
 
     /// Resize image
@@ -143,9 +192,12 @@ impl ImageContainer {
         let (width, height) = prop.dimension;
         let (s_width, s_height) = ((width * 500.0) / height, 500.0);
 
+        self.full = self.image.clone();
+        self.export_buffer = None;
         self.image = self.image.thumbnail_exact(s_width as u32, s_height as u32);
 
         self.buffer = self.image.clone();
+        self.cache.clear();
         prop.dimension = (s_width, s_height);
     }
 
@@ -171,6 +223,7 @@ impl ImageContainer {
             .image
             .crop(cx as u32, cy as u32, c_width as u32, c_height as u32);
         self.buffer = self.image.clone();
+        self.cache.clear();
     }
 
     pub(crate) fn apply_crop_position(&mut self, original_x: f64, original_y: f64) {
@@ -192,141 +245,142 @@ impl ImageContainer {
             .image
             .crop(cx as u32, cy as u32, c_width as u32, c_height as u32);
         self.buffer = self.image.clone();
+        self.cache.clear();
     }
 
-    /// Redraw: Copy image from main image to buffer and draw text and all on it
-    pub(crate) fn redraw_to_buffer(&mut self) {
-        let prop = rw_read!(self.properties);
-        let mut tmp = self.image.clone();
-
-        draw_layer_and_text(
-            &mut tmp,
-            &prop.translucent_layer_color,
-            &prop.quote,
-            &prop.subquote,
-            &prop.subquote2,
-            prop.quote_position,
-            prop.subquote_position,
-            prop.subquote2_position,
-            &prop.tag,
-            &prop.tag2,
-            prop.tag_position,
-            prop.tag2_position,
-            prop.original_dimension.0,
-            prop.original_dimension.1,
-        );
+    /// Redraw: Copy image from main image to buffer and draw text and all on it.
+    /// Returns false if drawing was stopped by `cancelled`, buffer is kept as it was then.
+    pub(crate) fn redraw_to_buffer(&mut self, cancelled: &dyn Fn() -> bool) -> bool {
+        let prop = rw_read!(self.properties).clone();
+        let (_, height): (f64, f64) = Coord::from(self.image.dimensions()).into();
+
+        // Stages are drawn again only from first stage whose inputs have changed
+        let mut key = 0;
+        let keys = STAGES.map(|stage| {
+            key = stage.key(key, &prop, height);
+            key
+        });
+        let unchanged = keys
+            .iter()
+            .zip(&self.cache)
+            .take_while(|(key, (cached, _))| *key == cached)
+            .count();
+        self.cache.truncate(unchanged);
+
+        let mut tmp = match self.cache.last() {
+            Some((_, image)) => image.clone(),
+            None => self.image.clone(),
+        };
+        for (stage, key) in STAGES.iter().zip(keys).skip(unchanged) {
+            if cancelled() {
+                return false;
+            }
+            stage.draw(&mut tmp, &prop);
+            self.cache.push((key, tmp.clone()));
+        }
 
         self.buffer = tmp;
+        self.export_buffer = None;
+        true
     }
 
-    /// Save image and properities
-    pub(crate) fn save(&self) {
-        let prop = rw_read!(self.properties);
-        let image_info = &prop.image_info;
-        let (export_path, path_properties, mut original_image) = match image_info {
-            Some(p) => (
-                get_export_image_path(p, &prop.name_prefix),
-                get_properties_path(p),
-                load_image(p),
-            ),
-            None => return,
-        };
-        let config = rw_read!(globals::CONFIG);
-        let export_format = &config.image_format;
-
-        let mut prop = prop.clone();
-        prop.image_info = None;
-        fs::write(
-            &path_properties,
-            serde_json::to_string(&ImagePropertiesFile::from(&prop)).unwrap(),
-        )
-        .warn_log("Failed to save properties!");
-
-        let (width, height): (f64, f64) = Coord::from(original_image.dimensions()).into();
-        let (crop_x, crop_y) = prop.crop_position.unwrap();
-        let (crop_width, crop_height) = croped_ratio(width, height);
-        let mut img = original_image.crop(
-            crop_x as u32,
-            crop_y as u32,
-            crop_width as u32,
-            crop_height as u32,
-        );
-
-        if crop_width > config.maximum_width_limit {
-            let (resize_width, resize_height) = (
-                config.maximum_width_limit,
-                height_from_width(config.maximum_width_limit),
-            );
-            img = img.resize_exact(
-                resize_width as u32,
-                resize_height as u32,
-                image::imageops::FilterType::Lanczos3,
-            );
+    /// Image drawn in full resolution exactly as it will be exported.
+    /// Returns None if drawing was stopped by `cancelled`.
+    pub(crate) fn export_preview(&mut self, cancelled: &dyn Fn() -> bool) -> Option<&DynamicImage> {
+        if self.export_buffer.is_none() {
+            let prop = rw_read!(self.properties).clone();
+            self.export_buffer = Some(render_export(&prop, self.full.clone(), cancelled)?);
         }
+        self.export_buffer.as_ref()
+    }
 
-        draw_layer_and_text(
-            &mut img,
-            &prop.translucent_layer_color,
-            &prop.quote,
-            &prop.subquote,
-            &prop.subquote2,
-            prop.quote_position,
-            prop.subquote_position,
-            prop.subquote2_position,
-            &prop.tag,
-            &prop.tag2,
-            prop.tag_position,
-            prop.tag2_position,
-            prop.original_dimension.0,
-            prop.original_dimension.1,
-        );
-
-        let mut output = match File::create(&export_path) {
-            Ok(a) => a,
-            Err(e) => {
-                Result::<(), _>::Err(e).warn_log("Failed to write to disk!");
-                return;
-            }
-        };
+    /// Least legible text on cropped image and its contrast ratio
+    pub(crate) fn legibility(&self) -> Option<(TextElement, f64)> {
+        legibility::worst_contrast(&self.image, &rw_read!(self.properties))
+    }
 
-        match export_format {
-            ImageType::Png => {
-                let encoder = image::codecs::png::PngEncoder::new_with_quality(
-                    &mut output,
-                    image::codecs::png::CompressionType::Best,
-                    image::codecs::png::FilterType::Sub,
-                );
-
-                let (w, h) = img.dimensions();
-                encoder
-                    .write_image(&img.into_rgba8(), w, h, image::ColorType::Rgba8)
-                    .warn_log("Failed to export Image!");
-            }
-            ImageType::Jpeg => {
-                let (width, height) = Coord::from(img.dimensions()).into();
-                let buf = img.into_rgb8();
+    /// Copy of properties with text made readable as per config, to export it without changing
+    /// saved properties. Image should not be cropped yet, like while exporting.
+    pub(crate) fn legibility_fixed(&self) -> ImageProperties {
+        let mut prop = rw_read!(self.properties).clone();
+        let config = rw_read!(globals::CONFIG);
+        let (method, minimum) = (config.legibility_fix, config.minimum_contrast_ratio);
+        drop(config);
+        if method == LegibilityFix::None {
+            return prop;
+        }
 
-                let mut comp = mozjpeg::Compress::new(mozjpeg::ColorSpace::JCS_RGB);
+        let (width, height) = prop.original_dimension;
+        let (crop_width, crop_height) = croped_ratio(width, height);
+        let (crop_x, crop_y) = prop
+            .crop_position
+            .unwrap_or(((width - crop_width) / 2.0, (height - crop_height) / 2.0));
+        let image = self
+            .image
+            .crop_imm(
+                crop_x as u32,
+                crop_y as u32,
+                crop_width as u32,
+                crop_height as u32,
+            )
+            .thumbnail((crop_width * 500.0 / crop_height) as u32, 500);
 
-                comp.set_size(width, height);
-                comp.set_quality(100.0);
-                comp.set_smoothing_factor(1);
-                comp.set_mem_dest();
-                comp.start_compress();
+        legibility::fix(&image, &mut prop, &method, minimum);
+        prop
+    }
 
-                comp.write_scanlines(&buf);
+    /// Save image and properities, returns path of exported image or None if it is skipped
+    /// because it already exists
+    pub(crate) fn save(&self) -> Result<Option<PathBuf>, Error> {
+        let prop = rw_read!(self.properties).clone();
+        let image_info = match &prop.image_info {
+            Some(p) => p,
+            None => return Err(Error::NoImage),
+        };
+        let mut prop_file = prop.clone();
+        prop_file.image_info = None;
+        let prop_file = ImagePropertiesFile::from(&prop_file);
+        properties_store::write(image_info, &prop_file)?;
+        properties_store::flush()?;
+
+        let index = image_list::position(image_info);
+        let export_path = get_export_image_path(image_info, &prop_file, index);
+        let export_dir = export_path.parent().unwrap_or_else(|| Path::new(""));
+        let mut manifest = Manifest::load(export_dir);
+        let own = manifest.output(&image_info.path, export_dir);
+        let output = Self::export(&prop, export_path.clone(), own.as_deref())?;
+
+        // Export All skips image while it is same as saved now
+        if let Some(output) = &output {
+            let image_hash = manifest::file_hash(&image_info.path)
+                .map_err(|e| Error::Io("Failed to read image".to_owned(), e))?;
+            let data = serde_json::to_vec(&prop_file).unwrap();
+            let hash =
+                manifest::input_hash(image_hash, &data, manifest::config_hash(), &export_path);
+            manifest.insert(&image_info.path, output, hash);
+            manifest
+                .save(export_dir)
+                .map_err(|e| Error::Io("Failed to save export manifest".to_owned(), e))?;
+        }
+        Ok(output)
+    }
 
-                comp.finish_compress();
+    /// Export image drawn with `export` properties to `export_path`, or path picked by collision
+    /// handling in config. `own` is earlier export of same image, which is replaced. Properties
+    /// are not saved
+    pub(crate) fn export(
+        export: &ImageProperties,
+        export_path: PathBuf,
+        own: Option<&Path>,
+    ) -> Result<Option<PathBuf>, Error> {
+        let collision = rw_read!(globals::CONFIG).export_collision;
+        write_export(&export_path, own, collision, || export_image(export))
+    }
 
-                match comp.data_to_vec() {
-                    Ok(data) => {
-                        std::fs::write(&export_path, data).warn_log("Failed to export Image!")
-                    }
-                    Err(e) => Result::<(), _>::Err(e).warn_log("Failed to encode image!"),
-                }
-            }
-            _ => (),
-        }
+    /// Bytes of exported image, same as written by save
+    pub(crate) fn export_bytes(&self) -> Result<Vec<u8>, Error> {
+        let prop = rw_read!(self.properties).clone();
+        export_image(&prop)
     }
 
     pub(crate) fn clone_img(&self) -> Option<ImageInfo> {
@@ -334,29 +388,27 @@ impl ImageContainer {
 
         match &prop.image_info {
             Some(image_info) => {
-                let stem = image_info.path.file_stem().unwrap().to_string_lossy();
-                let extension = image_info.path.extension().unwrap().to_string_lossy();
+                let stem = image_info
+                    .path
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy();
+                let extension = match image_info.path.extension() {
+                    Some(extension) => format!(".{}", extension.to_string_lossy()),
+                    None => String::new(),
+                };
                 let mut i = 1;
                 let mut new_image_info = image_info.clone();
                 while new_image_info.path.exists() {
-                    let new_file = format!("{}{}.{}", stem, "-copy".repeat(i), extension);
+                    let new_file = format!("{}{}{}", stem, "-copy".repeat(i), extension);
                     new_image_info.path = image_info.path.with_file_name(&new_file);
                     i += 1;
                 }
 
-                let path_properties = get_properties_path(&image_info);
-                let path_properties_new = get_properties_path(&new_image_info);
-
-                if image_info.path.exists() {
-                    fs::copy(&image_info.path, &new_image_info.path)
-                        .warn_log("Failed to clone image!");
-                }
-
-                if path_properties.exists() {
-                    fs::copy(path_properties, &path_properties_new)
-                        .warn_log("Failed to clone image properties!");
-                }
-                Some(new_image_info)
+                let copied = copy_image(image_info, &new_image_info);
+                copied.warn_log("Failed to clone image!");
+                properties_store::flush().warn_log("Failed to save properties index!");
+                copied.ok().map(|_| new_image_info)
             }
             None => None,
         }
@@ -364,22 +416,21 @@ impl ImageContainer {
 
     pub(crate) fn delete(&self) {
         let prop = rw_read!(self.properties);
-        let image_info = &prop.image_info;
-        let (export_path, path_image, path_properties) = match image_info {
-            Some(p) => (
-                get_export_image_path(p, &prop.name_prefix),
-                Path::new(&p.path),
-                get_properties_path(p),
-            ),
+        let image_info = match &prop.image_info {
+            Some(p) => p,
             None => return,
         };
+        let export_path = get_export_image_path(
+            image_info,
+            &ImagePropertiesFile::from(&*prop),
+            image_list::position(image_info),
+        );
 
-        if path_image.exists() {
-            fs::remove_file(path_image).warn_log("Failed to delete image!");
-        }
+        properties_store::remove(image_info).warn_log("Failed to delete image properties!");
+        properties_store::flush().warn_log("Failed to save properties index!");
 
-        if path_properties.exists() {
-            fs::remove_file(path_properties).warn_log("Failed to delete image properties!");
+        if image_info.path.exists() {
+            fs::remove_file(&image_info.path).warn_log("Failed to delete image!");
         }
 
         if export_path.exists() {
@@ -404,6 +455,7 @@ pub(crate) struct ImagePropertiesFile {
     pub(crate) tag_position: Option<f64>,   // as per original
     pub(crate) tag2_position: Option<f64>,  // as per original
     pub(crate) translucent_layer_color: Option<[u8; 4]>,
+    pub(crate) text_color: Option<[u8; 3]>,
 }
 
 impl Default for ImagePropertiesFile {
@@ -422,6 +474,7 @@ impl Default for ImagePropertiesFile {
             tag_position: None,
             tag2_position: None,
             translucent_layer_color: None,
+            text_color: None,
         }
     }
 }
@@ -442,6 +495,7 @@ impl From<&ImageProperties> for ImagePropertiesFile {
             tag_position: Some(props.tag_position),
             tag2_position: Some(props.tag2_position),
             translucent_layer_color: Some(props.translucent_layer_color),
+            text_color: Some(props.text_color),
         }
     }
 }
@@ -465,6 +519,7 @@ pub(crate) struct ImageProperties {
     pub(crate) tag_position: f64,       // as per original
     pub(crate) tag2_position: f64,      // as per original
     pub(crate) translucent_layer_color: [u8; 4],
+    pub(crate) text_color: [u8; 3],
     pub(crate) is_saved: bool,
 }
 
@@ -487,12 +542,24 @@ impl Default for ImageProperties {
             tag_position: 0.0,
             tag2_position: 0.0,
             translucent_layer_color: [0; 4],
+            text_color: [255, 255, 255],
             is_saved: true,
         }
     }
 }
 
 impl ImageProperties {
+    /// Position of text element, as per original
+    pub(crate) fn position_mut(&mut self, element: TextElement) -> &mut f64 {
+        match element {
+            TextElement::Quote => &mut self.quote_position,
+            TextElement::Subquote => &mut self.subquote_position,
+            TextElement::Subquote2 => &mut self.subquote2_position,
+            TextElement::Tag => &mut self.tag_position,
+            TextElement::Tag2 => &mut self.tag2_position,
+        }
+    }
+
     pub(crate) fn merge(
         &mut self,
         props: ImagePropertiesFile,
@@ -514,144 +581,381 @@ impl ImageProperties {
         self.translucent_layer_color = props
             .translucent_layer_color
             .unwrap_or(rw_read!(globals::CONFIG).color_layer);
+        self.text_color = props.text_color.unwrap_or([255, 255, 255]);
+    }
+}
+
+/// Size of exported image, after it is cropped and limited to maximum width
+pub(crate) fn export_dimension(prop: &ImageProperties) -> (f64, f64) {
+    limit_export_dimension(croped_ratio(
+        prop.original_dimension.0,
+        prop.original_dimension.1,
+    ))
+}
+
+/// Size of cropped image, made smaller if it is wider than maximum width
+fn limit_export_dimension((crop_width, crop_height): (f64, f64)) -> (f64, f64) {
+    let maximum_width = rw_read!(globals::CONFIG).maximum_width_limit;
+    if crop_width > maximum_width {
+        (maximum_width, height_from_width(maximum_width))
+    } else {
+        (crop_width, crop_height)
+    }
+}
+
+/// Crop original image, draw on it and encode it in export format
+fn export_image(prop: &ImageProperties) -> Result<Vec<u8>, Error> {
+    let image_info = prop.image_info.as_ref().ok_or(Error::NoImage)?;
+    let mut original_image = load_image(image_info)?;
+    let (width, height): (f64, f64) = Coord::from(original_image.dimensions()).into();
+    let (crop_width, crop_height) = croped_ratio(width, height);
+    let (crop_x, crop_y) = prop
+        .crop_position
+        .unwrap_or(((width - crop_width) / 2.0, (height - crop_height) / 2.0));
+    let img = render_export(
+        prop,
+        original_image.crop(
+            crop_x as u32,
+            crop_y as u32,
+            crop_width as u32,
+            crop_height as u32,
+        ),
+        &|| false,
+    )
+    .ok_or(Error::Stopped)?;
+
+    let (export_format, keep_copyright) = {
+        let config = rw_read!(globals::CONFIG);
+        (config.image_format.clone(), config.keep_copyright)
+    };
+    let profile = icc_profile::read(&image_info.path);
+    let copyright = if keep_copyright {
+        let exif = Exif::read(&image_info.path);
+        let artist = exif.as_ref().and_then(|exif| exif.artist());
+        let copyright = exif.as_ref().and_then(|exif| exif.copyright());
+        exif::copyright_segment(artist.as_deref(), copyright.as_deref())
+    } else {
+        None
+    };
+    match export_format {
+        ImageType::Png => {
+            let mut output = vec![];
+            let encoder = image::codecs::png::PngEncoder::new_with_quality(
+                &mut output,
+                image::codecs::png::CompressionType::Best,
+                image::codecs::png::FilterType::Sub,
+            );
+
+            let (w, h) = img.dimensions();
+            encoder
+                .write_image(&img.into_rgba8(), w, h, image::ColorType::Rgba8)
+                .map_err(|e| Error::Encode(format!("Failed to encode image: {}", e)))?;
+            if let Some(profile) = profile {
+                output = icc_profile::embed_in_png(output, &profile)?;
+            }
+            match copyright {
+                Some(segment) => exif::embed_in_png(output, &segment),
+                None => Ok(output),
+            }
+        }
+        ImageType::Jpeg => {
+            let (width, height) = Coord::from(img.dimensions()).into();
+            let buf = img.into_rgb8();
+
+            let mut comp = mozjpeg::Compress::new(mozjpeg::ColorSpace::JCS_RGB);
+
+            comp.set_size(width, height);
+            comp.set_quality(100.0);
+            comp.set_smoothing_factor(1);
+            comp.set_mem_dest();
+            comp.start_compress();
+
+            if let Some(segment) = copyright {
+                comp.write_marker(mozjpeg::Marker::APP(1), &segment);
+            }
+            for segment in profile
+                .iter()
+                .flat_map(|profile| icc_profile::jpeg_segments(profile))
+            {
+                comp.write_marker(mozjpeg::Marker::APP(2), &segment);
+            }
+
+            comp.write_scanlines(&buf);
+
+            comp.finish_compress();
+
+            comp.data_to_vec()
+                .map_err(|e| Error::Encode(format!("Failed to encode image: {:?}", e)))
+        }
+        ImageType::Webp
+        | ImageType::Tiff
+        | ImageType::Bmp
+        | ImageType::Gif
+        | ImageType::Avif
+        | ImageType::None => Err(Error::Encode(format!(
+            "Export as {} is not supported",
+            export_format.as_extension()
+        ))),
+    }
+}
+
+/// Resize cropped image to export size and draw layer and text on it
+fn render_export(
+    prop: &ImageProperties,
+    mut img: DynamicImage,
+    cancelled: &dyn Fn() -> bool,
+) -> Option<DynamicImage> {
+    let (crop_width, _) = croped_ratio(prop.original_dimension.0, prop.original_dimension.1);
+    if crop_width > rw_read!(globals::CONFIG).maximum_width_limit {
+        let (resize_width, resize_height) = export_dimension(prop);
+        img = img.resize_exact(
+            resize_width as u32,
+            resize_height as u32,
+            image::imageops::FilterType::Lanczos3,
+        );
+    }
+
+    match draw_layer_and_text(&mut img, prop, cancelled) {
+        true => Some(img),
+        false => None,
     }
 }
 
 /// Load image as Dynamic Image
-fn load_image(image_info: &ImageInfo) -> DynamicImage {
+pub(crate) fn load_image(image_info: &ImageInfo) -> Result<DynamicImage, Error> {
+    let open = || {
+        File::open(&image_info.path).map_err(|e| Error::Io("Failed to open image".to_owned(), e))
+    };
+    let read =
+        || fs::read(&image_info.path).map_err(|e| Error::Io("Failed to read image".to_owned(), e));
+    let decode_error =
+        |e: image::ImageError| Error::Decode(format!("Failed to decode image: {}", e));
     let img = match image_info.image_type {
         ImageType::Webp => {
-            let mut f = File::open(&image_info.path).expect_log("Failed to load image!");
-            let mut buf = vec![];
-            f.read_to_end(&mut buf).expect_log("Failed to read image!");
+            let buf = read()?;
             let a = webp::Decoder::new(&buf)
                 .decode()
-                .ok_or("")
-                .expect_log("Failed to decode image!");
+                .ok_or_else(|| Error::Decode("Failed to decode image".to_owned()))?;
             a.to_image()
         }
         ImageType::Jpeg => {
-            let mut f = File::open(&image_info.path).expect_log("Failed to load image!");
-            let mut buf = vec![];
-            f.read_to_end(&mut buf).expect_log("Failed to read image!");
-
-            let d = mozjpeg::Decompress::with_markers(mozjpeg::ALL_MARKERS)
-                .from_mem(&buf)
-                .expect_log("Failed to decompress image!");
-            let mut image = d.rgb().expect_log("Failed to convert to rgb image!");
-            let pixels = image.read_scanlines_flat().unwrap();
-            let image =
-                ImageBuffer::from_raw(image.width() as u32, image.height() as u32, pixels).unwrap();
-            DynamicImage::ImageRgb8(image)
+            let buf = read()?;
+            let image = decode_jpeg(&buf).map_err(Error::Decode)?;
+            // Phones save photos as taken and tell in EXIF how to turn them
+            let orientation = Exif::from_jpeg(&buf).map_or(1, |exif| exif.orientation());
+            exif::apply_orientation(DynamicImage::ImageRgb8(image), orientation)
         }
         ImageType::Png => {
-            let dec = image::codecs::png::PngDecoder::new(
-                File::open(&image_info.path).expect_log("Failed to load image!"),
-            )
-            .expect_log("Failed to decode image!");
-            DynamicImage::from_decoder(dec).expect_log("Failed to decode image!")
+            let dec = image::codecs::png::PngDecoder::new(open()?).map_err(decode_error)?;
+            DynamicImage::from_decoder(dec).map_err(decode_error)?
+        }
+        ImageType::Tiff => {
+            let dec = image::codecs::tiff::TiffDecoder::new(BufReader::new(open()?))
+                .map_err(decode_error)?;
+            DynamicImage::from_decoder(dec).map_err(decode_error)?
+        }
+        ImageType::Bmp => {
+            let dec = image::codecs::bmp::BmpDecoder::new(BufReader::new(open()?))
+                .map_err(decode_error)?;
+            DynamicImage::from_decoder(dec).map_err(decode_error)?
+        }
+        ImageType::Gif => {
+            // Decoder reads only first frame of animation
+            let dec = image::codecs::gif::GifDecoder::new(open()?).map_err(decode_error)?;
+            DynamicImage::from_decoder(dec).map_err(decode_error)?
+        }
+        #[cfg(feature = "avif")]
+        ImageType::Avif => {
+            let dec = image::codecs::avif::AvifDecoder::new(open()?).map_err(decode_error)?;
+            DynamicImage::from_decoder(dec).map_err(decode_error)?
+        }
+        #[cfg(not(feature = "avif"))]
+        ImageType::Avif => {
+            return Err(Error::Decode(
+                "AVIF can't be opened, program is built without AVIF support".to_owned(),
+            ))
         }
         ImageType::None => {
-            Result::<(), _>::Err("Failed to load image!").expect_log("Unknown format!");
-            std::process::exit(1);
+            return Err(Error::Decode("Format of image is not supported".to_owned()))
         }
     };
 
-    DynamicImage::ImageRgb8(img.into_rgb8())
+    Ok(DynamicImage::ImageRgb8(img.into_rgb8()))
 }
 
-/// Draw text and stuffs on image
-fn draw_layer_and_text(
-    tmp: &mut DynamicImage,
-    rgba: &[u8; 4],
-    quote: &str,
-    subquote: &str,
-    subquote2: &str,
-    quote_position: f64,
-    subquote_position: f64,
-    subquote2_position: f64,
-    tag: &str,
-    tag2: &str,
-    tag_y_position: f64,
-    tag2_position: f64,
-    original_width: f64,
-    original_height: f64,
-) {
-    let (width, height): (f64, f64) = Coord::from(tmp.dimensions()).into();
-    let layer =
-        DynamicImage::ImageRgba8(ImageBuffer::from_fn(width as u32, height as u32, |_, _| {
-            image::Rgba(rgba.to_owned())
-        }));
-    image::imageops::overlay(tmp, &layer, 0, 0);
+/// Decode JPEG with mozjpeg, which panics on errors of libjpeg like data which is not a JPEG
+fn decode_jpeg(buf: &[u8]) -> Result<image::RgbImage, String> {
+    panic::catch_unwind(|| {
+        let d = mozjpeg::Decompress::with_markers(mozjpeg::ALL_MARKERS)
+            .from_mem(buf)
+            .map_err(|e| format!("Failed to decompress image: {}", e))?;
+        let mut image = d
+            .rgb()
+            .map_err(|e| format!("Failed to convert to rgb image: {}", e))?;
+        let (width, height) = (image.width() as u32, image.height() as u32);
+        let pixels = image
+            .read_scanlines_flat()
+            .ok_or("Image data is incomplete")?;
+        ImageBuffer::from_raw(width, height, pixels)
+            .ok_or_else(|| "Image data is incomplete".to_owned())
+    })
+    .unwrap_or_else(|_| Err("Image is corrupt".to_owned()))
+}
 
-    let size = quote_from_height(height);
-    draw_multiline_mid_string(
-        tmp,
-        &globals::FONT_QUOTE,
-        size,
-        quote_position,
-        original_width,
-        original_height,
-        true,
-        quote,
-    );
+/// Steps of drawing on image, in the order they are drawn. Every step draws over the result
+/// of steps before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    Layer,
+    Quote,
+    Subquote,
+    Subquote2,
+    Tag2,
+    Tag,
+}
 
-    let size = subquote_from_height(height);
-    draw_multiline_mid_string(
-        tmp,
-        &globals::FONT_SUBQUOTE,
-        size,
-        subquote_position,
-        original_width,
-        original_height,
-        true,
-        subquote,
-    );
-    let size = subquote2_from_height(height);
-    draw_multiline_mid_string(
-        tmp,
-        &globals::FONT_SUBQUOTE2,
-        size,
-        subquote2_position,
-        original_width,
-        original_height,
-        true,
-        subquote2,
-    );
+const STAGES: [Stage; 6] = [
+    Stage::Layer,
+    Stage::Quote,
+    Stage::Subquote,
+    Stage::Subquote2,
+    Stage::Tag2,
+    Stage::Tag,
+];
+
+impl Stage {
+    /// Font, size, position and text drawn in stage, None for layer
+    fn text<'a>(
+        &self,
+        prop: &'a ImageProperties,
+        height: f64,
+    ) -> Option<(&'static rusttype::Font<'static>, f64, f64, &'a str)> {
+        match self {
+            Self::Layer => None,
+            Self::Quote => Some((
+                &*globals::FONT_QUOTE,
+                quote_from_height(height),
+                prop.quote_position,
+                &prop.quote,
+            )),
+            Self::Subquote => Some((
+                &*globals::FONT_SUBQUOTE,
+                subquote_from_height(height),
+                prop.subquote_position,
+                &prop.subquote,
+            )),
+            Self::Subquote2 => Some((
+                &*globals::FONT_SUBQUOTE2,
+                subquote2_from_height(height),
+                prop.subquote2_position,
+                &prop.subquote2,
+            )),
+            Self::Tag2 => Some((
+                &*globals::FONT_TAG2,
+                tag2_from_height(height),
+                prop.tag2_position,
+                &prop.tag2,
+            )),
+            Self::Tag => Some((
+                &*globals::FONT_TAG,
+                tag_from_height(height),
+                prop.tag_position,
+                &prop.tag,
+            )),
+        }
+    }
 
-    let size = tag2_from_height(height);
-    draw_multiline_mid_string(
-        tmp,
-        &globals::FONT_TAG2,
-        size,
-        tag2_position,
-        original_width,
-        original_height,
-        false,
-        tag2,
-    );
+    /// Hash of everything the stage draws with, chained with key of stage before it
+    fn key(&self, previous: u64, prop: &ImageProperties, height: f64) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        previous.hash(&mut hasher);
+        match self.text(prop, height) {
+            None => prop.translucent_layer_color.hash(&mut hasher),
+            Some((_, size, position, text)) => {
+                let config = rw_read!(globals::CONFIG);
+                config.draw_box_around_quote.hash(&mut hasher);
+                config.line_spacing.hash(&mut hasher);
+                if *self == Self::Tag {
+                    config.tag_x_position_ratio.to_bits().hash(&mut hasher);
+                }
+                drop(config);
 
-    let size = tag_from_height(height);
-    for (index, line) in tag.lines().enumerate() {
-        let (text_width, text_height) = measure_line(
-            &globals::FONT_TAG,
-            line,
-            rusttype::Scale::uniform(size as f32),
-        );
+                prop.text_color.hash(&mut hasher);
+                prop.original_dimension.1.to_bits().hash(&mut hasher);
+                size.to_bits().hash(&mut hasher);
+                position.to_bits().hash(&mut hasher);
+                text.hash(&mut hasher);
+            }
+        }
+        hasher.finish()
+    }
 
-        imageproc::drawing::draw_text_mut(
-            tmp,
-            image::Rgba([255, 255, 255, 255]),
-            (width * rw_read!(globals::CONFIG).tag_x_position_ratio - text_width) as i32,
-            ((tag_y_position * height) / original_height + index as f64 * (text_height * 1.2))
-                as i32,
-            rusttype::Scale::uniform(size as f32),
-            &globals::FONT_TAG,
-            line,
-        );
+    /// Draw this stage on image
+    fn draw(&self, tmp: &mut DynamicImage, prop: &ImageProperties) {
+        let text_rgb = &prop.text_color;
+        let (original_width, original_height) = prop.original_dimension;
+        let (width, height): (f64, f64) = Coord::from(tmp.dimensions()).into();
+        let (font, size, position, text) = match self.text(prop, height) {
+            Some(text) => text,
+            None => {
+                let layer = DynamicImage::ImageRgba8(ImageBuffer::from_fn(
+                    width as u32,
+                    height as u32,
+                    |_, _| image::Rgba(prop.translucent_layer_color),
+                ));
+                image::imageops::overlay(tmp, &layer, 0, 0);
+                return;
+            }
+        };
+
+        if *self != Self::Tag {
+            let boxed = *self != Self::Tag2;
+            draw_multiline_mid_string(
+                tmp,
+                font,
+                size,
+                position,
+                original_width,
+                original_height,
+                boxed,
+                text_rgb,
+                text,
+            );
+            return;
+        }
+
+        for (index, line) in text.lines().enumerate() {
+            let (text_width, text_height) =
+                measure_line(font, line, rusttype::Scale::uniform(size as f32));
+
+            imageproc::drawing::draw_text_mut(
+                tmp,
+                image::Rgba([text_rgb[0], text_rgb[1], text_rgb[2], 255]),
+                (width * rw_read!(globals::CONFIG).tag_x_position_ratio - text_width) as i32,
+                ((position * height) / original_height + index as f64 * (text_height * 1.2)) as i32,
+                rusttype::Scale::uniform(size as f32),
+                font,
+                line,
+            );
+        }
     }
 }
 
+/// Draw text and stuffs on image. Stops and returns false when `cancelled` gives true.
+fn draw_layer_and_text(
+    tmp: &mut DynamicImage,
+    prop: &ImageProperties,
+    cancelled: &dyn Fn() -> bool,
+) -> bool {
+    for stage in STAGES {
+        if cancelled() {
+            return false;
+        }
+        stage.draw(tmp, prop);
+    }
+    true
+}
+
 /// Draw multiline string on image
 pub(crate) fn draw_multiline_mid_string(
     tmp: &mut DynamicImage,
@@ -661,6 +965,7 @@ pub(crate) fn draw_multiline_mid_string(
     original_width: f64,
     original_height: f64,
     boxed: bool,
+    text_rgb: &[u8; 3],
     text: &str,
 ) {
     let (mut box_width, mut box_height) = (0.0, 0.0);
@@ -689,7 +994,7 @@ pub(crate) fn draw_multiline_mid_string(
         if !boxed || !rw_read!(globals::CONFIG).draw_box_around_quote {
             imageproc::drawing::draw_text_mut(
                 tmp,
-                image::Rgba([255, 255, 255, 100]),
+                image::Rgba([text_rgb[0], text_rgb[1], text_rgb[2], 100]),
                 x as i32,
                 y as i32,
                 rusttype::Scale::uniform(size as f32),
@@ -717,12 +1022,17 @@ pub(crate) fn draw_multiline_mid_string(
             original_width,
             original_height,
             false,
+            text_rgb,
             text,
         );
     }
 }
 
-/// Draws box around text.
+/// Colour of translucent box drawn around text
+pub(crate) const BOX_COLOR: [u8; 4] = [20, 22, 25, 80];
+
+/// Draws box around text. Sizes are relative to image, only blur of box is in pixels of
+/// exported image, so it is scaled for preview.
 fn draw_box(
     tmp: &mut DynamicImage,
     box_width: f64,
@@ -737,6 +1047,8 @@ fn draw_box(
 
     let (width, height): (f64, f64) = Coord::from(tmp.dimensions()).into();
     let (delta_x, delta_y) = (width / original_width, height / original_height);
+    let (_, export_height) = limit_export_dimension(croped_ratio(original_width, original_height));
+    let blur_scale = height / export_height;
 
     let (x_gap, y_gap) = (30.0 * delta_x, 10.0 * delta_y);
     let (x, y) = (
@@ -753,11 +1065,9 @@ fn draw_box(
     }
 
     let mut buff = tmp.crop(x, y, w, h);
-    let layer = DynamicImage::ImageRgba8(ImageBuffer::from_fn(w, h, |_, _| {
-        image::Rgba([20, 22, 25, 80])
-    }));
+    let layer = DynamicImage::ImageRgba8(ImageBuffer::from_fn(w, h, |_, _| image::Rgba(BOX_COLOR)));
     image::imageops::overlay(&mut buff, &layer, 0, 0);
-    buff = buff.blur(15.0);
+    buff = buff.blur((15.0 * blur_scale) as f32);
 
     let (dx, dy) = (20.0 * delta_x, 20.0 * delta_y);
     let mut shadow = DynamicImage::new_rgba8(w + (dx * 2.0) as u32, h + (dy * 2.0) as u32);
@@ -796,6 +1106,130 @@ fn draw_box(
     );
 }
 
+/// Text elements which are drawn on image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum TextElement {
+    Quote,
+    Subquote,
+    Subquote2,
+    Tag,
+    Tag2,
+}
+
+impl TextElement {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::Quote => "Quote",
+            Self::Subquote => "Subquote",
+            Self::Subquote2 => "Subquote 2",
+            Self::Tag => "Tag",
+            Self::Tag2 => "Tag 2",
+        }
+    }
+}
+
+/// Area covered by text element on image
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TextBlock {
+    pub(crate) element: TextElement,
+    pub(crate) x: f64,
+    pub(crate) y: f64,
+    pub(crate) width: f64,
+    pub(crate) height: f64,
+    /// Text is drawn inside the blurred box
+    pub(crate) boxed: bool,
+}
+
+/// Get areas covered by text elements on image of given size, same as they are drawn
+pub(crate) fn text_blocks(prop: &ImageProperties, width: f64, height: f64) -> Vec<TextBlock> {
+    let original_height = prop.original_dimension.1;
+    let config = rw_read!(globals::CONFIG);
+    let boxed = config.draw_box_around_quote;
+    let tag_x = width * config.tag_x_position_ratio;
+    drop(config);
+
+    let mut blocks = vec![];
+    let mid_strings = [
+        (
+            TextElement::Quote,
+            &*globals::FONT_QUOTE,
+            quote_from_height(height),
+            prop.quote_position,
+            &prop.quote,
+            boxed,
+        ),
+        (
+            TextElement::Subquote,
+            &*globals::FONT_SUBQUOTE,
+            subquote_from_height(height),
+            prop.subquote_position,
+            &prop.subquote,
+            boxed,
+        ),
+        (
+            TextElement::Subquote2,
+            &*globals::FONT_SUBQUOTE2,
+            subquote2_from_height(height),
+            prop.subquote2_position,
+            &prop.subquote2,
+            boxed,
+        ),
+        (
+            TextElement::Tag2,
+            &*globals::FONT_TAG2,
+            tag2_from_height(height),
+            prop.tag2_position,
+            &prop.tag2,
+            false,
+        ),
+    ];
+
+    for (element, font, size, position, text, boxed) in mid_strings {
+        let (text_width, text_height, lines) = measure_block(font, size, text);
+        if lines == 0 {
+            continue;
+        }
+        blocks.push(TextBlock {
+            element,
+            x: (width - text_width) / 2.0,
+            y: (position * height) / original_height,
+            width: text_width,
+            height: text_height * (1.0 + (lines - 1) as f64 * 1.12),
+            boxed,
+        });
+    }
+
+    let (text_width, text_height, lines) =
+        measure_block(&globals::FONT_TAG, tag_from_height(height), &prop.tag);
+    if lines > 0 {
+        blocks.push(TextBlock {
+            element: TextElement::Tag,
+            x: tag_x - text_width,
+            y: (prop.tag_position * height) / original_height,
+            width: text_width,
+            height: text_height * (1.0 + (lines - 1) as f64 * 1.2),
+            boxed: false,
+        });
+    }
+
+    blocks
+}
+
+/// Get widest line, line height and count of lines of multiline text
+pub(crate) fn measure_block(font: &rusttype::Font, size: f64, text: &str) -> (f64, f64, usize) {
+    let (mut block_width, mut line_height, mut lines) = (0.0, 0.0, 0);
+    for line in text.lines() {
+        let (text_width, text_height) =
+            measure_line(font, line, rusttype::Scale::uniform(size as f32));
+        if text_width > block_width {
+            block_width = text_width;
+        }
+        line_height = text_height;
+        lines += 1;
+    }
+    (block_width, line_height, lines)
+}
+
 /// Get size of text to draw on image
 pub(crate) fn measure_line(
     font: &rusttype::Font,
@@ -814,8 +1248,8 @@ pub(crate) fn measure_line(
     Coord::from((width, height)).into()
 }
 
-/// path of properties files
-pub(crate) fn get_properties_path(image_info: &ImageInfo) -> PathBuf {
+/// Path of properties file of image, `name-ext.prop`
+pub(crate) fn properties_file_path(image_info: &ImageInfo) -> PathBuf {
     let img = &image_info.path;
 
     let image_name: String = image_info
@@ -842,8 +1276,13 @@ pub(crate) fn get_properties_path(image_info: &ImageInfo) -> PathBuf {
         .into_iter()
         .rev();
     let image_name = format!("{}.prop", String::from_iter(image_name));
+    img.with_file_name(image_name)
+}
 
-    let default_path = img.with_file_name(&image_name);
+/// path of properties files, deprecated file of image is renamed if there is one
+pub(crate) fn get_properties_path(image_info: &ImageInfo) -> PathBuf {
+    let img = &image_info.path;
+    let default_path = properties_file_path(image_info);
 
     if default_path.exists() {
         return default_path;
@@ -861,46 +1300,293 @@ pub(crate) fn get_properties_path(image_info: &ImageInfo) -> PathBuf {
     default_path
 }
 
-/// path of properties files
-pub(crate) fn get_export_image_path(image_info: &ImageInfo, name_prefix: &str) -> PathBuf {
-    let config = rw_read!(globals::CONFIG);
-    let export_format = &config.image_format;
-    let image_name = image_info
-        .path
-        .file_name()
-        .unwrap_or_default()
-        .to_string_lossy();
+/// Copy image and its properties, if it has any
+pub(crate) fn copy_image(image_info: &ImageInfo, new_image_info: &ImageInfo) -> Result<(), Error> {
+    fs::copy(&image_info.path, &new_image_info.path)
+        .map_err(|e| Error::Io(format!("Failed to copy {}", image_info.path.display()), e))?;
 
-    let mut occurance = 0;
-    let image_name = image_name
-        .chars()
-        .into_iter()
-        .rev()
-        .map(|c| {
-            if occurance == 0 && c == '.' {
-                occurance = 1;
-                '-'
-            } else {
-                c
+    properties_store::copy(image_info, new_image_info)
+        .warn_log("Failed to clone image properties!");
+    Ok(())
+}
+
+/// Folder in which exported images of image are saved
+pub(crate) fn get_export_dir(image_path: &Path) -> PathBuf {
+    folder_export_dir(image_path.parent().unwrap_or_else(|| Path::new("")))
+}
+
+/// Folder in which exported images of images in folder are saved
+pub(crate) fn folder_export_dir(folder: &Path) -> PathBuf {
+    let project_dir = rw_read!(globals::PROJECT)
+        .as_ref()
+        .and_then(|(path, project)| project.export_folder(path));
+    if let Some(project_dir) = project_dir {
+        return project_dir;
+    }
+    let export_dir = rw_read!(globals::CONFIG).export_dir.clone();
+    let export_dir = match export_dir.trim() {
+        "" => PathBuf::from("export"),
+        dir => PathBuf::from(dir),
+    };
+    if export_dir.is_absolute() {
+        export_dir
+    } else {
+        folder.join(export_dir)
+    }
+}
+
+/// Files and subfolders of folder sorted by name. Hidden folders, symlinked folders and export
+/// folder are left out
+pub(crate) fn folder_entries(folder: &Path) -> std::io::Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+    let mut entries = fs::read_dir(folder)?
+        .filter_map(|r| r.ok())
+        .collect::<Vec<fs::DirEntry>>();
+    entries.sort_by_key(|i| i.file_name());
+    let export_dir = folder_export_dir(folder);
+    let mut files = vec![];
+    let mut folders = vec![];
+    for entry in entries {
+        let path = entry.path();
+        // Symlinks are not followed, they can make loops
+        if entry.file_type().is_ok_and(|ty| ty.is_dir()) {
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            if !hidden && path != export_dir {
+                folders.push(path);
             }
+            continue;
+        }
+        files.push(path);
+    }
+    Ok((files, folders))
+}
+
+/// Deepest folder which has all images
+pub(crate) fn common_folder(images: &[ImageInfo]) -> PathBuf {
+    let mut folders = images.iter().filter_map(|image| image.path.parent());
+    let mut common = match folders.next() {
+        Some(folder) => folder.to_path_buf(),
+        None => return PathBuf::new(),
+    };
+    for folder in folders {
+        while !folder.starts_with(&common) {
+            if !common.pop() {
+                return PathBuf::new();
+            }
+        }
+    }
+    common
+}
+
+/// Label of image in file choice, its path from `root` with `/` and `&` escaped for menu
+pub(crate) fn image_label(image: &ImageInfo, root: &Path) -> String {
+    let path = image.path.strip_prefix(root).unwrap_or(&image.path);
+    path.components()
+        .map(|c| {
+            c.as_os_str()
+                .to_string_lossy()
+                .replace('\\', "\\\\")
+                .replace('/', "\\/")
+                .replace('&', "&&")
         })
-        .collect::<Vec<char>>()
-        .into_iter()
-        .rev();
+        .collect::<Vec<String>>()
+        .join("\\/")
+}
+
+/// Path of exported image, as per filename template and export folder in config. `index` is
+/// position of image in loaded images, from 1. Export folder is not made
+pub(crate) fn get_export_image_path(
+    image_info: &ImageInfo,
+    props: &ImagePropertiesFile,
+    index: usize,
+) -> PathBuf {
+    let (template, export_format) = {
+        let config = rw_read!(globals::CONFIG);
+        (config.export_filename.clone(), config.image_format.clone())
+    };
     let image_name = format!(
-        "{}{}.{}",
-        name_prefix,
-        String::from_iter(image_name),
+        "{}.{}",
+        export_file_name(image_info, props, &template, index),
         export_format.as_extension()
     );
 
-    let expost_dir = image_info.path.parent().unwrap().join("export");
-    if !expost_dir.exists() {
-        fs::create_dir(&expost_dir).expect_log("Failed to create export folder!");
+    get_export_dir(&image_info.path).join(&image_name)
+}
+
+/// Fill placeholders of filename template:
+/// `{prefix}` name prefix, `{name}` file name with `-` in place of `.` before extension,
+/// `{stem}` file name without extension, `{ext}` extension, `{index}` position in loaded images,
+/// `{date}` today as YYYY-MM-DD, `{config}` name of config, `{width}` and `{height}` of exported image
+/// and `{quote}` first words of quote
+pub(crate) fn export_file_name(
+    image_info: &ImageInfo,
+    props: &ImagePropertiesFile,
+    template: &str,
+    index: usize,
+) -> String {
+    let path = &image_info.path;
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = path.extension().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(_) => format!("{}-{}", stem, ext),
+        None => file_name.to_string(),
+    };
+
+    let mut out = template
+        .replace("{prefix}", props.name_prefix.as_deref().unwrap_or_default())
+        .replace("{name}", &name)
+        .replace("{stem}", &stem)
+        .replace("{ext}", &ext)
+        .replace("{config}", &rw_read!(globals::CONFIG_NAME))
+        .replace(
+            "{quote}",
+            &slug(props.quote.as_deref().unwrap_or_default(), 5),
+        );
+    if out.contains("{index}") {
+        out = out.replace("{index}", &index.to_string());
+    }
+    if out.contains("{date}") {
+        out = out.replace("{date}", &today());
+    }
+    if out.contains("{width}") || out.contains("{height}") {
+        let (width, height) = exif::upright_dimensions(path)
+            .map(|(w, h)| limit_export_dimension(croped_ratio(w as f64, h as f64)))
+            .unwrap_or_default();
+        out = out
+            .replace("{width}", &(width as u32).to_string())
+            .replace("{height}", &(height as u32).to_string());
+    }
+
+    // Characters not allowed in file names
+    let out: String = out
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            c => c,
+        })
+        .collect();
+    let out = out.trim();
+    if out.is_empty() {
+        name
+    } else {
+        out.to_owned()
+    }
+}
+
+/// Write exported image made by `data` to `path`, or path picked by `collision` if it exists.
+/// `own` is earlier export of same image, it is replaced and is not a collision. Returns path
+/// which is written, `None` if it is skipped.
+///
+/// New file is made before image is drawn, so other threads exporting at same time see its name
+/// as taken
+pub(crate) fn write_export(
+    path: &Path,
+    own: Option<&Path>,
+    collision: ExportCollision,
+    data: impl FnOnce() -> Result<Vec<u8>, Error>,
+) -> Result<Option<PathBuf>, Error> {
+    let export_error = |e| Error::Io("Failed to export Image".to_owned(), e);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| Error::Io("Failed to create export folder".to_owned(), e))?;
+    }
+
+    let replaced = match own {
+        Some(own) if own == path || is_suffixed(own, path) => Some(own),
+        _ if collision == ExportCollision::Overwrite => Some(path),
+        _ => None,
+    };
+    if let Some(replaced) = replaced {
+        fs::write(replaced, data()?).map_err(export_error)?;
+        return Ok(Some(replaced.to_path_buf()));
+    }
+
+    let mut candidate = path.to_path_buf();
+    let mut i = 2;
+    let mut file = loop {
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&candidate)
+        {
+            Ok(file) => break file,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                if collision == ExportCollision::Skip {
+                    return Ok(None);
+                }
+                candidate = suffixed_path(path, i);
+                i += 1;
+            }
+            Err(e) => return Err(export_error(e)),
+        }
+    };
+    let written = data().and_then(|data| file.write_all(&data).map_err(export_error));
+    if written.is_err() {
+        fs::remove_file(&candidate).warn_log("Failed to delete unfinished export!");
     }
+    written.map(|_| Some(candidate))
+}
+
+/// `path` with `-i` added to file name
+fn suffixed_path(path: &Path, i: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    match path.extension() {
+        Some(ext) => path.with_file_name(format!("{}-{}.{}", stem, i, ext.to_string_lossy())),
+        None => path.with_file_name(format!("{}-{}", stem, i)),
+    }
+}
+
+/// `path` is `original` with `-2`, `-3`, ... added to file name
+fn is_suffixed(path: &Path, original: &Path) -> bool {
+    let stem = original.file_stem().unwrap_or_default().to_string_lossy();
+    let number = match path.file_stem().and_then(|s| s.to_str()) {
+        Some(s) => s.strip_prefix(&*stem).and_then(|s| s.strip_prefix('-')),
+        None => None,
+    };
+    path.parent() == original.parent()
+        && path.extension() == original.extension()
+        && number.is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
 
-    let export = expost_dir.join(&image_name);
-    export
+/// Path with `-2`, `-3`, ... added to file name, which does not exist yet
+pub(crate) fn free_path(path: &Path) -> PathBuf {
+    (2..)
+        .map(|i| suffixed_path(path, i))
+        .find(|path| !path.exists())
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// First words of text in lowercase, joined with `-`
+fn slug(text: &str, words: usize) -> String {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .take(words)
+        .collect::<Vec<String>>()
+        .join("-")
+}
+
+/// Today in UTC as YYYY-MM-DD
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() / 86400) as i64;
+    // Days to civil date, from http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
 }
 
 /// small hack because 0,0,0 rgb, because can't be set on fltk theme
@@ -982,9 +1668,210 @@ pub(crate) fn show_alert(msg: &str) {
     }
 }
 
-pub(crate) fn show_program_panic(msg: &str) {
-    let a = rw_read!(globals::MAIN_SENDER);
-    if let Some(a) = &*a {
-        a.send(crate::AppMessage::ProgramPanicMessage(msg.to_owned()));
+/// Empty folder in temporary folder of system, unique to test `name`
+#[cfg(test)]
+pub(crate) fn temp_folder(name: &str) -> PathBuf {
+    let folder =
+        std::env::temp_dir().join(format!("post_maker_test_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(&folder).unwrap();
+    folder
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn props(prefix: &str, quote: &str) -> ImagePropertiesFile {
+        ImagePropertiesFile {
+            name_prefix: Some(prefix.to_owned()),
+            quote: Some(quote.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    fn file_name(path: &str, props: &ImagePropertiesFile, template: &str) -> String {
+        let image = ImageInfo {
+            path: PathBuf::from(path),
+            image_type: ImageType::Jpeg,
+        };
+        export_file_name(&image, props, template, 7)
+    }
+
+    #[test]
+    fn file_name_placeholders() {
+        let props = props("pre_", "  Hello, World! It's a   fine day today.");
+        let name = |template| file_name("/photos/beach.day.jpg", &props, template);
+        assert_eq!(name("{prefix}{name}"), "pre_beach.day-jpg");
+        assert_eq!(name("{stem}.{ext}"), "beach.day.jpg");
+        assert_eq!(name("{index}-{quote}"), "7-hello-world-its-a-fine");
+        assert_eq!(name("plain"), "plain");
+        // Unknown placeholders are kept
+        assert_eq!(name("{other}"), "{other}");
+
+        let date = name("{date}");
+        assert_eq!(date.len(), 10);
+        assert!(date.chars().enumerate().all(|(i, c)| if i == 4 || i == 7 {
+            c == '-'
+        } else {
+            c.is_ascii_digit()
+        }));
+    }
+
+    #[test]
+    fn file_name_without_extension() {
+        let props = ImagePropertiesFile::default();
+        assert_eq!(file_name("/photos/scan", &props, "{name}"), "scan");
+        assert_eq!(file_name("/photos/scan", &props, "{prefix}{ext}"), "scan");
+    }
+
+    #[test]
+    fn file_name_is_made_safe() {
+        let props = props("a/b\\c:", "");
+        assert_eq!(
+            file_name("/photos/x.png", &props, "{prefix}*?\"<>|"),
+            "a-b-c-------"
+        );
+        // Name is used when template gives nothing
+        assert_eq!(file_name("/photos/x.png", &props, "  {quote}  "), "x-png");
+    }
+
+    #[test]
+    fn suffixes() {
+        let path = Path::new("/export/photo.jpg");
+        assert_eq!(suffixed_path(path, 2), Path::new("/export/photo-2.jpg"));
+        assert_eq!(
+            suffixed_path(Path::new("/export/photo"), 3),
+            Path::new("/export/photo-3")
+        );
+        assert!(is_suffixed(Path::new("/export/photo-2.jpg"), path));
+        assert!(is_suffixed(Path::new("/export/photo-12.jpg"), path));
+        assert!(!is_suffixed(path, path));
+        assert!(!is_suffixed(Path::new("/export/photo-.jpg"), path));
+        assert!(!is_suffixed(Path::new("/export/photo-2a.jpg"), path));
+        assert!(!is_suffixed(Path::new("/export/photo-2.png"), path));
+        assert!(!is_suffixed(Path::new("/other/photo-2.jpg"), path));
+    }
+
+    fn write(
+        path: &Path,
+        own: Option<&Path>,
+        collision: ExportCollision,
+        data: &str,
+    ) -> Option<PathBuf> {
+        write_export(path, own, collision, || Ok(data.as_bytes().to_vec())).unwrap()
+    }
+
+    fn read(path: &Path) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn collision_overwrite() {
+        let folder = temp_folder("collision_overwrite");
+        let path = folder.join("export").join("photo.jpg");
+        assert_eq!(
+            write(&path, None, ExportCollision::Overwrite, "one"),
+            Some(path.clone())
+        );
+        assert_eq!(
+            write(&path, None, ExportCollision::Overwrite, "two"),
+            Some(path.clone())
+        );
+        assert_eq!(read(&path), "two");
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn collision_skip() {
+        let folder = temp_folder("collision_skip");
+        let path = folder.join("photo.jpg");
+        fs::write(&path, "other").unwrap();
+        let rendered = std::cell::Cell::new(false);
+        let written = write_export(&path, None, ExportCollision::Skip, || {
+            rendered.set(true);
+            Ok(vec![])
+        })
+        .unwrap();
+        assert_eq!(written, None);
+        // Image is not drawn when it is skipped
+        assert!(!rendered.get());
+        assert_eq!(read(&path), "other");
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn collision_suffix() {
+        let folder = temp_folder("collision_suffix");
+        let path = folder.join("photo.jpg");
+        fs::write(&path, "other").unwrap();
+        let second = folder.join("photo-2.jpg");
+        assert_eq!(
+            write(&path, None, ExportCollision::Suffix, "a"),
+            Some(second.clone())
+        );
+        assert_eq!(
+            write(&path, None, ExportCollision::Suffix, "b"),
+            Some(folder.join("photo-3.jpg"))
+        );
+        assert_eq!(read(&path), "other");
+        assert_eq!(read(&second), "a");
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn own_export_is_replaced() {
+        let folder = temp_folder("own_export_is_replaced");
+        let path = folder.join("photo.jpg");
+        let second = folder.join("photo-2.jpg");
+        fs::write(&path, "other").unwrap();
+        fs::write(&second, "old").unwrap();
+        for collision in [ExportCollision::Suffix, ExportCollision::Skip] {
+            assert_eq!(
+                write(&path, Some(&second), collision, "new"),
+                Some(second.clone())
+            );
+        }
+        assert_eq!(read(&second), "new");
+        assert_eq!(read(&path), "other");
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[test]
+    fn failed_export_leaves_no_file() {
+        let folder = temp_folder("failed_export_leaves_no_file");
+        let path = folder.join("photo.jpg");
+        let written = write_export(&path, None, ExportCollision::Suffix, || {
+            Err(Error::Encode("Broken".to_owned()))
+        });
+        assert!(written.is_err());
+        assert!(!path.exists());
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[cfg(feature = "avif")]
+    #[test]
+    fn load_avif() {
+        let info = ImageInfo {
+            path: Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/tests/red_blue.avif"),
+            image_type: ImageType::from_mime("image/avif"),
+        };
+        let img = load_image(&info).unwrap().into_rgb8();
+        assert_eq!(img.dimensions(), (16, 8));
+        // Left half is red and right half blue, with some loss from encoding
+        let left = img.get_pixel(2, 4);
+        let right = img.get_pixel(13, 4);
+        assert!(left[0] > 200 && left[2] < 60, "{:?}", left);
+        assert!(right[2] > 200 && right[0] < 60, "{:?}", right);
+    }
+
+    #[cfg(not(feature = "avif"))]
+    #[test]
+    fn avif_unsupported() {
+        assert_eq!(unsupported_format("image/avif"), Some("AVIF"));
+        assert!(matches!(
+            ImageType::from_mime("image/avif"),
+            ImageType::None
+        ));
     }
 }